@@ -0,0 +1,28 @@
+pub mod utils {
+    pub mod plot;
+}
+
+pub mod physics {
+    pub mod barnes_hut;
+    pub mod constants;
+    pub mod gravity;
+    pub mod harmonic_oscillator;
+    pub mod particle;
+}
+
+pub mod math {
+    pub mod assignment;
+    pub mod complex;
+    pub mod convolution;
+    pub mod core;
+    pub mod dct;
+    pub mod fft;
+    pub mod field;
+    pub mod finite_difference;
+    pub mod grid;
+    pub mod integrate;
+    pub mod interpolation;
+    pub mod matrix;
+    pub mod random;
+    pub mod spectral;
+}
@@ -1,27 +1,7 @@
-mod utils {
-    pub mod plot;
-}
-
-mod physics {
-    pub mod constants;
-    pub mod harmonic_oscillator;
-    pub mod gravity;
-    pub mod particle;
-}
-
-mod math {
-    pub mod complex;
-    pub mod core;
-    pub mod fft;
-    pub mod field;
-    pub mod integrate;
-    pub mod matrix;
-}
-
-use crate::math::integrate::{LeapfrogMethod, Solver};
-use crate::utils::plot::plot_one;
+use fbi::math::integrate::{LeapfrogMethod, Solver};
+use fbi::utils::plot::plot_one;
 
-use crate::physics::harmonic_oscillator::DrivenHarmonicOscillator;
+use fbi::physics::harmonic_oscillator::DrivenHarmonicOscillator;
 
 fn main() {
     let dho_ode = DrivenHarmonicOscillator {
@@ -0,0 +1,257 @@
+use crate::math::grid::{Domain, Grid};
+
+/// Particle-mesh assignment scheme
+///
+/// The same cloud spreads mass onto the mesh and gathers mesh forces back to
+/// the particle. With an antisymmetric gradient (central or spectral) the
+/// self-force then cancels and pair forces obey Newton's third law.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Assignment {
+    /// Nearest grid point, 1 sample per axis
+    NGP,
+    /// Cloud in cell, 2 samples per axis, continuous
+    CIC,
+    /// Triangular-shaped cloud, 3 samples per axis, continuous first derivative
+    TSC,
+}
+
+impl Assignment {
+    /// 1D weights at `s` in units of the spacing, as (wrapped index, weight)
+    fn kernel(&self, s: f64, n: usize) -> Vec<(usize, f64)> {
+        let wrap = |i: f64| (i as isize).rem_euclid(n as isize) as usize;
+        match self {
+            Assignment::NGP => vec![(wrap(s.round()), 1.0)],
+            Assignment::CIC => {
+                let base = s.floor();
+                let t = s - base;
+                vec![(wrap(base), 1.0 - t), (wrap(base + 1.0), t)]
+            }
+            Assignment::TSC => {
+                let nearest = s.round();
+                let d = s - nearest;
+                vec![
+                    (wrap(nearest - 1.0), 0.5 * (0.5 - d).powi(2)),
+                    (wrap(nearest), 0.75 - d * d),
+                    (wrap(nearest + 1.0), 0.5 * (0.5 + d).powi(2)),
+                ]
+            }
+        }
+    }
+
+    /// First and last sample of the 1D kernel at `s`, before wrapping
+    fn span(&self, s: f64) -> (f64, f64) {
+        match self {
+            Assignment::NGP => (s.round(), s.round()),
+            Assignment::CIC => (s.floor(), s.floor() + 1.0),
+            Assignment::TSC => (s.round() - 1.0, s.round() + 1.0),
+        }
+    }
+
+    /// Whether the cloud at `position` lies on the grid without wrapping around,
+    /// as a non-periodic mesh needs
+    pub fn fits(&self, domain: &Domain, shape: &[usize], position: &[f64]) -> bool {
+        let lengths = domain.lengths();
+        (0..shape.len()).all(|axis| {
+            let s = (position[axis] - domain.min()[axis]) * shape[axis] as f64 / lengths[axis];
+            let (first, last) = self.span(s);
+            first >= 0.0 && last <= (shape[axis] - 1) as f64
+        })
+    }
+
+    /// Mesh points of a periodic grid around `position`, with weights summing to 1
+    ///
+    /// Sample `i` of an axis sits at $$ min + i \Delta x $$ and positions outside
+    /// the domain wrap around.
+    pub fn cloud(
+        &self,
+        domain: &Domain,
+        shape: &[usize],
+        position: &[f64],
+    ) -> Vec<(Vec<usize>, f64)> {
+        if position.len() != shape.len() || domain.ndim() != shape.len() {
+            panic!("Position {position:?} on a grid of shape {shape:?}");
+        }
+        let lengths = domain.lengths();
+        (0..shape.len()).fold(vec![(Vec::new(), 1.0)], |cloud, axis| {
+            let s = (position[axis] - domain.min()[axis]) * shape[axis] as f64 / lengths[axis];
+            let kernel = self.kernel(s, shape[axis]);
+            cloud
+                .iter()
+                .flat_map(|(index, weight)| {
+                    kernel.iter().map(move |&(i, w)| {
+                        let mut index = index.clone();
+                        index.push(i);
+                        (index, weight * w)
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// Adds the density $$ m W(x_i - x) / \Delta V $$ of a particle
+    pub fn deposit(&self, density: &mut Grid<f64>, position: &[f64], mass: f64) {
+        let cell_volume = density.cell_volume();
+        for (index, weight) in self.cloud(density.domain(), density.shape(), position) {
+            let offset = density.offset(&index);
+            density.as_mut_slice()[offset] += mass * weight / cell_volume;
+        }
+    }
+
+    /// Density of point masses, as (mass, position)
+    pub fn density(
+        &self,
+        shape: &[usize],
+        domain: &Domain,
+        particles: &[(f64, &[f64])],
+    ) -> Grid<f64> {
+        let mut density = Grid::new(shape).with_domain(domain.clone());
+        for &(mass, position) in particles {
+            self.deposit(&mut density, position, mass);
+        }
+        density
+    }
+
+    /// Gathers a mesh quantity at `position` with the assignment weights
+    pub fn interpolate(&self, field: &Grid<f64>, position: &[f64]) -> f64 {
+        self.cloud(field.domain(), field.shape(), position)
+            .into_iter()
+            .map(|(index, weight)| weight * field.as_slice()[field.offset(&index)])
+            .sum()
+    }
+
+    /// Gathers one value per component, e.g. the mesh acceleration
+    pub fn interpolate_vector(&self, components: &[Grid<f64>], position: &[f64]) -> Vec<f64> {
+        components
+            .iter()
+            .map(|component| self.interpolate(component, position))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::complex::{Complex, ComplexSpace};
+    use crate::math::field::Field;
+    use crate::math::random::Rng;
+
+    use super::*;
+
+    const SCHEMES: [Assignment; 3] = [Assignment::NGP, Assignment::CIC, Assignment::TSC];
+
+    fn random_position(rng: &mut Rng, domain: &Domain) -> Vec<f64> {
+        // Spills over the domain on both sides to exercise the wrapping
+        domain
+            .min()
+            .iter()
+            .zip(domain.lengths())
+            .map(|(min, length)| min + length * (3.0 * rng.uniform() - 1.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_fits() {
+        let domain = Domain::new(&[-1.0, 0.0], &[1.0, 8.0]);
+        let fits = |scheme: Assignment, x: f64| scheme.fits(&domain, &[8, 8], &[x, 4.0]);
+        // Samples at -1, -0.75, ..., 0.75
+        assert!(fits(Assignment::NGP, -1.1) && fits(Assignment::NGP, 0.8));
+        assert!(!fits(Assignment::NGP, -1.2) && !fits(Assignment::NGP, 0.9));
+        assert!(fits(Assignment::CIC, -1.0) && fits(Assignment::CIC, 0.7));
+        assert!(!fits(Assignment::CIC, -1.05) && !fits(Assignment::CIC, 0.75));
+        assert!(fits(Assignment::TSC, -0.85) && fits(Assignment::TSC, 0.6));
+        assert!(!fits(Assignment::TSC, -0.9) && !fits(Assignment::TSC, 0.65));
+        assert!(!Assignment::CIC.fits(&domain, &[8, 8], &[0.0, 7.5]));
+    }
+
+    #[test]
+    fn test_kernels() {
+        let domain = Domain::new(&[0.0], &[8.0]);
+        let cloud = |scheme: Assignment, x: f64| scheme.cloud(&domain, &[8], &[x]);
+        assert_eq!(cloud(Assignment::NGP, 2.6), vec![(vec![3], 1.0)]);
+        assert_eq!(
+            cloud(Assignment::CIC, 7.25),
+            vec![(vec![7], 0.75), (vec![0], 0.25)]
+        );
+        assert_eq!(
+            cloud(Assignment::TSC, -0.25),
+            vec![(vec![7], 0.28125), (vec![0], 0.6875), (vec![1], 0.03125)]
+        );
+        // On a mesh point TSC keeps 3/4 there
+        assert_eq!(cloud(Assignment::TSC, 3.0)[1], (vec![3], 0.75));
+    }
+
+    #[test]
+    fn test_mass_conservation() {
+        let mut rng = Rng::new(22);
+        let domain = Domain::new(&[-1.0, 0.0, 2.0], &[1.0, 3.0, 3.0]);
+        let shape = [12, 16, 10];
+        let particles: Vec<(f64, Vec<f64>)> = (0..500)
+            .map(|_| (rng.uniform() + 0.5, random_position(&mut rng, &domain)))
+            .collect();
+        let total: f64 = particles.iter().map(|(m, _)| m).sum();
+        for scheme in SCHEMES {
+            let points: Vec<(f64, &[f64])> =
+                particles.iter().map(|(m, x)| (*m, x.as_slice())).collect();
+            let density = scheme.density(&shape, &domain, &points);
+            let mass: f64 = density.as_slice().iter().sum::<f64>() * density.cell_volume();
+            assert!(
+                (mass - total).abs() < 1e-10 * total,
+                "{scheme:?}: {mass} != {total}"
+            );
+            assert!(density.as_slice().iter().all(|&rho| rho >= 0.0));
+        }
+    }
+
+    #[test]
+    fn test_interpolation_reproduces_linear_field() {
+        // CIC and TSC gather a linear mesh field exactly away from the wrap
+        let domain = Domain::cube(2, 8.0);
+        let field = Grid::from_fn(&[8, 8], |i| 2.0 * i[0] as f64 - i[1] as f64).with_domain(domain);
+        for scheme in [Assignment::CIC, Assignment::TSC] {
+            let value = scheme.interpolate(&field, &[3.3, 4.6]);
+            assert!((value - (6.6 - 4.6)).abs() < 1e-12, "{scheme:?}");
+        }
+        assert_eq!(Assignment::NGP.interpolate(&field, &[3.3, 4.6]), 1.0);
+    }
+
+    /// Mesh acceleration $$ -\nabla \Phi $$ with $$ \nabla^2 \Phi = \rho $$, spectral
+    fn acceleration(density: &Grid<f64>) -> Vec<Grid<f64>> {
+        let mut potential = density.map(|rho| Complex::new(rho, 0.0));
+        potential.inverse_laplacian();
+        potential
+            .gradient()
+            .iter()
+            .map(|g| g.map(|g| -g.re()))
+            .collect()
+    }
+
+    #[test]
+    fn test_self_force_and_momentum() {
+        let mut rng = Rng::new(23);
+        let domain = Domain::cube(3, 1.0);
+        let shape = [16, 16, 16];
+        for scheme in SCHEMES {
+            let x = random_position(&mut rng, &domain);
+            let y = random_position(&mut rng, &domain);
+
+            let alone = acceleration(&scheme.density(&shape, &domain, &[(1.0, &x)]));
+            let force = scheme.interpolate_vector(&alone, &x);
+            assert!(
+                force.iter().all(|f| f.abs() < 1e-10),
+                "{scheme:?}: {force:?}"
+            );
+
+            let (mx, my) = (1.0, 3.0);
+            let pair = acceleration(&scheme.density(&shape, &domain, &[(mx, &x), (my, &y)]));
+            let (ax, ay) = (
+                scheme.interpolate_vector(&pair, &x),
+                scheme.interpolate_vector(&pair, &y),
+            );
+            for axis in 0..3 {
+                let momentum = mx * ax[axis] + my * ay[axis];
+                assert!(momentum.abs() < 1e-10, "{scheme:?}: {momentum}");
+            }
+            assert!(ax.iter().any(|a| a.abs() > 1e-3));
+        }
+    }
+}
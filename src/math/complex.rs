@@ -27,7 +27,7 @@ pub struct Complex {
 
 impl Complex {
     pub fn new(re: f64, im: f64) -> Self {
-        Self { re: re, im: im }
+        Self { re, im }
     }
 }
 
@@ -49,6 +49,18 @@ impl LinearSpace for Complex {
     fn get_data(&self) -> Self::Data {
         [self.re, self.im]
     }
+    fn dim() -> usize {
+        2
+    }
+    fn components(&self) -> Vec<f64> {
+        vec![self.re, self.im]
+    }
+    fn from_components(components: &[f64]) -> Self {
+        Self {
+            re: components[0],
+            im: components[1],
+        }
+    }
 }
 
 impl ScalarSpace for Complex {
@@ -71,7 +83,7 @@ impl ScalarSpace for Complex {
         Self::from_polar(self.abs().powi(n), self.phase() * (n as f64))
     }
     fn powf(&self, n: f64) -> Self {
-        Self::from_polar(self.abs().powf(n), self.phase() * (n as f64))
+        Self::from_polar(self.abs().powf(n), self.phase() * n)
     }
 }
 
@@ -88,7 +100,7 @@ impl ComplexSpace for Complex {
         }
     }
     fn from_real(re: Self::Real) -> Self {
-        Self { re: re, im: 0.0 }
+        Self { re, im: 0.0 }
     }
     fn from_polar(r: Self::Real, phase: Self::Real) -> Self {
         Self {
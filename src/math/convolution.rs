@@ -0,0 +1,287 @@
+use crate::math::{
+    complex::Complex,
+    core::{LinearSpace, ScalarSpace, Vector},
+    fft::{FftPlan, RealFftPlan, next_fast_len},
+};
+
+/// Linear convolution $$ (a * b)_j = \sum_i a_i b_{j-i} $$ of length M + N - 1
+///
+/// Zero-padded to a fast FFT length, so no wrap-around occurs.
+pub fn convolve(a: &[Complex], b: &[Complex]) -> Vec<Complex> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let len = a.len() + b.len() - 1;
+    let plan = FftPlan::new(next_fast_len(len));
+    let mut a = padded(a, plan.len());
+    let mut b = padded(b, plan.len());
+    plan.forward(&mut a);
+    plan.forward(&mut b);
+    for (a, b) in a.iter_mut().zip(&b) {
+        *a = *a * *b;
+    }
+    plan.inverse(&mut a);
+    a.truncate(len);
+    a
+}
+
+/// Circular convolution $$ (a \circledast b)_j = \sum_i a_i b_{(j-i) \bmod N} $$
+pub fn circular_convolve(a: &[Complex], b: &[Complex]) -> Vec<Complex> {
+    check_lengths(a.len(), b.len());
+    let plan = FftPlan::new(a.len());
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+    plan.forward(&mut a);
+    plan.forward(&mut b);
+    for (a, b) in a.iter_mut().zip(&b) {
+        *a = *a * *b;
+    }
+    plan.inverse(&mut a);
+    a
+}
+
+/// Linear cross-correlation $$ c_k = \sum_n \bar{a}_n b_{n+k} $$
+///
+/// Lags run from $$ -(M-1) $$ to $$ N-1 $$, so lag k is at index k + M - 1.
+pub fn correlate(a: &[Complex], b: &[Complex]) -> Vec<Complex> {
+    let reversed: Vec<Complex> = a.iter().rev().map(|a| a.conj()).collect();
+    convolve(&reversed, b)
+}
+
+/// Circular cross-correlation $$ c_k = \sum_n \bar{a}_n b_{(n+k) \bmod N} $$
+pub fn circular_correlate(a: &[Complex], b: &[Complex]) -> Vec<Complex> {
+    check_lengths(a.len(), b.len());
+    let plan = FftPlan::new(a.len());
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+    plan.forward(&mut a);
+    plan.forward(&mut b);
+    for (a, b) in a.iter_mut().zip(&b) {
+        *a = a.conj() * *b;
+    }
+    plan.inverse(&mut a);
+    a
+}
+
+/// Linear convolution of real sequences with real FFTs
+pub fn convolve_real(a: &[f64], b: &[f64]) -> Vec<f64> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let len = a.len() + b.len() - 1;
+    let plan = RealFftPlan::new(next_fast_len(len));
+    let mut spectrum = real_spectrum(&plan, a);
+    for (x, y) in spectrum.iter_mut().zip(real_spectrum(&plan, b)) {
+        *x = *x * y;
+    }
+    let mut c = vec![0.0; plan.len()];
+    plan.inverse(&spectrum, &mut c);
+    c.truncate(len);
+    c
+}
+
+/// Linear cross-correlation of real sequences, lags as in `correlate`
+pub fn correlate_real(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let reversed: Vec<f64> = a.iter().rev().copied().collect();
+    convolve_real(&reversed, b)
+}
+
+/// Unbiased autocorrelation $$ R_k = \frac{1}{N-k} \sum_{n=0}^{N-1-k} x_n x_{n+k} $$
+/// for lags k = 0..=max_lag
+///
+/// With a velocity series, $$ D = \int_0^\infty R(t) dt $$ is the Green-Kubo
+/// diffusion constant per component.
+pub fn autocorrelation(x: &[f64], max_lag: usize) -> Vec<f64> {
+    let n = x.len();
+    if max_lag >= n {
+        panic!("Lag {max_lag} needs more than {n} samples");
+    }
+    let plan = RealFftPlan::new(next_fast_len(n + max_lag));
+    let mut spectrum = real_spectrum(&plan, x);
+    for x in spectrum.iter_mut() {
+        *x = Complex::new(x.abs_sq(), 0.0);
+    }
+    let mut r = vec![0.0; plan.len()];
+    plan.inverse(&spectrum, &mut r);
+    r.truncate(max_lag + 1);
+    for (k, r) in r.iter_mut().enumerate() {
+        *r /= (n - k) as f64;
+    }
+    r
+}
+
+/// Circular convolution of fixed-size vectors
+pub fn convolve1d<const N: usize>(
+    a: Vector<Complex, N>,
+    b: Vector<Complex, N>,
+) -> Vector<Complex, N> {
+    let c = circular_convolve(a.as_slice(), b.as_slice());
+    Vector::new(std::array::from_fn(|j| c[j]))
+}
+
+/// Circular cross-correlation of fixed-size vectors
+pub fn correlate1d<const N: usize>(
+    a: Vector<Complex, N>,
+    b: Vector<Complex, N>,
+) -> Vector<Complex, N> {
+    let c = circular_correlate(a.as_slice(), b.as_slice());
+    Vector::new(std::array::from_fn(|j| c[j]))
+}
+
+fn padded(x: &[Complex], len: usize) -> Vec<Complex> {
+    let mut padded = x.to_vec();
+    padded.resize(len, Complex::zero());
+    padded
+}
+
+fn real_spectrum(plan: &RealFftPlan, x: &[f64]) -> Vec<Complex> {
+    let mut padded = x.to_vec();
+    padded.resize(plan.len(), 0.0);
+    let mut spectrum = vec![Complex::zero(); plan.spectrum_len()];
+    plan.forward(&padded, &mut spectrum);
+    spectrum
+}
+
+fn check_lengths(m: usize, n: usize) {
+    if m != n {
+        panic!("Circular convolution needs equal lengths, got {m} and {n}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::integrate::stochastic::{
+        EulerMaruyamaMethod, StochasticSolver, StochasticSystem,
+    };
+    use crate::math::random::Rng;
+
+    use super::*;
+
+    fn random(rng: &mut Rng, n: usize) -> Vec<Complex> {
+        (0..n).map(|_| rng.normal_vector()).collect()
+    }
+
+    fn direct_convolve(a: &[Complex], b: &[Complex]) -> Vec<Complex> {
+        (0..a.len() + b.len() - 1)
+            .map(|j| {
+                (0..a.len())
+                    .filter(|&i| j >= i && j - i < b.len())
+                    .fold(Complex::zero(), |acc, i| acc + a[i] * b[j - i])
+            })
+            .collect()
+    }
+
+    fn assert_close(a: &[Complex], b: &[Complex]) {
+        assert_eq!(a.len(), b.len());
+        for (j, (a, b)) in a.iter().zip(b).enumerate() {
+            assert!((*a - *b).abs() < 1e-12, "index {j}: {a:?} != {b:?}");
+        }
+    }
+
+    #[test]
+    fn test_convolve() {
+        let mut rng = Rng::new(16);
+        for (m, n) in [(1, 1), (13, 7), (64, 64), (5, 100)] {
+            let (a, b) = (random(&mut rng, m), random(&mut rng, n));
+            assert_close(&convolve(&a, &b), &direct_convolve(&a, &b));
+        }
+        assert!(convolve(&[], &[Complex::one()]).is_empty());
+    }
+
+    #[test]
+    fn test_correlate() {
+        let mut rng = Rng::new(17);
+        let (a, b) = (random(&mut rng, 6), random(&mut rng, 11));
+        let c = correlate(&a, &b);
+        assert_eq!(c.len(), 16);
+        for (index, c) in c.iter().enumerate() {
+            let lag = index as isize - 5;
+            let expected = (0..6)
+                .filter(|&n| (0..11).contains(&(n + lag)))
+                .fold(Complex::zero(), |acc, n| {
+                    acc + a[n as usize].conj() * b[(n + lag) as usize]
+                });
+            assert!((*c - expected).abs() < 1e-12, "lag {lag}");
+        }
+    }
+
+    #[test]
+    fn test_circular() {
+        const N: usize = 12;
+        let mut rng = Rng::new(18);
+        let a: Vector<Complex, N> = Vector::new(std::array::from_fn(|_| rng.normal_vector()));
+        let b: Vector<Complex, N> = Vector::new(std::array::from_fn(|_| rng.normal_vector()));
+
+        let convolution = convolve1d(a, b);
+        let correlation = correlate1d(a, b);
+        for k in 0..N {
+            let (mut expected_convolution, mut expected_correlation) =
+                (Complex::zero(), Complex::zero());
+            for n in 0..N {
+                expected_convolution = expected_convolution + a[n] * b[(k + N - n) % N];
+                expected_correlation = expected_correlation + a[n].conj() * b[(n + k) % N];
+            }
+            assert!((convolution[k] - expected_convolution).abs() < 1e-12);
+            assert!((correlation[k] - expected_correlation).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_real() {
+        let mut rng = Rng::new(19);
+        let a: Vec<f64> = (0..9).map(|_| rng.normal()).collect();
+        let b: Vec<f64> = (0..20).map(|_| rng.normal()).collect();
+        let complex =
+            |x: &[f64]| -> Vec<Complex> { x.iter().map(|&x| Complex::new(x, 0.0)).collect() };
+
+        let expected = direct_convolve(&complex(&a), &complex(&b));
+        assert_close(&complex(&convolve_real(&a, &b)), &expected);
+        let expected = correlate(&complex(&a), &complex(&b));
+        assert_close(&complex(&correlate_real(&a, &b)), &expected);
+    }
+
+    #[test]
+    fn test_autocorrelation() {
+        let mut rng = Rng::new(20);
+        let x: Vec<f64> = (0..50).map(|_| rng.normal()).collect();
+        let r = autocorrelation(&x, 49);
+        for k in 0..50 {
+            let expected = (0..50 - k).map(|n| x[n] * x[n + k]).sum::<f64>() / (50 - k) as f64;
+            assert!((r[k] - expected).abs() < 1e-12, "lag {k}");
+        }
+    }
+
+    /// dv = -gamma v dt + sqrt(2 gamma T) dW
+    struct TestOrnsteinUhlenbeck {
+        gamma: f64,
+        temperature: f64,
+    }
+
+    impl StochasticSystem for TestOrnsteinUhlenbeck {
+        type Vector = f64;
+
+        fn drift(&self, _t: f64, v: Self::Vector) -> Self::Vector {
+            -self.gamma * v
+        }
+
+        fn diffusion(&self, _t: f64, _v: Self::Vector) -> Self::Vector {
+            (2.0 * self.gamma * self.temperature).sqrt()
+        }
+    }
+
+    #[test]
+    fn test_green_kubo_diffusion() {
+        // D = T / gamma for Brownian motion
+        let system = TestOrnsteinUhlenbeck {
+            gamma: 2.0,
+            temperature: 0.5,
+        };
+        let dt = 0.01;
+        let mut test_solver = StochasticSolver::new(EulerMaruyamaMethod::new(21), system, 0.0);
+        test_solver.run(dt, 1_000_000);
+        let (_, velocities, _) = test_solver.get_results();
+
+        let r = autocorrelation(&velocities, 300);
+        let diffusion = (r.iter().sum::<f64>() - 0.5 * (r[0] + r[300])) * dt;
+        assert!((r[0] - 0.5).abs() < 0.05, "<v^2> = {}", r[0]);
+        assert!((diffusion - 0.25).abs() < 0.025, "D = {diffusion}");
+    }
+}
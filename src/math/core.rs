@@ -18,6 +18,13 @@ pub trait LinearSpace:
     fn zero() -> Self;
     fn size(&self) -> usize;
     fn get_data(&self) -> Self::Data;
+
+    /// Number of real components (`f64`) in the flattened representation
+    fn dim() -> usize;
+    /// Flattens into real components
+    fn components(&self) -> Vec<f64>;
+    /// Rebuilds from real components, the inverse of `components`
+    fn from_components(components: &[f64]) -> Self;
 }
 
 pub trait ScalarSpace: LinearSpace + Mul<Output = Self> + Div<Output = Self> {
@@ -61,6 +68,9 @@ impl<L: LinearSpace, const N: usize> Vector<L, N> {
     pub fn as_slice(&self) -> &[L] {
         &self.data
     }
+    pub fn as_mut_slice(&mut self) -> &mut [L] {
+        &mut self.data
+    }
 }
 
 impl<L: LinearSpace, const N: usize> AsRef<[L; N]> for Vector<L, N> {
@@ -90,6 +100,15 @@ impl LinearSpace for f64 {
     fn size(&self) -> usize {
         1
     }
+    fn dim() -> usize {
+        1
+    }
+    fn components(&self) -> Vec<f64> {
+        vec![*self]
+    }
+    fn from_components(components: &[f64]) -> Self {
+        components[0]
+    }
 }
 
 impl ScalarSpace for f64 {
@@ -117,7 +136,7 @@ impl<L: LinearSpace, const N: usize> LinearSpace for Vector<L, N> {
     type Data = [L; N];
 
     fn new(data: Self::Data) -> Self {
-        Self { data: data }
+        Self { data }
     }
     fn zero() -> Self {
         // Self::new([L::zero(); N])
@@ -129,6 +148,18 @@ impl<L: LinearSpace, const N: usize> LinearSpace for Vector<L, N> {
     fn get_data(&self) -> Self::Data {
         self.data
     }
+    fn dim() -> usize {
+        N * L::dim()
+    }
+    fn components(&self) -> Vec<f64> {
+        self.data.iter().flat_map(|e| e.components()).collect()
+    }
+    fn from_components(components: &[f64]) -> Self {
+        let d = L::dim();
+        Self::new(std::array::from_fn(|i| {
+            L::from_components(&components[i * d..(i + 1) * d])
+        }))
+    }
 }
 
 impl<S: ScalarSpace, const N: usize> VectorSpace<S, N> for Vector<S, N> {
@@ -359,4 +390,25 @@ mod tests {
 
         assert_eq!(e2.cross(e2), Vector::zero(), "Vector Self Outer Product");
     }
+
+    #[test]
+    fn test_components_round_trip() {
+        let v = Vector::new([Vector::new([1.0, 2.0]), Vector::new([3.0, 4.0])]);
+
+        assert_eq!(
+            Vector::<Vector<f64, 2>, 2>::dim(),
+            4,
+            "Nested Vector::dim()"
+        );
+        assert_eq!(
+            v.components(),
+            vec![1.0, 2.0, 3.0, 4.0],
+            "Vector.components()"
+        );
+        assert_eq!(
+            Vector::from_components(&v.components()),
+            v,
+            "Vector::from_components()"
+        );
+    }
 }
@@ -0,0 +1,261 @@
+use std::f64::consts::PI;
+
+use crate::math::{
+    complex::{Complex, ComplexSpace},
+    core::{LinearSpace, ScalarSpace},
+    fft::{FftPlan, rfft},
+};
+
+/// Type of a discrete cosine or sine transform
+///
+/// Unnormalized as in FFTW: type I is its own inverse up to a factor, and
+/// types II and III are each other's inverses up to a factor.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    I,
+    II,
+    III,
+}
+
+/// DCT of the given type
+pub fn dct(x: &[f64], kind: Kind) -> Vec<f64> {
+    match kind {
+        Kind::I => dct1(x),
+        Kind::II => dct2(x),
+        Kind::III => dct3(x),
+    }
+}
+
+/// Inverse of `dct(x, kind)`
+pub fn idct(x: &[f64], kind: Kind) -> Vec<f64> {
+    let n = x.len() as f64;
+    match kind {
+        Kind::I => scale(dct1(x), 1.0 / (2.0 * (n - 1.0))),
+        Kind::II => scale(dct3(x), 1.0 / (2.0 * n)),
+        Kind::III => scale(dct2(x), 1.0 / (2.0 * n)),
+    }
+}
+
+/// DST of the given type
+pub fn dst(x: &[f64], kind: Kind) -> Vec<f64> {
+    match kind {
+        Kind::I => dst1(x),
+        Kind::II => dst2(x),
+        Kind::III => dst3(x),
+    }
+}
+
+/// Inverse of `dst(x, kind)`
+pub fn idst(x: &[f64], kind: Kind) -> Vec<f64> {
+    let n = x.len() as f64;
+    match kind {
+        Kind::I => scale(dst1(x), 1.0 / (2.0 * (n + 1.0))),
+        Kind::II => scale(dst3(x), 1.0 / (2.0 * n)),
+        Kind::III => scale(dst2(x), 1.0 / (2.0 * n)),
+    }
+}
+
+fn scale(mut x: Vec<f64>, factor: f64) -> Vec<f64> {
+    for x in x.iter_mut() {
+        *x *= factor;
+    }
+    x
+}
+
+/// DCT-I, Neumann boundaries on the grid points
+///
+/// $$ Y_k = x_0 + (-1)^k x_{N-1} + 2 \sum_{j=1}^{N-2} x_j \cos \frac{\pi j k}{N-1} $$
+/// from the real FFT of the even extension of length 2(N - 1).
+pub fn dct1(x: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    if n < 2 {
+        panic!("DCT-I needs at least 2 samples, got {n}");
+    }
+    let extended: Vec<f64> = x.iter().chain(x[1..n - 1].iter().rev()).copied().collect();
+    rfft(&extended).iter().map(|y| y.re()).collect()
+}
+
+/// DCT-II, Neumann boundaries halfway between the grid points
+///
+/// $$ Y_k = 2 \sum_{j=0}^{N-1} x_j \cos \frac{\pi (j + 1/2) k}{N} $$
+/// from the real FFT of the even extension of length 2N.
+pub fn dct2(x: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    let extended: Vec<f64> = x.iter().chain(x.iter().rev()).copied().collect();
+    let spectrum = rfft(&extended);
+    (0..n)
+        .map(|k| (Complex::cis(-PI * k as f64 / (2.0 * n as f64)) * spectrum[k]).re())
+        .collect()
+}
+
+/// DCT-III, the inverse of DCT-II up to 2N
+///
+/// $$ Y_k = x_0 + 2 \sum_{j=1}^{N-1} x_j \cos \frac{\pi j (k + 1/2)}{N} $$
+pub fn dct3(x: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    // Re of sum_j a_j e^{+2 pi i j k / 2N} with a_j = c_j x_j e^{i pi j / 2N}
+    let mut a = vec![Complex::zero(); 2 * n];
+    for j in 0..n {
+        let weight = if j == 0 { 1.0 } else { 2.0 };
+        a[j] = (Complex::cis(PI * j as f64 / (2.0 * n as f64)) * x[j] * weight).conj();
+    }
+    FftPlan::new(2 * n).forward(&mut a);
+    a[..n].iter().map(|y| y.re()).collect()
+}
+
+/// DST-I, Dirichlet boundaries one step outside the grid points
+///
+/// $$ Y_k = 2 \sum_{j=0}^{N-1} x_j \sin \frac{\pi (j + 1)(k + 1)}{N + 1} $$
+/// from the real FFT of the odd extension of length 2(N + 1).
+pub fn dst1(x: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    let extended: Vec<f64> = std::iter::once(0.0)
+        .chain(x.iter().copied())
+        .chain(std::iter::once(0.0))
+        .chain(x.iter().rev().map(|x| -x))
+        .collect();
+    let spectrum = rfft(&extended);
+    (0..n).map(|k| -spectrum[k + 1].im()).collect()
+}
+
+/// DST-II, Dirichlet boundaries halfway outside the grid points
+///
+/// $$ Y_k = 2 \sum_{j=0}^{N-1} x_j \sin \frac{\pi (j + 1/2)(k + 1)}{N} $$
+/// from the real FFT of the odd extension of length 2N.
+pub fn dst2(x: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    let extended: Vec<f64> = x
+        .iter()
+        .copied()
+        .chain(x.iter().rev().map(|x| -x))
+        .collect();
+    let spectrum = rfft(&extended);
+    (0..n)
+        .map(|k| {
+            let phase = Complex::cis(-PI * (k + 1) as f64 / (2.0 * n as f64));
+            (Complex::i() * phase * spectrum[k + 1]).re()
+        })
+        .collect()
+}
+
+/// DST-III, the inverse of DST-II up to 2N
+///
+/// $$ Y_k = (-1)^k x_{N-1} + 2 \sum_{j=0}^{N-2} x_j \sin \frac{\pi (j + 1)(k + 1/2)}{N} $$
+pub fn dst3(x: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    // Im of sum_m b_m e^{+2 pi i m k / 2N} with b_m = c_m x_{m-1} e^{i pi m / 2N}
+    let mut b = vec![Complex::zero(); 2 * n];
+    for m in 1..=n {
+        let weight = if m == n { 1.0 } else { 2.0 };
+        b[m] = (Complex::cis(PI * m as f64 / (2.0 * n as f64)) * x[m - 1] * weight).conj();
+    }
+    FftPlan::new(2 * n).forward(&mut b);
+    b[..n].iter().map(|y| -y.im()).collect()
+}
+
+/// Applies a real 1D transform along every axis of row-major complex `data`,
+/// to the real and imaginary parts separately
+pub fn map_axes(data: &mut [Complex], shape: &[usize], transform: &dyn Fn(&[f64]) -> Vec<f64>) {
+    if shape.iter().product::<usize>() != data.len() {
+        panic!("Shape {shape:?} does not match {} values", data.len());
+    }
+    for axis in 0..shape.len() {
+        let n = shape[axis];
+        let stride: usize = shape[axis + 1..].iter().product();
+        for block in data.chunks_mut(n * stride) {
+            for offset in 0..stride {
+                let line: Vec<Complex> = (0..n).map(|j| block[offset + j * stride]).collect();
+                let re: Vec<f64> = line.iter().map(|z| z.re()).collect();
+                let im: Vec<f64> = line.iter().map(|z| z.im()).collect();
+                for (j, (re, im)) in transform(&re).into_iter().zip(transform(&im)).enumerate() {
+                    block[offset + j * stride] = Complex::new(re, im);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::random::Rng;
+
+    use super::*;
+
+    /// Direct O(N^2) sums of the FFTW definitions
+    fn direct(x: &[f64], cosine: bool, kind: Kind) -> Vec<f64> {
+        let n = x.len();
+        let nf = n as f64;
+        (0..n)
+            .map(|k| {
+                let kf = k as f64;
+                (0..n)
+                    .map(|j| {
+                        let jf = j as f64;
+                        match (cosine, kind) {
+                            (true, Kind::I) => {
+                                let weight = if j == 0 || j == n - 1 { 1.0 } else { 2.0 };
+                                weight * x[j] * (PI * jf * kf / (nf - 1.0)).cos()
+                            }
+                            (true, Kind::II) => 2.0 * x[j] * (PI * (jf + 0.5) * kf / nf).cos(),
+                            (true, Kind::III) => {
+                                let weight = if j == 0 { 1.0 } else { 2.0 };
+                                weight * x[j] * (PI * jf * (kf + 0.5) / nf).cos()
+                            }
+                            (false, Kind::I) => {
+                                2.0 * x[j] * (PI * (jf + 1.0) * (kf + 1.0) / (nf + 1.0)).sin()
+                            }
+                            (false, Kind::II) => {
+                                2.0 * x[j] * (PI * (jf + 0.5) * (kf + 1.0) / nf).sin()
+                            }
+                            (false, Kind::III) => {
+                                let weight = if j == n - 1 { 1.0 } else { 2.0 };
+                                weight * x[j] * (PI * (jf + 1.0) * (kf + 0.5) / nf).sin()
+                            }
+                        }
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_against_direct_sums() {
+        let mut rng = Rng::new(14);
+        for n in [2, 3, 5, 8, 13, 64] {
+            let x: Vec<f64> = (0..n).map(|_| rng.normal()).collect();
+            for kind in [Kind::I, Kind::II, Kind::III] {
+                for (cosine, output) in [(true, dct(&x, kind)), (false, dst(&x, kind))] {
+                    let expected = direct(&x, cosine, kind);
+                    for k in 0..n {
+                        assert!(
+                            (output[k] - expected[k]).abs() < 1e-11,
+                            "{} {kind:?}, N = {n}, k = {k}",
+                            if cosine { "DCT" } else { "DST" }
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_inverse() {
+        let mut rng = Rng::new(15);
+        for n in [2, 7, 16, 100] {
+            let x: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+            for kind in [Kind::I, Kind::II, Kind::III] {
+                let restored = idct(&dct(&x, kind), kind);
+                assert!(restored.iter().zip(&x).all(|(a, b)| (a - b).abs() < 1e-12));
+                let restored = idst(&dst(&x, kind), kind);
+                assert!(restored.iter().zip(&x).all(|(a, b)| (a - b).abs() < 1e-12));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "DCT-I needs at least 2 samples")]
+    fn test_dct1_too_short() {
+        dct1(&[1.0]);
+    }
+}
@@ -6,130 +6,539 @@ use crate::math::{
 };
 
 pub fn dft1d<const N: usize>(x_n: Vector<Complex, N>) -> Vector<Complex, N> {
-    if N == 0 || (N & (N - 1)) != 0 {
-        panic!("DFT length N must be a power of 2"); // TODO: Implement zero padding
-    }
     Vector::new(std::array::from_fn(|k| {
         (0..N).fold(Complex::zero(), |acc, n| {
-            acc + Complex::cis(-2.0 * PI * (k as f64) * (n as f64) / (N as f64)) * x_n[n]
+            acc + Complex::cis(-2.0 * PI * ((k * n % N) as f64) / (N as f64)) * x_n[n]
         })
     }))
 }
 
 pub fn idft1d<const N: usize>(x_k: Vector<Complex, N>) -> Vector<Complex, N> {
-    if N == 0 || (N & (N - 1)) != 0 {
-        panic!("IDFT length N must be a power of 2"); // TODO: Implement zero padding
-    }
     Vector::new(std::array::from_fn(|n| {
         (0..N).fold(Complex::zero(), |acc, k| {
-            acc + Complex::cis(2.0 * PI * (k as f64) * (n as f64) / (N as f64)) * x_k[k]
+            acc + Complex::cis(2.0 * PI * ((k * n % N) as f64) / (N as f64)) * x_k[k]
         }) / (N as f64)
     }))
 }
 
-/// FFT with Cooley-Tukey algorithm
+/// FFT of any length N
 ///
-/// using bit reverse for Radix-2 DIT divides
+/// Radix-2 for powers of 2, mixed-radix Cooley-Tukey for $$ N = 2^a 3^b 5^c 7^d $$
+/// and Bluestein's chirp-z algorithm otherwise
 pub fn fft1d<const N: usize>(x_n: &mut Vector<Complex, N>) {
-    if N == 0 || (N & (N - 1)) != 0 {
-        panic!("FFT length N must be a power of 2"); // TODO: Implement zero padding
+    FftPlan::new(N).forward(x_n.as_mut_slice());
+}
+
+/// IFFT of any length N, normalized by 1/N
+pub fn ifft1d<const N: usize>(x_k: &mut Vector<Complex, N>) {
+    FftPlan::new(N).inverse(x_k.as_mut_slice());
+}
+
+/// In-place FFT of a slice of any length
+pub fn fft(x_n: &mut [Complex]) {
+    FftPlan::new(x_n.len()).forward(x_n);
+}
+
+/// In-place IFFT of a slice of any length, normalized by 1/N
+pub fn ifft(x_k: &mut [Complex]) {
+    FftPlan::new(x_k.len()).inverse(x_k);
+}
+
+/// Precomputed FFT of a fixed length N
+///
+/// Twiddle factors $$ e^{-2 \pi i k / N} $$ are evaluated directly instead of by
+/// repeated multiplication, and the bit-reversal permutation is tabulated once.
+/// Reuse one plan for the many equal-length lines of a multi-dimensional transform.
+pub struct FftPlan {
+    n: usize,
+    algorithm: Algorithm,
+}
+
+enum Algorithm {
+    Trivial,
+    Radix2 {
+        permutation: Vec<usize>,
+        twiddles: Vec<Complex>,
+    },
+    MixedRadix {
+        factors: Vec<usize>,
+        twiddles: Vec<Complex>,
+    },
+    Bluestein {
+        chirp: Vec<Complex>,
+        kernel: Vec<Complex>,
+        inner: Box<FftPlan>,
+    },
+}
+
+impl FftPlan {
+    pub fn new(n: usize) -> Self {
+        let algorithm = if n <= 1 {
+            Algorithm::Trivial
+        } else if n.is_power_of_two() {
+            Algorithm::Radix2 {
+                permutation: bit_reversal(n),
+                twiddles: twiddles(n, n / 2),
+            }
+        } else {
+            let factors = factorize(n);
+            if factors.iter().all(|&p| p <= 7) {
+                Algorithm::MixedRadix {
+                    factors,
+                    twiddles: twiddles(n, n),
+                }
+            } else {
+                bluestein(n)
+            }
+        };
+        Self { n, algorithm }
     }
-    // Radix-2 DIT divides using bit reverse sort
-    // 반대방향 bit 덧셈
-    let mut j = 0; // j: 000 부터 시작
-    for i in 1..N {
-        let mut bit = N >> 1; // bit: 100 부터 시작
-        while j & bit != 0 {
-            // j의 bit 자리가 1 이라면
-            j ^= bit; // j의 bit 자리를 1에서 0으로 변경
-            bit >>= 1; // bit 자리가 오른쪽으로 한칸 이동
+
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// In-place FFT $$ X_k = \sum_n x_n e^{-2 \pi i k n / N} $$
+    pub fn forward(&self, x: &mut [Complex]) {
+        if x.len() != self.n {
+            panic!(
+                "FftPlan of length {} applied to {} samples",
+                self.n,
+                x.len()
+            );
         }
-        j ^= bit; // j의 bit 자리를 0에서 1로 변경
+        match &self.algorithm {
+            Algorithm::Trivial => {}
+            Algorithm::Radix2 {
+                permutation,
+                twiddles,
+            } => radix2(x, permutation, twiddles),
+            Algorithm::MixedRadix { factors, twiddles } => {
+                let input = x.to_vec();
+                decimate(&input, 1, x, factors, twiddles, 1);
+            }
+            Algorithm::Bluestein {
+                chirp,
+                kernel,
+                inner,
+            } => {
+                let mut a = vec![Complex::zero(); inner.len()];
+                for j in 0..self.n {
+                    a[j] = x[j] * chirp[j];
+                }
+                inner.forward(&mut a);
+                for j in 0..a.len() {
+                    a[j] = a[j] * kernel[j];
+                }
+                inner.inverse(&mut a);
+                for k in 0..self.n {
+                    x[k] = chirp[k] * a[k];
+                }
+            }
+        }
+    }
 
-        if i < j {
-            // Index swap x_n.swap(i, j)
-            let temp = x_n[i];
-            x_n[i] = x_n[j];
-            x_n[j] = temp;
+    /// In-place IFFT, normalized by 1/N
+    ///
+    /// $$ \mathrm{IFFT}(x) = \overline{\mathrm{FFT}(\bar{x})} / N $$
+    pub fn inverse(&self, x: &mut [Complex]) {
+        for z in x.iter_mut() {
+            *z = z.conj();
+        }
+        self.forward(x);
+        let inv_n = 1.0 / (self.n as f64);
+        for z in x.iter_mut() {
+            *z = z.conj() * inv_n;
         }
     }
-    // Butterfly diagram
-    let mut len = 2;
-    while len <= N {
-        let w_step = Complex::cis(-2.0 * PI / (len as f64)); // twiddle factor
-        for i in (0..N).step_by(len) {
-            let mut w = Complex::one(); // twiddle factor
-            for j in 0..(len / 2) {
-                let u = x_n[i + j]; // even
-                let v = x_n[i + j + len / 2] * w; // odd
+}
 
-                // 1D DFT
-                x_n[i + j] = u + v;
-                x_n[i + j + len / 2] = u - v;
+/// r2c FFT of a real slice: the Hermitian half-spectrum $$ X_0, \dots, X_{N/2} $$
+pub fn rfft(x: &[f64]) -> Vec<Complex> {
+    let plan = RealFftPlan::new(x.len());
+    let mut spectrum = vec![Complex::zero(); plan.spectrum_len()];
+    plan.forward(x, &mut spectrum);
+    spectrum
+}
+
+/// c2r IFFT of a half-spectrum back to `n` real samples, normalized by 1/N
+pub fn irfft(spectrum: &[Complex], n: usize) -> Vec<f64> {
+    let plan = RealFftPlan::new(n);
+    let mut x = vec![0.0; n];
+    plan.inverse(spectrum, &mut x);
+    x
+}
 
-                w = w * w_step;
+/// Multi-dimensional r2c FFT of row-major `real` data with the given `shape`
+///
+/// The half-spectrum is row-major with the last axis shortened to $$ N_{last}/2 + 1 $$.
+pub fn rfftn(real: &[f64], shape: &[usize]) -> Vec<Complex> {
+    let (n_last, half_shape) = half_shape(shape, real.len());
+    let half = half_shape[half_shape.len() - 1];
+    let plan = RealFftPlan::new(n_last);
+    let mut spectrum = vec![Complex::zero(); real.len() / n_last * half];
+    for (row, out) in real.chunks(n_last).zip(spectrum.chunks_mut(half)) {
+        plan.forward(row, out);
+    }
+    for (axis, &n) in shape[..shape.len() - 1].iter().enumerate() {
+        fft_axis(&mut spectrum, &half_shape, axis, &FftPlan::new(n), false);
+    }
+    spectrum
+}
+
+/// Inverse of `rfftn`, normalized by the number of samples
+pub fn irfftn(spectrum: &[Complex], shape: &[usize]) -> Vec<f64> {
+    let len = shape.iter().product();
+    let (n_last, half_shape) = half_shape(shape, len);
+    let half = half_shape[half_shape.len() - 1];
+    if spectrum.len() != len / n_last * half {
+        panic!(
+            "Half-spectrum of shape {half_shape:?} expected, got {} values",
+            spectrum.len()
+        );
+    }
+    let mut spectrum = spectrum.to_vec();
+    for (axis, &n) in shape[..shape.len() - 1].iter().enumerate() {
+        fft_axis(&mut spectrum, &half_shape, axis, &FftPlan::new(n), true);
+    }
+    let plan = RealFftPlan::new(n_last);
+    let mut real = vec![0.0; len];
+    for (row, out) in spectrum.chunks(half).zip(real.chunks_mut(n_last)) {
+        plan.inverse(row, out);
+    }
+    real
+}
+
+/// Last axis length and the half-spectrum shape
+fn half_shape(shape: &[usize], len: usize) -> (usize, Vec<usize>) {
+    let Some(&n_last) = shape.last() else {
+        panic!("Real FFT needs at least one axis");
+    };
+    if shape.iter().product::<usize>() != len {
+        panic!("Shape {shape:?} does not match {len} samples");
+    }
+    let mut half_shape = shape.to_vec();
+    half_shape[shape.len() - 1] = n_last / 2 + 1;
+    (n_last, half_shape)
+}
+
+/// FFT (or normalized IFFT) of every line of row-major `data` along `axis`
+pub fn fft_axis(data: &mut [Complex], shape: &[usize], axis: usize, plan: &FftPlan, inverse: bool) {
+    let n = shape[axis];
+    let stride: usize = shape[axis + 1..].iter().product();
+    let mut line = vec![Complex::zero(); n];
+    for block in data.chunks_mut(n * stride) {
+        for offset in 0..stride {
+            for j in 0..n {
+                line[j] = block[offset + j * stride];
+            }
+            if inverse {
+                plan.inverse(&mut line);
+            } else {
+                plan.forward(&mut line);
+            }
+            for j in 0..n {
+                block[offset + j * stride] = line[j];
             }
         }
-        len <<= 1;
     }
 }
 
-/// IFFT with Cooley-Tukey algorithm
+/// Precomputed real-to-complex FFT of a fixed length N
 ///
-/// using bit reverse for Radix-2 DIT divides
-pub fn ifft1d<const N: usize>(x_k: &mut Vector<Complex, N>) {
-    if N == 0 || (N & (N - 1)) != 0 {
-        panic!("IFFT length N must be a power of 2"); // TODO: Implement zero padding
+/// Even N packs $$ z_j = x_{2j} + i x_{2j+1} $$ into a complex FFT of length N/2 and
+/// separates the even and odd spectra with
+/// $$ X_k = E_k + e^{-2 \pi i k / N} O_k $$.
+/// Odd N falls back to a complex FFT of length N.
+pub struct RealFftPlan {
+    n: usize,
+    inner: FftPlan,
+    twiddles: Vec<Complex>,
+}
+
+impl RealFftPlan {
+    pub fn new(n: usize) -> Self {
+        if n == 0 {
+            panic!("Real FFT length must be positive");
+        }
+        if n.is_multiple_of(2) {
+            Self {
+                n,
+                inner: FftPlan::new(n / 2),
+                twiddles: twiddles(n, n / 2 + 1),
+            }
+        } else {
+            Self {
+                n,
+                inner: FftPlan::new(n),
+                twiddles: Vec::new(),
+            }
+        }
     }
-    // Divide and Conquer using bit reverse sort
+
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Length of the half-spectrum, N/2 + 1
+    pub fn spectrum_len(&self) -> usize {
+        self.n / 2 + 1
+    }
+
+    /// r2c FFT of `x` into the half-spectrum $$ X_0, \dots, X_{N/2} $$
+    pub fn forward(&self, x: &[f64], spectrum: &mut [Complex]) {
+        self.check(x.len(), spectrum.len());
+        if self.n % 2 == 1 {
+            let mut z: Vec<Complex> = x.iter().map(|&x| Complex::new(x, 0.0)).collect();
+            self.inner.forward(&mut z);
+            spectrum.copy_from_slice(&z[..spectrum.len()]);
+            return;
+        }
+
+        let m = self.n / 2;
+        let mut z: Vec<Complex> = x.chunks(2).map(|p| Complex::new(p[0], p[1])).collect();
+        self.inner.forward(&mut z);
+        for k in 0..=m {
+            let z_k = z[k % m];
+            let z_mk = z[(m - k) % m].conj();
+            let even = (z_k + z_mk) * 0.5;
+            let odd = (z_k - z_mk) * Complex::new(0.0, -0.5);
+            spectrum[k] = even + self.twiddles[k] * odd;
+        }
+    }
+
+    /// c2r IFFT of a Hermitian half-spectrum, normalized by 1/N
+    ///
+    /// The imaginary parts of $$ X_0 $$ (and $$ X_{N/2} $$ for even N) are ignored.
+    pub fn inverse(&self, spectrum: &[Complex], x: &mut [f64]) {
+        self.check(x.len(), spectrum.len());
+        if self.n % 2 == 1 {
+            let mut z: Vec<Complex> = (0..self.n)
+                .map(|k| {
+                    if k < spectrum.len() {
+                        spectrum[k]
+                    } else {
+                        spectrum[self.n - k].conj()
+                    }
+                })
+                .collect();
+            z[0] = Complex::new(z[0].re(), 0.0);
+            self.inner.inverse(&mut z);
+            for (x, z) in x.iter_mut().zip(&z) {
+                *x = z.re();
+            }
+            return;
+        }
+
+        let m = self.n / 2;
+        let (x_0, x_m) = (spectrum[0].re(), spectrum[m].re());
+        let mut z: Vec<Complex> = (0..m)
+            .map(|k| {
+                let (x_k, x_mk) = if k == 0 {
+                    (Complex::new(x_0, 0.0), Complex::new(x_m, 0.0))
+                } else {
+                    (spectrum[k], spectrum[m - k].conj())
+                };
+                let even = (x_k + x_mk) * 0.5;
+                let odd = (x_k - x_mk) * self.twiddles[k].conj() * 0.5;
+                even + Complex::i() * odd
+            })
+            .collect();
+        self.inner.inverse(&mut z);
+        for (j, z) in z.iter().enumerate() {
+            x[2 * j] = z.re();
+            x[2 * j + 1] = z.im();
+        }
+    }
+
+    fn check(&self, samples: usize, spectrum: usize) {
+        if samples != self.n || spectrum != self.spectrum_len() {
+            panic!(
+                "RealFftPlan of length {} applied to {samples} samples and {spectrum} frequencies",
+                self.n
+            );
+        }
+    }
+}
+
+/// $$ e^{-2 \pi i k / n} $$ for `k < count`
+fn twiddles(n: usize, count: usize) -> Vec<Complex> {
+    (0..count)
+        .map(|k| Complex::cis(-2.0 * PI * (k as f64) / (n as f64)))
+        .collect()
+}
+
+/// Radix-2 DIT divides using bit reverse sort
+fn bit_reversal(n: usize) -> Vec<usize> {
     // 반대방향 bit 덧셈
+    let mut permutation = vec![0; n];
     let mut j = 0; // j: 000 부터 시작
-    for i in 1..N {
-        let mut bit = N >> 1; // bit: 100 부터 시작
+    for p in permutation.iter_mut().skip(1) {
+        let mut bit = n >> 1; // bit: 100 부터 시작
         while j & bit != 0 {
             // j의 bit 자리가 1 이라면
             j ^= bit; // j의 bit 자리를 1에서 0으로 변경
             bit >>= 1; // bit 자리가 오른쪽으로 한칸 이동
         }
         j ^= bit; // j의 bit 자리를 0에서 1로 변경
+        *p = j;
+    }
+    permutation
+}
+
+/// Smallest length $$ \geq n $$ of the form $$ 2^a 3^b 5^c 7^d $$, for zero padding
+pub fn next_fast_len(n: usize) -> usize {
+    (n.max(1)..)
+        .find(|&m| factorize(m).iter().all(|&p| p <= 7))
+        .unwrap()
+}
+
+/// Angular wavenumbers $$ k_m = 2 \pi m / L $$ in FFT order, m = 0, 1, ..., -2, -1
+///
+/// An even N puts the Nyquist mode at m = -N/2.
+pub fn wavenumbers(n: usize, length: f64) -> Vec<f64> {
+    (0..n)
+        .map(|j| {
+            let m = if j < n.div_ceil(2) {
+                j as f64
+            } else {
+                j as f64 - n as f64
+            };
+            2.0 * PI * m / length
+        })
+        .collect()
+}
+
+/// Prime factors in ascending order
+fn factorize(mut n: usize) -> Vec<usize> {
+    let mut factors = Vec::new();
+    let mut p = 2;
+    while p * p <= n {
+        while n.is_multiple_of(p) {
+            factors.push(p);
+            n /= p;
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
 
+/// Cooley-Tukey with Radix-2 DIT divides using bit reverse
+fn radix2(x_n: &mut [Complex], permutation: &[usize], twiddles: &[Complex]) {
+    let n = x_n.len();
+    for (i, &j) in permutation.iter().enumerate() {
         if i < j {
-            // Index swap x_k.swap(i, j)
-            let temp = x_k[i];
-            x_k[i] = x_k[j];
-            x_k[j] = temp;
+            x_n.swap(i, j);
         }
     }
     // Butterfly diagram
     let mut len = 2;
-    while len <= N {
-        let w_step = Complex::cis(2.0 * PI / (len as f64)); // twiddle factor
-        for i in (0..N).step_by(len) {
-            let mut w = Complex::one(); // twiddle factor
+    while len <= n {
+        let step = n / len; // twiddle factor w_len^j = w_n^(j * step)
+        for i in (0..n).step_by(len) {
             for j in 0..(len / 2) {
-                let u = x_k[i + j]; // even
-                let v = x_k[i + j + len / 2] * w; // odd
+                let u = x_n[i + j]; // even
+                let v = x_n[i + j + len / 2] * twiddles[j * step]; // odd
 
                 // 1D DFT
-                x_k[i + j] = u + v;
-                x_k[i + j + len / 2] = u - v;
-
-                w = w * w_step;
+                x_n[i + j] = u + v;
+                x_n[i + j + len / 2] = u - v;
             }
         }
         len <<= 1;
     }
+}
 
-    // Normalization
-    let inv_n = 1.0 / (N as f64);
-    for i in 0..N {
-        x_k[i] = x_k[i] * inv_n;
+/// DFT of `input[0], input[stride], ...` into `output`
+///
+/// Splits $$ n = p m $$ into p interleaved DFTs of length m and combines them with
+/// $$ X_{k + r m} = \sum_{q=0}^{p-1} w_p^{q r} w_n^{q k} Y_q[k] $$
+/// where $$ w_n $$ is `twiddles[tw_stride]`.
+fn decimate(
+    input: &[Complex],
+    stride: usize,
+    output: &mut [Complex],
+    factors: &[usize],
+    twiddles: &[Complex],
+    tw_stride: usize,
+) {
+    let n = output.len();
+    let p = factors[0];
+    let m = n / p;
+    if m == 1 {
+        for q in 0..p {
+            output[q] = input[q * stride];
+        }
+    } else {
+        for q in 0..p {
+            decimate(
+                &input[q * stride..],
+                stride * p,
+                &mut output[q * m..(q + 1) * m],
+                &factors[1..],
+                twiddles,
+                tw_stride * p,
+            );
+        }
+    }
+
+    // Radix-p butterfly
+    let mut t = vec![Complex::zero(); p];
+    for k in 0..m {
+        for q in 0..p {
+            t[q] = output[q * m + k] * twiddles[q * k * tw_stride];
+        }
+        for r in 0..p {
+            output[r * m + k] = (0..p).fold(Complex::zero(), |acc, q| {
+                acc + t[q] * twiddles[(q * r % p) * m * tw_stride]
+            });
+        }
+    }
+}
+
+/// Bluestein's chirp-z algorithm
+///
+/// $$ jk = (j^2 + k^2 - (k - j)^2) / 2 $$ turns the DFT into a circular convolution
+/// with the chirp $$ e^{\pi i j^2 / N} $$, evaluated with radix-2 FFTs of length
+/// $$ M \geq 2N - 1 $$.
+fn bluestein(n: usize) -> Algorithm {
+    let m = (2 * n - 1).next_power_of_two();
+    // j^2 mod 2N keeps the phase accurate for large j
+    let chirp: Vec<Complex> = (0..n)
+        .map(|j| Complex::cis(-PI * ((j * j % (2 * n)) as f64) / (n as f64)))
+        .collect();
+
+    let mut kernel = vec![Complex::zero(); m];
+    kernel[0] = chirp[0].conj();
+    for j in 1..n {
+        kernel[j] = chirp[j].conj();
+        kernel[m - j] = chirp[j].conj();
+    }
+    let inner = FftPlan::new(m);
+    inner.forward(&mut kernel);
+
+    Algorithm::Bluestein {
+        chirp,
+        kernel,
+        inner: Box::new(inner),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::math::core::ScalarSpace;
+    use crate::math::random::Rng;
 
     use super::*;
 
@@ -144,8 +553,7 @@ mod tests {
             .get_data()
             .iter()
             .zip(b.get_data().iter())
-            .map(|(a, b)| (a.re() - b.re()).abs() < EPS && (a.im() - b.im()).abs() < EPS)
-            .fold(true, |acc, val| acc && val);
+            .all(|(a, b)| (a.re() - b.re()).abs() < EPS && (a.im() - b.im()).abs() < EPS);
         assert!(result, "{msg}\n left: {:?}\nright: {:?}", a, b)
     }
 
@@ -220,4 +628,207 @@ mod tests {
         ifft1d(&mut frequancy);
         assert_complex_vector_eq(x, frequancy, "1D IFFT");
     }
+
+    fn assert_fft_matches_dft<const N: usize>() {
+        let x: Vector<Complex, N> = Vector::new(std::array::from_fn(|n| {
+            Complex::new((0.3 * n as f64).sin() + 1.0, (n % 7) as f64 - 3.0)
+        }));
+        let expected = dft1d(x);
+        let mut output = x;
+        fft1d(&mut output);
+        let scale = expected
+            .as_slice()
+            .iter()
+            .map(|z| z.abs())
+            .fold(1.0, f64::max);
+        for k in 0..N {
+            assert!(
+                (output[k] - expected[k]).abs() < 1e-12 * scale,
+                "N = {N}, k = {k}: {:?} != {:?}",
+                output[k],
+                expected[k]
+            );
+        }
+
+        ifft1d(&mut output);
+        for n in 0..N {
+            assert!(
+                (output[n] - x[n]).abs() < 1e-12,
+                "N = {N}: IFFT(FFT(x)) != x"
+            );
+        }
+    }
+
+    #[test]
+    fn test_factorize() {
+        assert_eq!(factorize(96), vec![2, 2, 2, 2, 2, 3]);
+        assert_eq!(factorize(100), vec![2, 2, 5, 5]);
+        assert_eq!(factorize(97), vec![97]);
+        assert_eq!(factorize(143), vec![11, 13]);
+    }
+
+    #[test]
+    fn test_fft_any_length() {
+        assert_fft_matches_dft::<1>();
+        assert_fft_matches_dft::<3>();
+        assert_fft_matches_dft::<6>();
+        assert_fft_matches_dft::<7>();
+        assert_fft_matches_dft::<12>();
+        assert_fft_matches_dft::<35>();
+        assert_fft_matches_dft::<96>();
+        assert_fft_matches_dft::<100>();
+        assert_fft_matches_dft::<384>();
+        assert_fft_matches_dft::<210>();
+    }
+
+    #[test]
+    fn test_bluestein() {
+        assert_fft_matches_dft::<11>();
+        assert_fft_matches_dft::<97>();
+        assert_fft_matches_dft::<143>();
+        assert_fft_matches_dft::<254>();
+    }
+
+    #[test]
+    fn test_slice_fft() {
+        // A single tone lands in one bin for every length
+        for n in [1000, 1009, 1024, 1215] {
+            let mut x: Vec<Complex> = (0..n)
+                .map(|j| Complex::cis(2.0 * PI * ((5 * j % n) as f64) / (n as f64)))
+                .collect();
+            fft(&mut x);
+            for (k, z) in x.iter().enumerate() {
+                let expected = if k == 5 { n as f64 } else { 0.0 };
+                assert!(
+                    (z.re() - expected).abs() < 1e-9 && z.im().abs() < 1e-9,
+                    "N = {n}"
+                );
+            }
+            ifft(&mut x);
+            assert!((x[1] - Complex::cis(2.0 * PI * 5.0 / n as f64)).abs() < 1e-12);
+        }
+    }
+
+    /// Selected bins of the direct DFT sum with exactly reduced phases
+    fn direct_dft_bin(x: &[Complex], k: usize) -> Complex {
+        let n = x.len();
+        x.iter()
+            .enumerate()
+            .fold(Complex::zero(), |acc, (j, &x_j)| {
+                acc + Complex::cis(-2.0 * PI * ((k * j % n) as f64) / (n as f64)) * x_j
+            })
+    }
+
+    #[test]
+    fn test_plan_matches_dft1d() {
+        const N: usize = 1024;
+        let mut rng = Rng::new(12);
+        let x: Vector<Complex, N> = Vector::new(std::array::from_fn(|_| rng.normal_vector()));
+        let expected = dft1d(x);
+
+        let plan = FftPlan::new(N);
+        assert_eq!(plan.len(), N);
+        for _ in 0..2 {
+            let mut output = x;
+            plan.forward(output.as_mut_slice());
+            for k in 0..N {
+                assert!((output[k] - expected[k]).abs() < 1e-11, "k = {k}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_plan_accuracy_large() {
+        let mut rng = Rng::new(20);
+        for n in [1 << 20, 3 * 5 * 7 * (1 << 10), 65537] {
+            let x: Vec<Complex> = (0..n).map(|_| rng.normal_vector()).collect();
+            let plan = FftPlan::new(n);
+            let mut output = x.clone();
+            plan.forward(&mut output);
+
+            for k in [0, 1, 7, n / 3, n / 2, n - 1] {
+                let expected = direct_dft_bin(&x, k);
+                let error = (output[k] - expected).abs();
+                assert!(error < 1e-9, "N = {n}, k = {k}: error {error}");
+            }
+
+            plan.inverse(&mut output);
+            let max_error = x
+                .iter()
+                .zip(&output)
+                .map(|(a, b)| (*a - *b).abs())
+                .fold(0.0, f64::max);
+            assert!(max_error < 1e-12, "N = {n}: IFFT(FFT(x)) error {max_error}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "FftPlan of length 8 applied to 6 samples")]
+    fn test_plan_length_mismatch() {
+        FftPlan::new(8).forward(&mut [Complex::zero(); 6]);
+    }
+
+    #[test]
+    fn test_rfft_matches_complex() {
+        let mut rng = Rng::new(13);
+        for n in [1, 2, 3, 8, 15, 96, 97, 100, 1024] {
+            let x: Vec<f64> = (0..n).map(|_| rng.normal()).collect();
+            let mut z: Vec<Complex> = x.iter().map(|&x| Complex::new(x, 0.0)).collect();
+            fft(&mut z);
+
+            let spectrum = rfft(&x);
+            assert_eq!(spectrum.len(), n / 2 + 1);
+            for k in 0..spectrum.len() {
+                assert!((spectrum[k] - z[k]).abs() < 1e-12, "N = {n}, k = {k}");
+            }
+
+            let restored = irfft(&spectrum, n);
+            for j in 0..n {
+                assert!((restored[j] - x[j]).abs() < 1e-12, "N = {n}, j = {j}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_rfftn_matches_complex() {
+        let shape = [4, 6, 10];
+        let len: usize = shape.iter().product();
+        let mut rng = Rng::new(14);
+        let real: Vec<f64> = (0..len).map(|_| rng.uniform()).collect();
+
+        let mut full: Vec<Complex> = real.iter().map(|&x| Complex::new(x, 0.0)).collect();
+        for axis in 0..3 {
+            fft_axis(&mut full, &shape, axis, &FftPlan::new(shape[axis]), false);
+        }
+
+        let spectrum = rfftn(&real, &shape);
+        assert_eq!(spectrum.len(), 4 * 6 * 6);
+        for i in 0..4 * 6 {
+            for k in 0..6 {
+                assert!((spectrum[i * 6 + k] - full[i * 10 + k]).abs() < 1e-12);
+            }
+        }
+
+        let restored = irfftn(&spectrum, &shape);
+        for j in 0..len {
+            assert!((restored[j] - real[j]).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_next_fast_len() {
+        assert_eq!(next_fast_len(0), 1);
+        assert_eq!(next_fast_len(97), 98);
+        assert_eq!(next_fast_len(1025), 1029);
+        assert_eq!(next_fast_len(210), 210);
+    }
+
+    #[test]
+    fn test_wavenumbers() {
+        let k: Vec<f64> = wavenumbers(6, 2.0 * PI).iter().map(|k| k.round()).collect();
+        assert_eq!(k, vec![0.0, 1.0, 2.0, -3.0, -2.0, -1.0]);
+        let k = wavenumbers(5, 0.5);
+        assert!((k[2] - 8.0 * PI).abs() < 1e-12);
+        assert!((k[3] + 8.0 * PI).abs() < 1e-12);
+    }
 }
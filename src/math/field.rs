@@ -1,33 +1,250 @@
+use std::ops::{Add, Sub};
+
 use crate::math::{
     complex::Complex,
-    core::Vector,
-    fft::{fft1d, ifft1d},
+    core::{LinearSpace, Vector},
+    dct::{self, Kind},
+    fft::{FftPlan, irfftn, rfftn, wavenumbers},
 };
 
 pub type Field1D<const N: usize> = Vector<Complex, N>;
 pub type Field2D<const N: usize> = Vector<Vector<Complex, N>, N>;
 pub type Field3D<const N: usize> = Vector<Vector<Vector<Complex, N>, N>, N>;
 
+pub type RealField1D<const N: usize> = Vector<f64, N>;
+pub type RealField2D<const N: usize> = Vector<Vector<f64, N>, N>;
+pub type RealField3D<const N: usize> = Vector<Vector<Vector<f64, N>, N>, N>;
+
 pub trait Field {
+    /// Real-valued field of the same shape
+    type Real;
+
     fn fft(&mut self) -> &mut Self;
     fn ifft(&mut self) -> &mut Self;
+    /// FFT along every axis with a plan of the side length N
+    fn fft_with(&mut self, plan: &FftPlan) -> &mut Self;
+    /// IFFT along every axis with a plan of the side length N
+    fn ifft_with(&mut self, plan: &FftPlan) -> &mut Self;
+    /// r2c FFT: the Hermitian half-spectrum, row-major with the last axis N/2 + 1 long
+    fn rfft(real: &Self::Real) -> Vec<Complex>;
+    /// c2r IFFT of a half-spectrum from `rfft` into `real`, normalized
+    ///
+    /// The output provides the shape, since N and N + 1 share a half-spectrum length.
+    fn irfft(spectrum: &[Complex], real: &mut Self::Real);
+    /// Applies a real 1D transform along every axis, to the real and imaginary parts
+    fn map_axes(&mut self, transform: &dyn Fn(&[f64]) -> Vec<f64>) -> &mut Self;
+    /// Samples per axis
+    fn shape(&self) -> Vec<usize>;
+    /// Physical box length per axis, the sample count (unit spacing) for fixed-size fields
+    fn lengths(&self) -> Vec<f64>;
+    /// Replaces every value by `f(index, value)`
+    fn map_indexed(&mut self, f: &dyn Fn(&[usize], Complex) -> Complex) -> &mut Self;
+
+    /// Angular wavenumbers of every axis in FFT order
+    fn wavenumbers(&self) -> Vec<Vec<f64>> {
+        self.shape()
+            .iter()
+            .zip(self.lengths())
+            .map(|(&n, length)| wavenumbers(n, length))
+            .collect()
+    }
+
+    /// Multiplies the Fourier coefficients of a transformed field by the symbol
+    /// $$ \hat{L}(k) $$ of an operator
+    fn multiply_spectrum(
+        &mut self,
+        wavenumbers: &[Vec<f64>],
+        symbol: &dyn Fn(&[f64]) -> Complex,
+    ) -> &mut Self {
+        self.map_indexed(&|index, value| {
+            let k: Vec<f64> = index.iter().zip(wavenumbers).map(|(&i, k)| k[i]).collect();
+            value * symbol(&k)
+        })
+    }
+
+    /// Spectral derivative $$ \partial / \partial x_{axis} $$
+    fn derivative(&mut self, axis: usize) -> &mut Self {
+        differentiate_spectrum(self.fft(), axis);
+        self.ifft()
+    }
+
+    /// $$ \nabla f $$, one field per axis
+    fn gradient(&self) -> Vec<Self>
+    where
+        Self: Sized + Clone,
+    {
+        let mut spectrum = self.clone();
+        spectrum.fft();
+        (0..self.shape().len())
+            .map(|axis| {
+                let mut component = spectrum.clone();
+                differentiate_spectrum(&mut component, axis);
+                component.ifft();
+                component
+            })
+            .collect()
+    }
+
+    /// $$ \nabla \cdot \mathbf{v} $$ of one component per axis
+    fn divergence(components: &[Self]) -> Self
+    where
+        Self: Sized + Clone + Add<Output = Self>,
+    {
+        check_components(components);
+        let mut divergence = components
+            .iter()
+            .enumerate()
+            .map(|(axis, component)| {
+                let mut spectrum = component.clone();
+                differentiate_spectrum(spectrum.fft(), axis);
+                spectrum
+            })
+            .reduce(|a, b| a + b)
+            .unwrap();
+        divergence.ifft();
+        divergence
+    }
+
+    /// $$ \nabla \times \mathbf{v} $$ of a 3D vector field
+    fn curl(components: &[Self; 3]) -> [Self; 3]
+    where
+        Self: Sized + Clone + Sub<Output = Self>,
+    {
+        check_components(components);
+        let d = |component: usize, axis: usize| {
+            let mut field = components[component].clone();
+            field.derivative(axis);
+            field
+        };
+        [d(2, 1) - d(1, 2), d(0, 2) - d(2, 0), d(1, 0) - d(0, 1)]
+    }
+
+    /// $$ \nabla^2 f $$
+    fn laplacian(&mut self) -> &mut Self {
+        let wavenumbers = self.wavenumbers();
+        self.fft()
+            .multiply_spectrum(&wavenumbers, &laplacian_symbol)
+            .ifft()
+    }
+
+    /// Solves $$ \nabla^2 u = f $$ for the zero-mean u
+    ///
+    /// The k = 0 mode is dropped: a periodic source must have zero mean, and u is
+    /// fixed only up to a constant.
+    fn inverse_laplacian(&mut self) -> &mut Self {
+        let wavenumbers = self.wavenumbers();
+        self.fft()
+            .multiply_spectrum(&wavenumbers, &inverse_laplacian_symbol)
+            .ifft()
+    }
+
+    /// DCT along every axis, for Neumann boundaries
+    fn dct(&mut self, kind: Kind) -> &mut Self {
+        self.map_axes(&|x| dct::dct(x, kind))
+    }
+    fn idct(&mut self, kind: Kind) -> &mut Self {
+        self.map_axes(&|x| dct::idct(x, kind))
+    }
+    /// DST along every axis, for Dirichlet boundaries
+    fn dst(&mut self, kind: Kind) -> &mut Self {
+        self.map_axes(&|x| dct::dst(x, kind))
+    }
+    fn idst(&mut self, kind: Kind) -> &mut Self {
+        self.map_axes(&|x| dct::idst(x, kind))
+    }
+}
+
+/// Symbol $$ -|k|^2 $$ of the Laplacian
+pub fn laplacian_symbol(k: &[f64]) -> Complex {
+    Complex::new(-k.iter().map(|k| k * k).sum::<f64>(), 0.0)
+}
+
+/// Symbol $$ -1 / |k|^2 $$ of the inverse Laplacian, zero for the mean
+pub fn inverse_laplacian_symbol(k: &[f64]) -> Complex {
+    let k_sq: f64 = k.iter().map(|k| k * k).sum();
+    if k_sq == 0.0 {
+        Complex::zero()
+    } else {
+        Complex::new(-1.0 / k_sq, 0.0)
+    }
+}
+
+/// Multiplies a spectrum by $$ i k_{axis} $$
+///
+/// The Nyquist mode of an even axis has no real odd derivative and is dropped.
+fn differentiate_spectrum<F: Field + ?Sized>(spectrum: &mut F, axis: usize) {
+    let mut wavenumbers = spectrum.wavenumbers();
+    let n = wavenumbers[axis].len();
+    if n.is_multiple_of(2) {
+        wavenumbers[axis][n / 2] = 0.0;
+    }
+    spectrum.multiply_spectrum(&wavenumbers, &|k| Complex::new(0.0, k[axis]));
+}
+
+fn check_components<F: Field>(components: &[F]) {
+    let ndim = components.first().map_or(0, |c| c.shape().len());
+    if ndim != components.len() {
+        panic!(
+            "Expected one component per axis of a {ndim}D field, got {}",
+            components.len()
+        );
+    }
 }
 
 impl<const N: usize> Field for Field1D<N> {
+    type Real = RealField1D<N>;
+
     fn fft(&mut self) -> &mut Self {
-        fft1d(self);
-        self
+        self.fft_with(&FftPlan::new(N))
     }
     fn ifft(&mut self) -> &mut Self {
-        ifft1d(self);
+        self.ifft_with(&FftPlan::new(N))
+    }
+    fn fft_with(&mut self, plan: &FftPlan) -> &mut Self {
+        plan.forward(self.as_mut_slice());
+        self
+    }
+    fn ifft_with(&mut self, plan: &FftPlan) -> &mut Self {
+        plan.inverse(self.as_mut_slice());
+        self
+    }
+    fn rfft(real: &Self::Real) -> Vec<Complex> {
+        rfftn(real.as_slice(), &[N])
+    }
+    fn irfft(spectrum: &[Complex], real: &mut Self::Real) {
+        let flat = irfftn(spectrum, &[N]);
+        *real = Vector::new(std::array::from_fn(|x| flat[x]));
+    }
+    fn map_axes(&mut self, transform: &dyn Fn(&[f64]) -> Vec<f64>) -> &mut Self {
+        dct::map_axes(self.as_mut_slice(), &[N], transform);
+        self
+    }
+    fn shape(&self) -> Vec<usize> {
+        vec![N]
+    }
+    fn lengths(&self) -> Vec<f64> {
+        vec![N as f64]
+    }
+    fn map_indexed(&mut self, f: &dyn Fn(&[usize], Complex) -> Complex) -> &mut Self {
+        for x in 0..N {
+            self[x] = f(&[x], self[x]);
+        }
         self
     }
 }
 
 impl<const N: usize> Field for Field2D<N> {
+    type Real = RealField2D<N>;
+
     fn fft(&mut self) -> &mut Self {
+        self.fft_with(&FftPlan::new(N))
+    }
+    fn ifft(&mut self) -> &mut Self {
+        self.ifft_with(&FftPlan::new(N))
+    }
+    fn fft_with(&mut self, plan: &FftPlan) -> &mut Self {
         for x in 0..N {
-            fft1d(&mut self[x]);
+            plan.forward(self[x].as_mut_slice());
         }
 
         // Transpose
@@ -40,7 +257,7 @@ impl<const N: usize> Field for Field2D<N> {
         }
 
         for x in 0..N {
-            fft1d(&mut self[x]);
+            plan.forward(self[x].as_mut_slice());
         }
 
         // Transpose
@@ -53,9 +270,9 @@ impl<const N: usize> Field for Field2D<N> {
         }
         self
     }
-    fn ifft(&mut self) -> &mut Self {
+    fn ifft_with(&mut self, plan: &FftPlan) -> &mut Self {
         for x in 0..N {
-            ifft1d(&mut self[x]);
+            plan.inverse(self[x].as_mut_slice());
         }
 
         // Transpose
@@ -68,7 +285,7 @@ impl<const N: usize> Field for Field2D<N> {
         }
 
         for x in 0..N {
-            ifft1d(&mut self[x]);
+            plan.inverse(self[x].as_mut_slice());
         }
 
         // Transpose
@@ -81,13 +298,63 @@ impl<const N: usize> Field for Field2D<N> {
         }
         self
     }
+    fn rfft(real: &Self::Real) -> Vec<Complex> {
+        let flat: Vec<f64> = real
+            .as_slice()
+            .iter()
+            .flat_map(|row| row.as_slice().to_vec())
+            .collect();
+        rfftn(&flat, &[N, N])
+    }
+    fn irfft(spectrum: &[Complex], real: &mut Self::Real) {
+        let flat = irfftn(spectrum, &[N, N]);
+        *real = Vector::new(std::array::from_fn(|x| {
+            Vector::new(std::array::from_fn(|y| flat[x * N + y]))
+        }));
+    }
+    fn map_axes(&mut self, transform: &dyn Fn(&[f64]) -> Vec<f64>) -> &mut Self {
+        let mut flat: Vec<Complex> = self
+            .as_slice()
+            .iter()
+            .flat_map(|row| row.as_slice().to_vec())
+            .collect();
+        dct::map_axes(&mut flat, &[N, N], transform);
+        for x in 0..N {
+            for y in 0..N {
+                self[x][y] = flat[x * N + y];
+            }
+        }
+        self
+    }
+    fn shape(&self) -> Vec<usize> {
+        vec![N, N]
+    }
+    fn lengths(&self) -> Vec<f64> {
+        vec![N as f64; 2]
+    }
+    fn map_indexed(&mut self, f: &dyn Fn(&[usize], Complex) -> Complex) -> &mut Self {
+        for x in 0..N {
+            for y in 0..N {
+                self[x][y] = f(&[x, y], self[x][y]);
+            }
+        }
+        self
+    }
 }
 
 impl<const N: usize> Field for Field3D<N> {
+    type Real = RealField3D<N>;
+
     fn fft(&mut self) -> &mut Self {
+        self.fft_with(&FftPlan::new(N))
+    }
+    fn ifft(&mut self) -> &mut Self {
+        self.ifft_with(&FftPlan::new(N))
+    }
+    fn fft_with(&mut self, plan: &FftPlan) -> &mut Self {
         // 2D FFT in YZ surface
         for x in 0..N {
-            self[x].fft();
+            self[x].fft_with(plan);
         }
 
         // Transpose xz
@@ -104,7 +371,7 @@ impl<const N: usize> Field for Field3D<N> {
         // 1D FFT in X axis
         for z in 0..N {
             for y in 0..N {
-                self[z][y].fft();
+                self[z][y].fft_with(plan);
             }
         }
 
@@ -120,10 +387,10 @@ impl<const N: usize> Field for Field3D<N> {
         }
         self
     }
-    fn ifft(&mut self) -> &mut Self {
+    fn ifft_with(&mut self, plan: &FftPlan) -> &mut Self {
         // 2D IFFT in YZ surface
         for x in 0..N {
-            self[x].ifft();
+            self[x].ifft_with(plan);
         }
 
         // Transpose xz
@@ -140,7 +407,7 @@ impl<const N: usize> Field for Field3D<N> {
         // 1D IFFT in X axis
         for z in 0..N {
             for y in 0..N {
-                self[z][y].ifft();
+                self[z][y].ifft_with(plan);
             }
         }
 
@@ -156,6 +423,64 @@ impl<const N: usize> Field for Field3D<N> {
         }
         self
     }
+    fn rfft(real: &Self::Real) -> Vec<Complex> {
+        let flat: Vec<f64> = real
+            .as_slice()
+            .iter()
+            .flat_map(|plane| {
+                plane
+                    .as_slice()
+                    .iter()
+                    .flat_map(|row| row.as_slice().to_vec())
+            })
+            .collect();
+        rfftn(&flat, &[N, N, N])
+    }
+    fn irfft(spectrum: &[Complex], real: &mut Self::Real) {
+        let flat = irfftn(spectrum, &[N, N, N]);
+        *real = Vector::new(std::array::from_fn(|x| {
+            Vector::new(std::array::from_fn(|y| {
+                Vector::new(std::array::from_fn(|z| flat[(x * N + y) * N + z]))
+            }))
+        }));
+    }
+    fn map_axes(&mut self, transform: &dyn Fn(&[f64]) -> Vec<f64>) -> &mut Self {
+        let mut flat: Vec<Complex> = self
+            .as_slice()
+            .iter()
+            .flat_map(|plane| {
+                plane
+                    .as_slice()
+                    .iter()
+                    .flat_map(|row| row.as_slice().to_vec())
+            })
+            .collect();
+        dct::map_axes(&mut flat, &[N, N, N], transform);
+        for x in 0..N {
+            for y in 0..N {
+                for z in 0..N {
+                    self[x][y][z] = flat[(x * N + y) * N + z];
+                }
+            }
+        }
+        self
+    }
+    fn shape(&self) -> Vec<usize> {
+        vec![N, N, N]
+    }
+    fn lengths(&self) -> Vec<f64> {
+        vec![N as f64; 3]
+    }
+    fn map_indexed(&mut self, f: &dyn Fn(&[usize], Complex) -> Complex) -> &mut Self {
+        for x in 0..N {
+            for y in 0..N {
+                for z in 0..N {
+                    self[x][y][z] = f(&[x, y, z], self[x][y][z]);
+                }
+            }
+        }
+        self
+    }
 }
 
 #[cfg(test)]
@@ -175,8 +500,7 @@ mod tests {
             .get_data()
             .iter()
             .zip(b.get_data().iter())
-            .map(|(a, b)| (a.re() - b.re()).abs() < EPS && (a.im() - b.im()).abs() < EPS)
-            .fold(true, |acc, val| acc && val);
+            .all(|(a, b)| (a.re() - b.re()).abs() < EPS && (a.im() - b.im()).abs() < EPS);
         assert!(result, "{msg}\n left: {:?}\nright: {:?}", a, b)
     }
 
@@ -184,12 +508,12 @@ mod tests {
     fn test_field1d_reversibility() {
         const N: usize = 8;
         let mut data = [Complex::zero(); N];
-        for i in 0..N {
-            data[i] = Complex::new(i as f64, (i as f64) * 0.5);
+        for (i, x) in data.iter_mut().enumerate() {
+            *x = Complex::new(i as f64, (i as f64) * 0.5);
         }
 
         let mut field = Field1D::<N>::new(data);
-        let original = field.clone();
+        let original = field;
 
         field.fft().ifft();
 
@@ -293,4 +617,274 @@ mod tests {
             "Field3D transpose test are failed."
         );
     }
+
+    #[test]
+    fn test_field2d_reversibility_non_power_of_two() {
+        const N: usize = 10;
+        let mut field = Field2D::<N>::zero();
+        for x in 0..N {
+            for y in 0..N {
+                field[x][y] = Complex::new((x * y) as f64, (x + 2 * y) as f64 * 0.1);
+            }
+        }
+        let original = field;
+
+        field.fft().ifft();
+
+        for x in 0..N {
+            for y in 0..N {
+                assert!((field[x][y] - original[x][y]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_field3d_plane_wave_non_power_of_two() {
+        // exp(2 pi i (x + 2y + 3z) / N) -> N^3 at k = (1, 2, 3)
+        const N: usize = 6;
+        let mut field = Field3D::<N>::zero();
+        for x in 0..N {
+            for y in 0..N {
+                for z in 0..N {
+                    let phase = ((x + 2 * y + 3 * z) % N) as f64 / N as f64;
+                    field[x][y][z] = Complex::cis(2.0 * std::f64::consts::PI * phase);
+                }
+            }
+        }
+
+        field.fft();
+
+        for x in 0..N {
+            for y in 0..N {
+                for z in 0..N {
+                    let expected = if (x, y, z) == (1, 2, 3) {
+                        (N * N * N) as f64
+                    } else {
+                        0.0
+                    };
+                    assert!(
+                        (field[x][y][z] - Complex::new(expected, 0.0)).abs() < 1e-10,
+                        "Field3D<6>.fft() at ({x}, {y}, {z}): {:?}",
+                        field[x][y][z]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_field3d_rfft_matches_complex() {
+        const N: usize = 6;
+        let mut real = RealField3D::<N>::zero();
+        let mut field = Field3D::<N>::zero();
+        for x in 0..N {
+            for y in 0..N {
+                for z in 0..N {
+                    let value = ((x * 7 + y * 3 + z * z) % 5) as f64 - 2.0;
+                    real[x][y][z] = value;
+                    field[x][y][z] = Complex::new(value, 0.0);
+                }
+            }
+        }
+
+        let spectrum = Field3D::<N>::rfft(&real);
+        field.fft();
+
+        let half = N / 2 + 1;
+        assert_eq!(spectrum.len(), N * N * half);
+        for x in 0..N {
+            for y in 0..N {
+                for z in 0..half {
+                    let difference = spectrum[(x * N + y) * half + z] - field[x][y][z];
+                    assert!(difference.abs() < 1e-12, "k = ({x}, {y}, {z})");
+                }
+            }
+        }
+
+        let mut restored = RealField3D::<N>::zero();
+        Field3D::<N>::irfft(&spectrum, &mut restored);
+        for x in 0..N {
+            for y in 0..N {
+                for z in 0..N {
+                    assert!((restored[x][y][z] - real[x][y][z]).abs() < 1e-12);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_field2d_rfft_odd_length() {
+        const N: usize = 5;
+        let mut real = RealField2D::<N>::zero();
+        let mut field = Field2D::<N>::zero();
+        for x in 0..N {
+            for y in 0..N {
+                real[x][y] = (x as f64 - 1.5) * (y as f64 + 0.25);
+                field[x][y] = Complex::new(real[x][y], 0.0);
+            }
+        }
+
+        let spectrum = Field2D::<N>::rfft(&real);
+        field.fft();
+        for x in 0..N {
+            for y in 0..N / 2 + 1 {
+                assert!((spectrum[x * (N / 2 + 1) + y] - field[x][y]).abs() < 1e-12);
+            }
+        }
+
+        let mut restored = RealField2D::<N>::zero();
+        Field2D::<N>::irfft(&spectrum, &mut restored);
+        for x in 0..N {
+            for y in 0..N {
+                assert!((restored[x][y] - real[x][y]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_field2d_dst_sine_mode() {
+        // sin(pi (x + 1) / (N + 1)) sin(2 pi (y + 1) / (N + 1)) -> (N + 1)^2 at (0, 1)
+        const N: usize = 7;
+        let mut field = Field2D::<N>::zero();
+        let h = std::f64::consts::PI / (N + 1) as f64;
+        for x in 0..N {
+            for y in 0..N {
+                let value = (h * (x + 1) as f64).sin() * (2.0 * h * (y + 1) as f64).sin();
+                field[x][y] = Complex::new(value, -value);
+            }
+        }
+
+        field.dst(Kind::I);
+
+        let peak = ((N + 1) * (N + 1)) as f64;
+        for x in 0..N {
+            for y in 0..N {
+                let expected = if (x, y) == (0, 1) { peak } else { 0.0 };
+                assert!((field[x][y] - Complex::new(expected, -expected)).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_field3d_trig_reversibility() {
+        const N: usize = 4;
+        let mut field = Field3D::<N>::zero();
+        for x in 0..N {
+            for y in 0..N {
+                for z in 0..N {
+                    field[x][y][z] = Complex::new((x + 2 * y) as f64, (z * x) as f64);
+                }
+            }
+        }
+        let original = field;
+
+        for kind in [Kind::I, Kind::II, Kind::III] {
+            field.dct(kind).idct(kind).dst(kind).idst(kind);
+        }
+
+        for x in 0..N {
+            for y in 0..N {
+                for z in 0..N {
+                    assert!((field[x][y][z] - original[x][y][z]).abs() < 1e-12);
+                }
+            }
+        }
+    }
+
+    fn assert_close(a: Complex, b: f64, msg: &str) {
+        assert!(
+            (a - Complex::new(b, 0.0)).abs() < 1e-11,
+            "{msg}: {a:?} != {b}"
+        );
+    }
+
+    #[test]
+    fn test_field1d_derivative() {
+        const N: usize = 16;
+        let a = 2.0 * std::f64::consts::PI * 3.0 / N as f64;
+        // The Nyquist cosine has no derivative on the grid
+        let mut field = Field1D::<N>::new(std::array::from_fn(|x| {
+            Complex::new(
+                (a * x as f64).sin() + (std::f64::consts::PI * x as f64).cos(),
+                0.0,
+            )
+        }));
+        field.derivative(0);
+        for x in 0..N {
+            assert_close(field[x], a * (a * x as f64).cos(), "derivative");
+        }
+
+        let mut field = Field1D::<N>::new(std::array::from_fn(|x| {
+            Complex::new((a * x as f64).sin(), 0.0)
+        }));
+        field.laplacian();
+        for x in 0..N {
+            assert_close(field[x], -a * a * (a * x as f64).sin(), "laplacian");
+        }
+    }
+
+    #[test]
+    fn test_field2d_inverse_laplacian() {
+        const N: usize = 16;
+        let a = 2.0 * std::f64::consts::PI / N as f64;
+        let wave = |x: usize, y: usize| (a * x as f64).sin() * (2.0 * a * y as f64).cos();
+        let source = Field2D::<N>::new(std::array::from_fn(|x| {
+            Vector::new(std::array::from_fn(|y| Complex::new(3.0 + wave(x, y), 0.0)))
+        }));
+
+        let mut potential = source;
+        potential.inverse_laplacian();
+        for x in 0..N {
+            for y in 0..N {
+                assert_close(potential[x][y], -wave(x, y) / (5.0 * a * a), "potential");
+            }
+        }
+
+        // The mean of the source is dropped
+        potential.laplacian();
+        for x in 0..N {
+            for y in 0..N {
+                assert_close(potential[x][y], wave(x, y), "round trip");
+            }
+        }
+    }
+
+    #[test]
+    fn test_field3d_curl_divergence() {
+        const N: usize = 12;
+        let a = 2.0 * std::f64::consts::PI / N as f64;
+        let component = |f: &dyn Fn(usize, usize, usize) -> f64| -> Field3D<N> {
+            Vector::new(std::array::from_fn(|x| {
+                Vector::new(std::array::from_fn(|y| {
+                    Vector::new(std::array::from_fn(|z| Complex::new(f(x, y, z), 0.0)))
+                }))
+            }))
+        };
+        // v = (sin a y, sin a z, sin a x)
+        let v = [
+            component(&|_, y, _| (a * y as f64).sin()),
+            component(&|_, _, z| (a * z as f64).sin()),
+            component(&|x, _, _| (a * x as f64).sin()),
+        ];
+
+        let divergence = Field3D::divergence(&v);
+        let curl = Field3D::curl(&v);
+        for x in 0..N {
+            for y in 0..N {
+                for z in 0..N {
+                    let (xf, yf, zf) = (x as f64, y as f64, z as f64);
+                    assert_close(divergence[x][y][z], 0.0, "divergence");
+                    assert_close(curl[0][x][y][z], -a * (a * zf).cos(), "curl x");
+                    assert_close(curl[1][x][y][z], -a * (a * xf).cos(), "curl y");
+                    assert_close(curl[2][x][y][z], -a * (a * yf).cos(), "curl z");
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected one component per axis of a 2D field, got 3")]
+    fn test_divergence_component_count() {
+        let _ = Field2D::<4>::divergence(&[Field2D::<4>::zero(); 3]);
+    }
 }
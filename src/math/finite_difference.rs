@@ -0,0 +1,577 @@
+use crate::math::grid::Grid;
+
+/// Order of accuracy of a central stencil
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Accuracy {
+    Second,
+    Fourth,
+    Sixth,
+}
+
+impl Accuracy {
+    pub fn order(&self) -> usize {
+        match self {
+            Accuracy::Second => 2,
+            Accuracy::Fourth => 4,
+            Accuracy::Sixth => 6,
+        }
+    }
+
+    /// Weights of $$ u_{i+j} - u_{i-j} $$ for j = 1..=r in the first derivative
+    fn first_coefficients(&self) -> &'static [f64] {
+        match self {
+            Accuracy::Second => &[1.0 / 2.0],
+            Accuracy::Fourth => &[2.0 / 3.0, -1.0 / 12.0],
+            Accuracy::Sixth => &[3.0 / 4.0, -3.0 / 20.0, 1.0 / 60.0],
+        }
+    }
+
+    /// Weights of $$ u_i $$ and of $$ u_{i+j} + u_{i-j} $$ for j = 1..=r in the
+    /// second derivative
+    fn second_coefficients(&self) -> &'static [f64] {
+        match self {
+            Accuracy::Second => &[-2.0, 1.0],
+            Accuracy::Fourth => &[-5.0 / 2.0, 4.0 / 3.0, -1.0 / 12.0],
+            Accuracy::Sixth => &[-49.0 / 18.0, 3.0 / 2.0, -3.0 / 20.0, 1.0 / 90.0],
+        }
+    }
+
+    fn half_width(&self) -> usize {
+        self.order() / 2
+    }
+}
+
+/// Condition on one wall of an axis
+///
+/// Walls lie half a spacing outside the first and last samples, as for DCT-II
+/// and DST-II. A grid over [a, b) samples $$ a, \dots, b - \Delta x $$, so its
+/// walls are at $$ a - \Delta x / 2 $$ and $$ b - \Delta x / 2 $$. Derivatives
+/// along the normal n point out of the domain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Boundary {
+    Periodic,
+    /// $$ u = g $$
+    Dirichlet(f64),
+    /// $$ \partial u / \partial n = g $$
+    Neumann(f64),
+    /// $$ \alpha u + \beta \partial u / \partial n = \gamma $$
+    Robin {
+        alpha: f64,
+        beta: f64,
+        gamma: f64,
+    },
+}
+
+impl Boundary {
+    /// $$ (\alpha, \beta, \gamma) $$ of the equivalent Robin condition
+    fn robin(&self) -> (f64, f64, f64) {
+        match *self {
+            Boundary::Periodic => unreachable!(),
+            Boundary::Dirichlet(g) => (1.0, 0.0, g),
+            Boundary::Neumann(g) => (0.0, 1.0, g),
+            Boundary::Robin { alpha, beta, gamma } => (alpha, beta, gamma),
+        }
+    }
+}
+
+/// Finite-difference operators on real grids, using the grid spacing per axis
+///
+/// Non-periodic walls are imposed through ghost samples. These come from the
+/// polynomial through the condition and the order + 2 nearest samples, so
+/// boundary rows keep the order of the interior stencil.
+#[derive(Clone, Debug)]
+pub struct FiniteDifference {
+    accuracy: Accuracy,
+    /// `[lower, upper]` per axis, periodic past the end
+    boundaries: Vec<[Boundary; 2]>,
+}
+
+impl FiniteDifference {
+    /// Periodic on every axis
+    pub fn new(accuracy: Accuracy) -> Self {
+        Self {
+            accuracy,
+            boundaries: Vec::new(),
+        }
+    }
+
+    pub fn with_boundary(mut self, axis: usize, lower: Boundary, upper: Boundary) -> Self {
+        if (lower == Boundary::Periodic) != (upper == Boundary::Periodic) {
+            panic!("Periodic boundaries must be paired, got {lower:?} and {upper:?}");
+        }
+        if self.boundaries.len() <= axis {
+            self.boundaries
+                .resize(axis + 1, [Boundary::Periodic, Boundary::Periodic]);
+        }
+        self.boundaries[axis] = [lower, upper];
+        self
+    }
+
+    pub fn accuracy(&self) -> Accuracy {
+        self.accuracy
+    }
+
+    /// `[lower, upper]` walls of `axis`
+    pub fn boundary(&self, axis: usize) -> [Boundary; 2] {
+        self.boundaries
+            .get(axis)
+            .copied()
+            .unwrap_or([Boundary::Periodic, Boundary::Periodic])
+    }
+
+    /// $$ \partial u / \partial x_{axis} $$
+    pub fn derivative(&self, u: &Grid<f64>, axis: usize) -> Grid<f64> {
+        let h = u.spacing()[axis];
+        let coefficients = self.accuracy.first_coefficients();
+        let extension = self.extension(u, axis, self.accuracy);
+        map_lines(u, axis, |line| {
+            let r = coefficients.len();
+            let extended = extension.extend(line);
+            (0..line.len())
+                .map(|i| {
+                    let c = i + r;
+                    coefficients
+                        .iter()
+                        .enumerate()
+                        .map(|(j, w)| w * (extended[c + j + 1] - extended[c - j - 1]))
+                        .sum::<f64>()
+                        / h
+                })
+                .collect()
+        })
+    }
+
+    /// $$ \partial^2 u / \partial x_{axis}^2 $$
+    pub fn second_derivative(&self, u: &Grid<f64>, axis: usize) -> Grid<f64> {
+        let h = u.spacing()[axis];
+        let extension = self.extension(u, axis, self.accuracy);
+        map_lines(u, axis, |line| {
+            second_difference(&extension.extend(line), line.len(), self.accuracy, h)
+        })
+    }
+
+    /// $$ \nabla u $$, one grid per axis
+    pub fn gradient(&self, u: &Grid<f64>) -> Vec<Grid<f64>> {
+        (0..u.ndim()).map(|axis| self.derivative(u, axis)).collect()
+    }
+
+    /// $$ \nabla^2 u $$ as the sum of the second derivatives
+    pub fn laplacian(&self, u: &Grid<f64>) -> Grid<f64> {
+        (1..u.ndim()).fold(self.second_derivative(u, 0), |sum, axis| {
+            sum + self.second_derivative(u, axis)
+        })
+    }
+
+    /// 4th-order compact (Padé) Laplacian, whatever the stencil accuracy
+    ///
+    /// Each second derivative solves
+    /// $$ \frac{1}{10} u''_{i-1} + u''_i + \frac{1}{10} u''_{i+1} = \frac{6}{5} \frac{u_{i+1} - 2 u_i + u_{i-1}}{\Delta x^2} $$
+    /// on a three-point stencil. Rows next to a wall are closed by the explicit
+    /// 4th-order stencil.
+    pub fn compact_laplacian(&self, u: &Grid<f64>) -> Grid<f64> {
+        (0..u.ndim())
+            .map(|axis| self.compact_second_derivative(u, axis))
+            .reduce(|sum, term| sum + term)
+            .unwrap()
+    }
+
+    fn compact_second_derivative(&self, u: &Grid<f64>, axis: usize) -> Grid<f64> {
+        let h = u.spacing()[axis];
+        let periodic = self.boundary(axis)[0] == Boundary::Periodic;
+        let extension = self.extension(u, axis, Accuracy::Fourth);
+        map_lines(u, axis, |line| {
+            let n = line.len();
+            let extended = extension.extend(line);
+            let mut f: Vec<f64> = (0..n)
+                .map(|i| {
+                    1.2 * (extended[i + 3] - 2.0 * extended[i + 2] + extended[i + 1]) / (h * h)
+                })
+                .collect();
+            if periodic {
+                solve_cyclic_tridiagonal(0.1, 1.0, &mut f);
+                return f;
+            }
+            let explicit = second_difference(&extended, n, Accuracy::Fourth, h);
+            f[0] = explicit[0];
+            f[n - 1] = explicit[n - 1];
+            f[1] -= 0.1 * f[0];
+            f[n - 2] -= 0.1 * f[n - 1];
+            solve_tridiagonal(0.1, &vec![1.0; n - 2], &mut f[1..n - 1]);
+            f
+        })
+    }
+
+    fn extension(&self, u: &Grid<f64>, axis: usize, accuracy: Accuracy) -> Extension {
+        let n = u.shape()[axis];
+        let width = accuracy.half_width();
+        let h = u.spacing()[axis];
+        let [lower, upper] = self.boundary(axis);
+        if lower == Boundary::Periodic {
+            if n < width {
+                panic!("Periodic stencil of width {width} on {n} samples along axis {axis}");
+            }
+            return Extension {
+                width,
+                lower: None,
+                upper: None,
+            };
+        }
+        let points = accuracy.order() + 2;
+        if n < points {
+            panic!(
+                "{accuracy:?}-order boundaries need {points} samples along axis {axis}, got {n}"
+            );
+        }
+        Extension {
+            width,
+            lower: Some(Ghosts::new(lower, width, points, h)),
+            upper: Some(Ghosts::new(upper, width, points, h)),
+        }
+    }
+}
+
+/// Second-derivative stencil on the samples of a line padded by the stencil half width
+fn second_difference(extended: &[f64], n: usize, accuracy: Accuracy, h: f64) -> Vec<f64> {
+    let coefficients = accuracy.second_coefficients();
+    let r = coefficients.len() - 1;
+    (0..n)
+        .map(|i| {
+            let c = i + r;
+            let sum = coefficients[0] * extended[c]
+                + (1..=r)
+                    .map(|j| coefficients[j] * (extended[c + j] + extended[c - j]))
+                    .sum::<f64>();
+            sum / (h * h)
+        })
+        .collect()
+}
+
+/// Ghost samples beyond one wall, $$ u_{-q} = \sum_j w_{qj} u_j + b_q $$, with j
+/// counted from the wall inwards
+struct Ghosts {
+    weights: Vec<Vec<f64>>,
+    offsets: Vec<f64>,
+}
+
+impl Ghosts {
+    fn new(boundary: Boundary, count: usize, points: usize, h: f64) -> Self {
+        let (alpha, beta, gamma) = boundary.robin();
+        // Lagrange interpolation in units of the spacing through the wall value u_w
+        // at s = -1/2 and the samples at s = 0..points. The outward derivative is
+        // -p'(s) / h, so the condition fixes u_w = sum_j a_j u_j + a_gamma gamma.
+        let nodes: Vec<f64> = std::iter::once(-0.5)
+            .chain((0..points).map(|j| j as f64))
+            .collect();
+        let slope: Vec<f64> = (0..nodes.len())
+            .map(|j| lagrange_derivative(&nodes, j, -0.5))
+            .collect();
+        let denominator = alpha - beta * slope[0] / h;
+        let wall: Vec<f64> = slope[1..]
+            .iter()
+            .map(|d| beta * d / h / denominator)
+            .collect();
+
+        let (mut weights, mut offsets) = (Vec::new(), Vec::new());
+        for q in 1..=count {
+            let s = -(q as f64);
+            let through_wall = lagrange(&nodes, 0, s);
+            weights.push(
+                (0..points)
+                    .map(|j| lagrange(&nodes, j + 1, s) + through_wall * wall[j])
+                    .collect(),
+            );
+            offsets.push(through_wall * gamma / denominator);
+        }
+        Self { weights, offsets }
+    }
+
+    /// Ghost `q` (1-based) from samples ordered from the wall inwards
+    fn value(&self, q: usize, sample: impl Fn(usize) -> f64) -> f64 {
+        self.weights[q - 1]
+            .iter()
+            .enumerate()
+            .map(|(j, w)| w * sample(j))
+            .sum::<f64>()
+            + self.offsets[q - 1]
+    }
+}
+
+/// Lagrange basis polynomial $$ L_j(s) = \prod_{k \neq j} \frac{s - x_k}{x_j - x_k} $$
+fn lagrange(nodes: &[f64], j: usize, s: f64) -> f64 {
+    (0..nodes.len())
+        .filter(|&k| k != j)
+        .map(|k| (s - nodes[k]) / (nodes[j] - nodes[k]))
+        .product()
+}
+
+/// $$ L_j'(s) $$
+fn lagrange_derivative(nodes: &[f64], j: usize, s: f64) -> f64 {
+    (0..nodes.len())
+        .filter(|&l| l != j)
+        .map(|l| {
+            (0..nodes.len())
+                .filter(|&k| k != j && k != l)
+                .map(|k| (s - nodes[k]) / (nodes[j] - nodes[k]))
+                .product::<f64>()
+                / (nodes[j] - nodes[l])
+        })
+        .sum()
+}
+
+/// Pads lines with `width` samples on each side, periodic where ghosts are `None`
+struct Extension {
+    width: usize,
+    lower: Option<Ghosts>,
+    upper: Option<Ghosts>,
+}
+
+impl Extension {
+    fn extend(&self, line: &[f64]) -> Vec<f64> {
+        let n = line.len();
+        let lower = (1..=self.width).rev().map(|q| match &self.lower {
+            Some(ghosts) => ghosts.value(q, |j| line[j]),
+            None => line[n - q],
+        });
+        let upper = (1..=self.width).map(|q| match &self.upper {
+            Some(ghosts) => ghosts.value(q, |j| line[n - 1 - j]),
+            None => line[q - 1],
+        });
+        lower.chain(line.iter().copied()).chain(upper).collect()
+    }
+}
+
+/// Applies `f` to every line of `u` along `axis`
+fn map_lines(u: &Grid<f64>, axis: usize, f: impl Fn(&[f64]) -> Vec<f64>) -> Grid<f64> {
+    let n = u.shape()[axis];
+    let stride = u.strides()[axis];
+    let mut result = u.clone();
+    for (block, out) in u
+        .as_slice()
+        .chunks(n * stride)
+        .zip(result.as_mut_slice().chunks_mut(n * stride))
+    {
+        for offset in 0..stride {
+            let line: Vec<f64> = (0..n).map(|j| block[offset + j * stride]).collect();
+            for (j, value) in f(&line).into_iter().enumerate() {
+                out[offset + j * stride] = value;
+            }
+        }
+    }
+    result
+}
+
+/// Thomas algorithm for $$ a x_{i-1} + b_i x_i + a x_{i+1} = d_i $$, in place
+fn solve_tridiagonal(a: f64, b: &[f64], d: &mut [f64]) {
+    let n = d.len();
+    let mut upper = vec![0.0; n];
+    let mut pivot = b[0];
+    upper[0] = a / pivot;
+    d[0] /= pivot;
+    for i in 1..n {
+        pivot = b[i] - a * upper[i - 1];
+        upper[i] = a / pivot;
+        d[i] = (d[i] - a * d[i - 1]) / pivot;
+    }
+    for i in (0..n - 1).rev() {
+        d[i] -= upper[i] * d[i + 1];
+    }
+}
+
+/// Periodic tridiagonal system with constant bands, by Sherman-Morrison
+fn solve_cyclic_tridiagonal(a: f64, b: f64, d: &mut [f64]) {
+    let n = d.len();
+    let gamma = -b;
+    let mut diagonal = vec![b; n];
+    diagonal[0] = b - gamma;
+    diagonal[n - 1] = b - a * a / gamma;
+    solve_tridiagonal(a, &diagonal, d);
+
+    let mut z = vec![0.0; n];
+    z[0] = gamma;
+    z[n - 1] = a;
+    solve_tridiagonal(a, &diagonal, &mut z);
+    let factor = (d[0] + a / gamma * d[n - 1]) / (1.0 + z[0] + a / gamma * z[n - 1]);
+    for (d, z) in d.iter_mut().zip(z) {
+        *d -= factor * z;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::math::grid::Domain;
+
+    use super::*;
+
+    const ACCURACIES: [Accuracy; 3] = [Accuracy::Second, Accuracy::Fourth, Accuracy::Sixth];
+
+    /// Samples on n points of [0, 1], periodic or cell-centered
+    fn sample(n: usize, periodic: bool, f: impl Fn(f64) -> f64) -> Grid<f64> {
+        let h = 1.0 / n as f64;
+        let shift = if periodic { 0.0 } else { h / 2.0 };
+        let grid = Grid::from_fn(&[n], |i| f(i[0] as f64 * h + shift));
+        grid.with_domain(Domain::new(&[shift], &[1.0 + shift]))
+    }
+
+    fn max_error(grid: &Grid<f64>, exact: &Grid<f64>) -> f64 {
+        grid.as_slice()
+            .iter()
+            .zip(exact.as_slice())
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max)
+    }
+
+    /// Observed order from errors at n and 2n samples
+    fn observed_order(error: impl Fn(usize) -> f64, n: usize) -> f64 {
+        (error(n) / error(2 * n)).log2()
+    }
+
+    #[test]
+    fn test_periodic_convergence() {
+        // u = exp(sin 2 pi x)
+        let u = |x: f64| (2.0 * PI * x).sin().exp();
+        let du = |x: f64| 2.0 * PI * (2.0 * PI * x).cos() * u(x);
+        let d2u = |x: f64| {
+            let (s, c) = (2.0 * PI * x).sin_cos();
+            4.0 * PI * PI * (c * c - s) * u(x)
+        };
+        for accuracy in ACCURACIES {
+            let fd = FiniteDifference::new(accuracy);
+            let first = |n| max_error(&fd.derivative(&sample(n, true, u), 0), &sample(n, true, du));
+            let second = |n| {
+                max_error(
+                    &fd.second_derivative(&sample(n, true, u), 0),
+                    &sample(n, true, d2u),
+                )
+            };
+            let expected = accuracy.order() as f64;
+            for order in [observed_order(first, 32), observed_order(second, 32)] {
+                assert!(
+                    (order - expected).abs() < 0.3,
+                    "{accuracy:?}: order {order}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_boundary_convergence() {
+        // u = cos 3x + x, normals -x at 0 and +x at 1
+        let u = |x: f64| (3.0 * x).cos() + x;
+        let du = |x: f64| -3.0 * (3.0 * x).sin() + 1.0;
+        let d2u = |x: f64| -9.0 * (3.0 * x).cos();
+        let robin = |x: f64, normal: f64| Boundary::Robin {
+            alpha: 2.0,
+            beta: 0.5,
+            gamma: 2.0 * u(x) + 0.5 * normal * du(x),
+        };
+        let pairs = [
+            (Boundary::Dirichlet(u(0.0)), Boundary::Neumann(du(1.0))),
+            (robin(0.0, -1.0), Boundary::Dirichlet(u(1.0))),
+            (Boundary::Neumann(-du(0.0)), robin(1.0, 1.0)),
+        ];
+        for accuracy in ACCURACIES {
+            for (lower, upper) in pairs {
+                let fd = FiniteDifference::new(accuracy).with_boundary(0, lower, upper);
+                let first = |n| {
+                    max_error(
+                        &fd.derivative(&sample(n, false, u), 0),
+                        &sample(n, false, du),
+                    )
+                };
+                let second = |n| {
+                    max_error(
+                        &fd.second_derivative(&sample(n, false, u), 0),
+                        &sample(n, false, d2u),
+                    )
+                };
+                let expected = accuracy.order() as f64;
+                for order in [observed_order(first, 32), observed_order(second, 32)] {
+                    assert!(
+                        order > expected - 0.3,
+                        "{accuracy:?} {lower:?} {upper:?}: order {order}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_laplacian_2d() {
+        // u = sin(2 pi x) sin(3 pi y / 2): periodic in x, u = 0 at y = 0, du/dy = 0 at y = 1
+        let (kx, ky) = (2.0 * PI, 1.5 * PI);
+        let u = |x: f64, y: f64| (kx * x).sin() * (ky * y).sin();
+        let grids = |n: usize| {
+            let h = 1.0 / n as f64;
+            let domain = Domain::new(&[0.0, h / 2.0], &[1.0, 1.0 + h / 2.0]);
+            let field = Grid::from_fn(&[n, n], |i| u(i[0] as f64 * h, (i[1] as f64 + 0.5) * h));
+            let exact = field.map(|u| -(kx * kx + ky * ky) * u);
+            (field.with_domain(domain.clone()), exact.with_domain(domain))
+        };
+        let fd = FiniteDifference::new(Accuracy::Fourth).with_boundary(
+            1,
+            Boundary::Dirichlet(0.0),
+            Boundary::Neumann(0.0),
+        );
+        let explicit = |n| {
+            let (field, exact) = grids(n);
+            max_error(&fd.laplacian(&field), &exact)
+        };
+        let compact = |n| {
+            let (field, exact) = grids(n);
+            max_error(&fd.compact_laplacian(&field), &exact)
+        };
+        for order in [observed_order(explicit, 16), observed_order(compact, 16)] {
+            assert!(order > 3.7, "order {order}");
+        }
+    }
+
+    #[test]
+    fn test_compact_laplacian_periodic() {
+        // Compact scheme has a smaller error constant than the explicit 4th-order one
+        let n = 24;
+        let u = |x: f64| (2.0 * PI * x).sin().exp();
+        let d2u = |x: f64| {
+            let (s, c) = (2.0 * PI * x).sin_cos();
+            4.0 * PI * PI * (c * c - s) * u(x)
+        };
+        let fd = FiniteDifference::new(Accuracy::Fourth);
+        let field = sample(n, true, u);
+        let exact = sample(n, true, d2u);
+        let compact = max_error(&fd.compact_laplacian(&field), &exact);
+        let explicit = max_error(&fd.laplacian(&field), &exact);
+        assert!(compact < 0.5 * explicit, "{compact} vs {explicit}");
+        let order = observed_order(
+            |n| {
+                max_error(
+                    &fd.compact_laplacian(&sample(n, true, u)),
+                    &sample(n, true, d2u),
+                )
+            },
+            32,
+        );
+        assert!((order - 4.0).abs() < 0.3, "order {order}");
+    }
+
+    #[test]
+    #[should_panic(expected = "Periodic boundaries must be paired")]
+    fn test_unpaired_periodic() {
+        let _ = FiniteDifference::new(Accuracy::Second).with_boundary(
+            0,
+            Boundary::Periodic,
+            Boundary::Dirichlet(0.0),
+        );
+    }
+
+    #[test]
+    fn test_cyclic_tridiagonal() {
+        let n = 7;
+        let x: Vec<f64> = (0..n).map(|i| (i as f64).sin()).collect();
+        let mut d: Vec<f64> = (0..n)
+            .map(|i| 0.1 * x[(i + n - 1) % n] + x[i] + 0.1 * x[(i + 1) % n])
+            .collect();
+        solve_cyclic_tridiagonal(0.1, 1.0, &mut d);
+        assert!(d.iter().zip(&x).all(|(a, b)| (a - b).abs() < 1e-14));
+    }
+}
@@ -0,0 +1,644 @@
+use std::ops::{Add, Div, Index, IndexMut, Mul, Neg, Sub};
+
+use crate::math::{
+    complex::Complex,
+    core::{LinearSpace, ScalarSpace, Vector},
+    dct,
+    fft::{FftPlan, fft_axis, irfftn, rfftn},
+    field::Field,
+};
+
+/// Physical extent of a periodic grid, $$ min_i \leq x_i < max_i $$
+#[derive(Clone, Debug, PartialEq)]
+pub struct Domain {
+    min: Vec<f64>,
+    max: Vec<f64>,
+}
+
+impl Domain {
+    pub fn new(min: &[f64], max: &[f64]) -> Self {
+        if min.len() != max.len() || min.is_empty() {
+            panic!("Domain bounds {min:?} and {max:?} must have the same positive length");
+        }
+        if min.iter().zip(max).any(|(a, b)| a >= b) {
+            panic!("Domain needs min < max on every axis, got {min:?} and {max:?}");
+        }
+        Self {
+            min: min.to_vec(),
+            max: max.to_vec(),
+        }
+    }
+
+    /// $$ [0, L)^{ndim} $$
+    pub fn cube(ndim: usize, length: f64) -> Self {
+        Self::new(&vec![0.0; ndim], &vec![length; ndim])
+    }
+
+    pub fn ndim(&self) -> usize {
+        self.min.len()
+    }
+
+    pub fn min(&self) -> &[f64] {
+        &self.min
+    }
+
+    pub fn max(&self) -> &[f64] {
+        &self.max
+    }
+
+    /// Box lengths $$ L_i = max_i - min_i $$
+    pub fn lengths(&self) -> Vec<f64> {
+        self.min.iter().zip(&self.max).map(|(a, b)| b - a).collect()
+    }
+
+    pub fn volume(&self) -> f64 {
+        self.lengths().iter().product()
+    }
+}
+
+/// Contiguous heap-backed grid with runtime dimensions
+///
+/// Row-major: the last axis is contiguous and `strides[i]` is the distance
+/// between neighbours along axis `i`. Every axis may have its own length.
+/// Sample `i` lies at $$ min + i \Delta x $$ of the `Domain`, which defaults to
+/// unit spacing from the origin.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<T: LinearSpace + Copy = Complex> {
+    shape: Vec<usize>,
+    strides: Vec<usize>,
+    domain: Domain,
+    data: Vec<T>,
+}
+
+impl<T: LinearSpace + Copy> Grid<T> {
+    /// Zero-filled grid
+    pub fn new(shape: &[usize]) -> Self {
+        Self::from_vec(shape, vec![T::zero(); shape.iter().product()])
+    }
+
+    pub fn from_vec(shape: &[usize], data: Vec<T>) -> Self {
+        if shape.is_empty() {
+            panic!("Grid needs at least one axis");
+        }
+        if shape.iter().product::<usize>() != data.len() {
+            panic!("Grid of shape {shape:?} cannot hold {} values", data.len());
+        }
+        let mut strides = vec![1; shape.len()];
+        for axis in (0..shape.len() - 1).rev() {
+            strides[axis] = strides[axis + 1] * shape[axis + 1];
+        }
+        let extent: Vec<f64> = shape.iter().map(|&n| n.max(1) as f64).collect();
+        Self {
+            shape: shape.to_vec(),
+            strides,
+            domain: Domain::new(&vec![0.0; shape.len()], &extent),
+            data,
+        }
+    }
+
+    pub fn with_domain(mut self, domain: Domain) -> Self {
+        if domain.ndim() != self.ndim() {
+            panic!(
+                "Domain of {} axes attached to a grid of shape {:?}",
+                domain.ndim(),
+                self.shape
+            );
+        }
+        self.domain = domain;
+        self
+    }
+
+    pub fn from_fn(shape: &[usize], f: impl Fn(&[usize]) -> T) -> Self {
+        let mut grid = Self::new(shape);
+        let mut index = vec![0; shape.len()];
+        for offset in 0..grid.len() {
+            grid.data[offset] = f(&index);
+            grid.advance(&mut index);
+        }
+        grid
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn strides(&self) -> &[usize] {
+        &self.strides
+    }
+
+    pub fn ndim(&self) -> usize {
+        self.shape.len()
+    }
+
+    pub fn domain(&self) -> &Domain {
+        &self.domain
+    }
+
+    /// Grid spacing $$ \Delta x_i = L_i / N_i $$
+    pub fn spacing(&self) -> Vec<f64> {
+        self.domain
+            .lengths()
+            .iter()
+            .zip(&self.shape)
+            .map(|(l, &n)| l / n as f64)
+            .collect()
+    }
+
+    pub fn cell_volume(&self) -> f64 {
+        self.spacing().iter().product()
+    }
+
+    /// Sample coordinates along `axis`
+    pub fn coordinates(&self, axis: usize) -> Vec<f64> {
+        let dx = self.spacing()[axis];
+        (0..self.shape[axis])
+            .map(|i| self.domain.min[axis] + i as f64 * dx)
+            .collect()
+    }
+
+    /// Physical position of a multi-index
+    pub fn position(&self, index: &[usize]) -> Vec<f64> {
+        index
+            .iter()
+            .zip(self.spacing())
+            .zip(&self.domain.min)
+            .map(|((&i, dx), min)| min + i as f64 * dx)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    /// Offset of a multi-index in the data
+    pub fn offset(&self, index: &[usize]) -> usize {
+        if index.len() != self.ndim() || index.iter().zip(&self.shape).any(|(i, n)| i >= n) {
+            panic!("Index {index:?} out of bounds for shape {:?}", self.shape);
+        }
+        index.iter().zip(&self.strides).map(|(i, s)| i * s).sum()
+    }
+
+    /// Multi-index of an offset in the data
+    pub fn unravel(&self, offset: usize) -> Vec<usize> {
+        self.shape
+            .iter()
+            .zip(&self.strides)
+            .map(|(n, s)| offset / s % n)
+            .collect()
+    }
+
+    pub fn map<U: LinearSpace + Copy>(&self, f: impl Fn(T) -> U) -> Grid<U> {
+        Grid::from_vec(&self.shape, self.data.iter().map(|&x| f(x)).collect())
+            .with_domain(self.domain.clone())
+    }
+
+    /// Next multi-index in row-major order
+    fn advance(&self, index: &mut [usize]) {
+        for axis in (0..index.len()).rev() {
+            index[axis] += 1;
+            if index[axis] < self.shape[axis] {
+                return;
+            }
+            index[axis] = 0;
+        }
+    }
+
+    fn check_shape(&self, shape: &[usize]) {
+        if self.shape != shape {
+            panic!("Expected a grid of shape {shape:?}, got {:?}", self.shape);
+        }
+    }
+}
+
+impl<T: LinearSpace + Copy, const D: usize> Index<[usize; D]> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, index: [usize; D]) -> &Self::Output {
+        &self.data[self.offset(&index)]
+    }
+}
+
+impl<T: LinearSpace + Copy, const D: usize> IndexMut<[usize; D]> for Grid<T> {
+    fn index_mut(&mut self, index: [usize; D]) -> &mut Self::Output {
+        let offset = self.offset(&index);
+        &mut self.data[offset]
+    }
+}
+
+impl<T: LinearSpace + Copy> Neg for Grid<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self.map(|x| -x)
+    }
+}
+
+impl<T: LinearSpace + Copy> Add for Grid<T> {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self.check_shape(&rhs.shape);
+        for (a, b) in self.data.iter_mut().zip(rhs.data) {
+            *a = *a + b;
+        }
+        self
+    }
+}
+
+impl<T: LinearSpace + Copy> Sub for Grid<T> {
+    type Output = Self;
+
+    fn sub(mut self, rhs: Self) -> Self::Output {
+        self.check_shape(&rhs.shape);
+        for (a, b) in self.data.iter_mut().zip(rhs.data) {
+            *a = *a - b;
+        }
+        self
+    }
+}
+
+impl<T: LinearSpace + Copy> Mul<f64> for Grid<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        self.map(|x| x * rhs)
+    }
+}
+
+impl<T: LinearSpace + Copy> Div<f64> for Grid<T> {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        self.map(|x| x / rhs)
+    }
+}
+
+impl Grid<Complex> {
+    /// One FFT plan per axis, for `fft_with_plans`
+    pub fn plans(&self) -> Vec<FftPlan> {
+        self.shape.iter().map(|&n| FftPlan::new(n)).collect()
+    }
+
+    /// FFT along every axis with the plan of that axis' length
+    pub fn fft_with_plans(&mut self, plans: &[FftPlan]) -> &mut Self {
+        self.transform_axes(plans, false)
+    }
+
+    /// IFFT along every axis with the plan of that axis' length
+    pub fn ifft_with_plans(&mut self, plans: &[FftPlan]) -> &mut Self {
+        self.transform_axes(plans, true)
+    }
+
+    fn transform_axes(&mut self, plans: &[FftPlan], inverse: bool) -> &mut Self {
+        let lengths: Vec<usize> = plans.iter().map(|plan| plan.len()).collect();
+        self.check_shape(&lengths);
+        for (axis, plan) in plans.iter().enumerate() {
+            fft_axis(&mut self.data, &self.shape, axis, plan, inverse);
+        }
+        self
+    }
+}
+
+impl Field for Grid<Complex> {
+    type Real = Grid<f64>;
+
+    fn fft(&mut self) -> &mut Self {
+        self.fft_with_plans(&self.plans())
+    }
+    fn ifft(&mut self) -> &mut Self {
+        self.ifft_with_plans(&self.plans())
+    }
+    fn fft_with(&mut self, plan: &FftPlan) -> &mut Self {
+        self.check_shape(&vec![plan.len(); self.ndim()]);
+        for axis in 0..self.ndim() {
+            fft_axis(&mut self.data, &self.shape, axis, plan, false);
+        }
+        self
+    }
+    fn ifft_with(&mut self, plan: &FftPlan) -> &mut Self {
+        self.check_shape(&vec![plan.len(); self.ndim()]);
+        for axis in 0..self.ndim() {
+            fft_axis(&mut self.data, &self.shape, axis, plan, true);
+        }
+        self
+    }
+    fn rfft(real: &Self::Real) -> Vec<Complex> {
+        rfftn(&real.data, &real.shape)
+    }
+    fn irfft(spectrum: &[Complex], real: &mut Self::Real) {
+        real.data = irfftn(spectrum, &real.shape);
+    }
+    fn map_axes(&mut self, transform: &dyn Fn(&[f64]) -> Vec<f64>) -> &mut Self {
+        dct::map_axes(&mut self.data, &self.shape, transform);
+        self
+    }
+    fn shape(&self) -> Vec<usize> {
+        self.shape.clone()
+    }
+    fn lengths(&self) -> Vec<f64> {
+        self.domain.lengths()
+    }
+    fn map_indexed(&mut self, f: &dyn Fn(&[usize], Complex) -> Complex) -> &mut Self {
+        let mut index = vec![0; self.ndim()];
+        for offset in 0..self.len() {
+            self.data[offset] = f(&index, self.data[offset]);
+            self.advance(&mut index);
+        }
+        self
+    }
+}
+
+impl<T: ScalarSpace + Copy, const N: usize> From<Vector<T, N>> for Grid<T> {
+    fn from(field: Vector<T, N>) -> Self {
+        Self::from_vec(&[N], field.as_slice().to_vec())
+    }
+}
+
+impl<T: ScalarSpace + Copy, const N: usize> From<Vector<Vector<T, N>, N>> for Grid<T> {
+    fn from(field: Vector<Vector<T, N>, N>) -> Self {
+        let data = field
+            .as_slice()
+            .iter()
+            .flat_map(|row| row.as_slice().to_vec())
+            .collect();
+        Self::from_vec(&[N, N], data)
+    }
+}
+
+impl<T: ScalarSpace + Copy, const N: usize> From<Vector<Vector<Vector<T, N>, N>, N>> for Grid<T> {
+    fn from(field: Vector<Vector<Vector<T, N>, N>, N>) -> Self {
+        let data = field
+            .as_slice()
+            .iter()
+            .flat_map(|plane| {
+                plane
+                    .as_slice()
+                    .iter()
+                    .flat_map(|row| row.as_slice().to_vec())
+            })
+            .collect();
+        Self::from_vec(&[N, N, N], data)
+    }
+}
+
+/// Panics unless the grid has shape `[N]`
+impl<T: ScalarSpace + Copy, const N: usize> From<Grid<T>> for Vector<T, N> {
+    fn from(grid: Grid<T>) -> Self {
+        grid.check_shape(&[N]);
+        Vector::new(std::array::from_fn(|x| grid.data[x]))
+    }
+}
+
+/// Panics unless the grid has shape `[N, N]`
+impl<T: ScalarSpace + Copy, const N: usize> From<Grid<T>> for Vector<Vector<T, N>, N> {
+    fn from(grid: Grid<T>) -> Self {
+        grid.check_shape(&[N, N]);
+        Vector::new(std::array::from_fn(|x| {
+            Vector::new(std::array::from_fn(|y| grid[[x, y]]))
+        }))
+    }
+}
+
+/// Panics unless the grid has shape `[N, N, N]`
+impl<T: ScalarSpace + Copy, const N: usize> From<Grid<T>> for Vector<Vector<Vector<T, N>, N>, N> {
+    fn from(grid: Grid<T>) -> Self {
+        grid.check_shape(&[N, N, N]);
+        Vector::new(std::array::from_fn(|x| {
+            Vector::new(std::array::from_fn(|y| {
+                Vector::new(std::array::from_fn(|z| grid[[x, y, z]]))
+            }))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::complex::ComplexSpace;
+    use crate::math::dct::Kind;
+    use crate::math::field::{Field2D, Field3D, RealField3D};
+
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let grid = Grid::<f64>::from_fn(&[2, 3, 4], |i| (100 * i[0] + 10 * i[1] + i[2]) as f64);
+        assert_eq!(grid.strides(), &[12, 4, 1]);
+        assert_eq!(grid.len(), 24);
+        assert_eq!(grid[[1, 2, 3]], 123.0);
+        assert_eq!(grid.as_slice()[grid.offset(&[1, 0, 2])], 102.0);
+        assert_eq!(grid.unravel(23), vec![1, 2, 3]);
+        assert_eq!(grid.map(|x| 2.0 * x)[[0, 1, 1]], 22.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_out_of_bounds() {
+        let grid = Grid::<f64>::new(&[2, 3]);
+        let _ = grid[[0, 3]];
+    }
+
+    #[test]
+    fn test_field_conversion() {
+        const N: usize = 4;
+        let mut field = Field3D::<N>::zero();
+        field[1][2][3] = Complex::new(1.0, -1.0);
+        field[3][0][1] = Complex::new(0.5, 2.0);
+
+        let grid = Grid::from(field);
+        assert_eq!(grid.shape(), &[N, N, N]);
+        assert_eq!(grid[[1, 2, 3]], Complex::new(1.0, -1.0));
+        assert_eq!(Field3D::<N>::from(grid), field);
+
+        let square = Field2D::<N>::from(Grid::from_fn(&[N, N], |i| {
+            Complex::new(i[0] as f64, i[1] as f64)
+        }));
+        assert_eq!(square[2][3], Complex::new(2.0, 3.0));
+    }
+
+    #[test]
+    fn test_fft_matches_field() {
+        const N: usize = 6;
+        let mut field = Field3D::<N>::zero();
+        let mut real = RealField3D::<N>::zero();
+        for x in 0..N {
+            for y in 0..N {
+                for z in 0..N {
+                    real[x][y][z] = ((x * 5 + y * y + 2 * z) % 7) as f64;
+                    field[x][y][z] = Complex::new(real[x][y][z], 0.5 * x as f64);
+                }
+            }
+        }
+        let mut grid = Grid::from(field);
+
+        field.fft();
+        grid.fft();
+        let expected = Grid::from(field);
+        for (a, b) in grid.as_slice().iter().zip(expected.as_slice()) {
+            assert!((*a - *b).abs() < 1e-12);
+        }
+
+        let real_grid = Grid::from(real);
+        let spectrum = Grid::rfft(&real_grid);
+        assert_eq!(spectrum, Field3D::<N>::rfft(&real));
+        let mut restored = Grid::<f64>::new(&[N, N, N]);
+        Grid::irfft(&spectrum, &mut restored);
+        for (a, b) in restored.as_slice().iter().zip(real_grid.as_slice()) {
+            assert!((a - b).abs() < 1e-12);
+        }
+
+        field.ifft();
+        grid.ifft().dst(Kind::II).idst(Kind::II);
+        for (a, b) in grid.as_slice().iter().zip(Grid::from(field).as_slice()) {
+            assert!((*a - *b).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_large_grid() {
+        // 128^3 complex values live on the heap
+        let n = 128;
+        let mut grid = Grid::<Complex>::new(&[n, n, n]);
+        grid[[0, 0, 0]] = Complex::one();
+        grid.fft_with(&FftPlan::new(n));
+        assert!(
+            grid.as_slice()
+                .iter()
+                .all(|z| (*z - Complex::one()).abs() < 1e-12)
+        );
+
+        grid.ifft();
+        assert!((grid[[0, 0, 0]] - Complex::one()).abs() < 1e-12);
+        assert!(grid[[5, 7, 9]].abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_domain() {
+        let domain = Domain::new(&[-1.0, 0.0, 2.0], &[1.0, 3.0, 2.5]);
+        assert_eq!(domain.lengths(), vec![2.0, 3.0, 0.5]);
+        assert!((domain.volume() - 3.0).abs() < 1e-15);
+
+        let grid = Grid::<f64>::new(&[4, 6, 5]).with_domain(domain);
+        assert_eq!(grid.spacing(), vec![0.5, 0.5, 0.1]);
+        assert!((grid.cell_volume() - 0.025).abs() < 1e-15);
+        assert_eq!(grid.coordinates(0), vec![-1.0, -0.5, 0.0, 0.5]);
+        let position = grid.position(&[3, 2, 4]);
+        assert!((position[0] - 0.5).abs() < 1e-15);
+        assert!((position[1] - 1.0).abs() < 1e-15);
+        assert!((position[2] - 2.4).abs() < 1e-15);
+        assert_eq!(grid.map(|x| x + 1.0).domain(), grid.domain());
+
+        assert_eq!(Grid::<f64>::new(&[8, 2]).spacing(), vec![1.0, 1.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Domain of 2 axes")]
+    fn test_domain_mismatch() {
+        let _ = Grid::<f64>::new(&[4, 4, 4]).with_domain(Domain::cube(2, 1.0));
+    }
+
+    #[test]
+    fn test_rectangular_fft() {
+        // exp(2 pi i (k . x) / L) on a [5, 8, 3] grid in a [2, 1, 3] box
+        let (shape, wave) = ([5, 8, 3], [2, 7, 1]);
+        let domain = Domain::new(&[0.0; 3], &[2.0, 1.0, 3.0]);
+        let lengths = domain.lengths();
+        let grid = Grid::<f64>::new(&shape).with_domain(domain);
+        let mut field = Grid::<Complex>::from_fn(&shape, |i| {
+            let x = grid.position(i);
+            let phase: f64 = (0..3)
+                .map(|axis| wave[axis] as f64 * x[axis] / lengths[axis])
+                .sum();
+            Complex::cis(2.0 * std::f64::consts::PI * phase)
+        });
+
+        let original = field.clone();
+        let plans = field.plans();
+        field.fft_with_plans(&plans);
+        for offset in 0..field.len() {
+            let index = field.unravel(offset);
+            let expected = if index == wave { 120.0 } else { 0.0 };
+            assert!(
+                (field.as_slice()[offset] - Complex::new(expected, 0.0)).abs() < 1e-10,
+                "k = {index:?}"
+            );
+        }
+
+        field.ifft_with_plans(&plans);
+        for (a, b) in field.as_slice().iter().zip(original.as_slice()) {
+            assert!((*a - *b).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected a grid of shape [6, 6]")]
+    fn test_cubic_plan_on_rectangular_grid() {
+        Grid::<Complex>::new(&[6, 4]).fft_with(&FftPlan::new(6));
+    }
+
+    #[test]
+    fn test_spectral_operators_on_box() {
+        // f = sin(2 pi x / 3) cos(2 pi y) sin(4 pi z / 5) in [0, 3) x [-1, 1) x [0, 5)
+        use std::f64::consts::PI;
+        let shape = [16, 12, 10];
+        let domain = Domain::new(&[0.0, -1.0, 0.0], &[3.0, 1.0, 5.0]);
+        let k = [2.0 * PI / 3.0, 2.0 * PI, 4.0 * PI / 5.0];
+        let grid = Grid::<f64>::new(&shape).with_domain(domain.clone());
+        let f = |x: &[f64]| (k[0] * x[0]).sin() * (k[1] * x[1]).cos() * (k[2] * x[2]).sin();
+        let field = Grid::<Complex>::from_fn(&shape, |i| Complex::new(f(&grid.position(i)), 0.0))
+            .with_domain(domain);
+
+        let gradient = field.gradient();
+        let mut laplacian = field.clone();
+        laplacian.laplacian();
+        let divergence = Grid::divergence(&gradient);
+        let k_sq: f64 = k.iter().map(|k| k * k).sum();
+        for offset in 0..field.len() {
+            let x = grid.position(&field.unravel(offset));
+            let (sx, cx) = (k[0] * x[0]).sin_cos();
+            let (sy, cy) = (k[1] * x[1]).sin_cos();
+            let (sz, cz) = (k[2] * x[2]).sin_cos();
+            let expected = [
+                k[0] * cx * cy * sz,
+                -k[1] * sx * sy * sz,
+                k[2] * sx * cy * cz,
+            ];
+            for axis in 0..3 {
+                assert!(
+                    (gradient[axis].as_slice()[offset] - Complex::new(expected[axis], 0.0)).abs()
+                        < 1e-10
+                );
+            }
+            let expected = Complex::new(-k_sq * f(&x), 0.0);
+            assert!((laplacian.as_slice()[offset] - expected).abs() < 1e-9);
+            assert!((divergence.as_slice()[offset] - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Grid::from_vec(&[2, 2], vec![1.0, 2.0, 3.0, 4.0]);
+        let b = Grid::from_vec(&[2, 2], vec![0.5; 4]);
+        assert_eq!((a.clone() + b.clone()).into_vec(), vec![1.5, 2.5, 3.5, 4.5]);
+        assert_eq!((a.clone() - b).into_vec(), vec![0.5, 1.5, 2.5, 3.5]);
+        assert_eq!(
+            (-a.clone() * 2.0 / 4.0).into_vec(),
+            vec![-0.5, -1.0, -1.5, -2.0]
+        );
+    }
+}
@@ -1,12 +1,241 @@
-use crate::math::core::LinearSpace;
+pub mod adaptive;
+pub mod dense;
+pub mod event;
+pub mod extrapolation;
+pub mod implicit;
+pub mod multistep;
+pub mod observer;
+pub mod stochastic;
+pub mod symplectic;
+
+use crate::math::core::{LinearSpace, Vector};
+use crate::math::integrate::adaptive::AdaptiveIntegrator;
+use crate::math::integrate::dense::{DenseStep, StepPolynomial};
+use crate::math::integrate::event::{Event, EventRecord};
+use crate::math::integrate::implicit::{Newton, implicit_midpoint};
+use crate::math::integrate::observer::Observer;
 
 pub trait System {
     type Vector: LinearSpace + Copy;
 
     fn derivative(&self, t: f64, y: Self::Vector, y_prime: Self::Vector) -> Self::Vector;
+
+    /// Analytic Jacobian of `derivative`, used by the implicit integrators.
+    ///
+    /// Returns `None` (default) to let the integrator use finite differences.
+    fn jacobian(&self, _t: f64, _y: Self::Vector, _y_prime: Self::Vector) -> Option<Jacobian> {
+        None
+    }
+}
+
+/// First-order ODE system
+///
+/// $$ y' = f(t, y) $$
+pub trait FirstOrderSystem {
+    type Vector: LinearSpace + Copy;
+
+    fn derivative(&self, t: f64, y: Self::Vector) -> Self::Vector;
+
+    /// Analytic Jacobian of `derivative`, row-major over the real components
+    /// (`LinearSpace::components`), used by the implicit integrators.
+    ///
+    /// Returns `None` (default) to let the integrator use finite differences.
+    fn jacobian(&self, _t: f64, _y: Self::Vector) -> Option<Vec<f64>> {
+        None
+    }
+}
+
+/// Jacobian of $$ y'' = f(t, y, y') $$
+///
+/// Row-major `n x n` matrices over the real components (`LinearSpace::components`),
+/// `df_dy[i * n + j]` is $$ \partial f_i / \partial y_j $$
+#[derive(Clone, Debug)]
+pub struct Jacobian {
+    pub df_dy: Vec<f64>,
+    pub df_dy_prime: Vec<f64>,
+}
+
+impl<S: System> System for &S {
+    type Vector = S::Vector;
+
+    fn derivative(&self, t: f64, y: Self::Vector, y_prime: Self::Vector) -> Self::Vector {
+        (*self).derivative(t, y, y_prime)
+    }
+    fn jacobian(&self, t: f64, y: Self::Vector, y_prime: Self::Vector) -> Option<Jacobian> {
+        (*self).jacobian(t, y, y_prime)
+    }
+}
+
+impl<F: FirstOrderSystem> FirstOrderSystem for &F {
+    type Vector = F::Vector;
+
+    fn derivative(&self, t: f64, y: Self::Vector) -> Self::Vector {
+        (*self).derivative(t, y)
+    }
+    fn jacobian(&self, t: f64, y: Self::Vector) -> Option<Vec<f64>> {
+        (*self).jacobian(t, y)
+    }
+}
+
+/// Phase-space state `[y, y']` of a 2nd-order system
+pub type Phase<V> = Vector<V, 2>;
+
+/// Adapter running a 2nd-order `System` as a `FirstOrderSystem` on the phase space
+///
+/// $$ (y, y')' = (y', f(t, y, y')) $$
+pub struct SecondOrder<S: System>(pub S);
+
+impl<S: System> FirstOrderSystem for SecondOrder<S> {
+    type Vector = Phase<S::Vector>;
+
+    fn derivative(&self, t: f64, z: Self::Vector) -> Self::Vector {
+        Vector::new([z[1], self.0.derivative(t, z[0], z[1])])
+    }
+
+    /// $$ \begin{pmatrix} 0 & I \\ \partial f / \partial y & \partial f / \partial y' \end{pmatrix} $$
+    fn jacobian(&self, t: f64, z: Self::Vector) -> Option<Vec<f64>> {
+        let jacobian = self.0.jacobian(t, z[0], z[1])?;
+        let n = S::Vector::dim();
+        let mut m = vec![0.0; 4 * n * n];
+        for i in 0..n {
+            m[i * 2 * n + n + i] = 1.0;
+            for j in 0..n {
+                m[(n + i) * 2 * n + j] = jacobian.df_dy[i * n + j];
+                m[(n + i) * 2 * n + n + j] = jacobian.df_dy_prime[i * n + j];
+            }
+        }
+        Some(m)
+    }
+}
+
+/// A `FirstOrderSystem` driven by `Solver`
+///
+/// `Solver` tracks `(y, y')` with $$ y' = f(t, y) $$ so that `Results` has the
+/// same layout for first and second-order systems.
+pub struct FirstOrder<F: FirstOrderSystem>(pub F);
+
+/// Dynamics advanced by `Solver` as `(y, y')` pairs
+///
+/// Implemented for every 2nd-order `System` and for `FirstOrder` systems.
+pub trait Problem {
+    type Vector: LinearSpace + Copy;
+
+    fn advance<I: Integrator>(
+        &self,
+        integrator: &mut I,
+        t: f64,
+        y: Self::Vector,
+        y_prime: Self::Vector,
+        h: f64,
+    ) -> (Self::Vector, Self::Vector);
+
+    fn advance_adaptive<I: AdaptiveIntegrator>(
+        &self,
+        integrator: &mut I,
+        t: f64,
+        y: Self::Vector,
+        y_prime: Self::Vector,
+        h_max: f64,
+    ) -> (f64, Self::Vector, Self::Vector);
+
+    /// Converts the `Integrator::dense_output` of the last step into `(y, y')` form
+    fn dense_step(&self, polynomial: &StepPolynomial) -> DenseStep<Self::Vector>;
+
+    /// $$ y'' $$ if the problem is 2nd-order, used to interpolate $$ y' $$ inside a step
+    fn second_derivative(
+        &self,
+        _t: f64,
+        _y: Self::Vector,
+        _y_prime: Self::Vector,
+    ) -> Option<Self::Vector> {
+        None
+    }
+}
+
+impl<S: System> Problem for S {
+    type Vector = S::Vector;
+
+    fn advance<I: Integrator>(
+        &self,
+        integrator: &mut I,
+        t: f64,
+        y: Self::Vector,
+        y_prime: Self::Vector,
+        h: f64,
+    ) -> (Self::Vector, Self::Vector) {
+        integrator.step(self, t, y, y_prime, h)
+    }
+
+    fn advance_adaptive<I: AdaptiveIntegrator>(
+        &self,
+        integrator: &mut I,
+        t: f64,
+        y: Self::Vector,
+        y_prime: Self::Vector,
+        h_max: f64,
+    ) -> (f64, Self::Vector, Self::Vector) {
+        integrator.adaptive_step(self, t, y, y_prime, h_max)
+    }
+
+    fn dense_step(&self, polynomial: &StepPolynomial) -> DenseStep<Self::Vector> {
+        DenseStep::from_phase(polynomial)
+    }
+
+    fn second_derivative(
+        &self,
+        t: f64,
+        y: Self::Vector,
+        y_prime: Self::Vector,
+    ) -> Option<Self::Vector> {
+        Some(self.derivative(t, y, y_prime))
+    }
+}
+
+impl<F: FirstOrderSystem> Problem for FirstOrder<F> {
+    type Vector = F::Vector;
+
+    fn advance<I: Integrator>(
+        &self,
+        integrator: &mut I,
+        t: f64,
+        y: Self::Vector,
+        _y_prime: Self::Vector,
+        h: f64,
+    ) -> (Self::Vector, Self::Vector) {
+        let y = integrator.step_first_order(&self.0, t, y, h);
+        let y_prime = match integrator.last_derivative() {
+            Some(components) => F::Vector::from_components(components),
+            None => self.0.derivative(t + h, y),
+        };
+        (y, y_prime)
+    }
+
+    fn advance_adaptive<I: AdaptiveIntegrator>(
+        &self,
+        integrator: &mut I,
+        t: f64,
+        y: Self::Vector,
+        _y_prime: Self::Vector,
+        h_max: f64,
+    ) -> (f64, Self::Vector, Self::Vector) {
+        let (h, y) = integrator.adaptive_step_first_order(&self.0, t, y, h_max);
+        (h, y, self.0.derivative(t + h, y))
+    }
+
+    fn dense_step(&self, polynomial: &StepPolynomial) -> DenseStep<Self::Vector> {
+        DenseStep::from_first_order(polynomial)
+    }
 }
 
 pub trait Integrator {
+    /// Advances the first-order system $$ y' = f(t, y) $$ by `h`
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
+    where
+        F: FirstOrderSystem;
+
+    /// Advances the 2nd-order system $$ y'' = f(t, y, y') $$ by `h`
+    ///
+    /// Defaults to `step_first_order` on the `SecondOrder` phase-space adapter.
     fn step<S>(
         &mut self,
         system: &S,
@@ -16,56 +245,150 @@ pub trait Integrator {
         h: f64,
     ) -> (S::Vector, S::Vector)
     where
-        S: System;
+        S: System,
+    {
+        let z = self.step_first_order(&SecondOrder(system), t, Vector::new([y, y_prime]), h);
+        (z[0], z[1])
+    }
+
+    /// Components of $$ f(t + h, y) $$ from the last `step_first_order`, if the
+    /// method already evaluated it, sparing `Solver` another evaluation
+    fn last_derivative(&self) -> Option<&[f64]> {
+        None
+    }
+
+    /// Continuous extension of the last step, if the method has one
+    ///
+    /// For 2nd-order systems the polynomial is over the phase-space state $$ (y, y') $$.
+    fn dense_output(&self) -> Option<&StepPolynomial> {
+        None
+    }
 }
 
-pub struct Solver<I, S>
+/// Sampled `(ts, ys, ys_prime)` of a solved trajectory
+pub type Trajectory<V> = (Vec<f64>, Vec<V>, Vec<V>);
+
+/// Drives an `Integrator` over a `Problem`, passing every step to an `Observer`
+///
+/// The default observer stores the whole trajectory in `Results`.
+pub struct Solver<I, S, O = Results<<S as Problem>::Vector>>
 where
     I: Integrator,
-    S: System,
+    S: Problem,
+    O: Observer<S::Vector>,
 {
     integrator: I,
     system: S,
     t: f64,
     y: S::Vector,
     y_prime: S::Vector,
-    results: Results<S>,
+    events: Vec<Event<S::Vector>>,
+    terminated: bool,
+    observer: O,
 }
 
 #[derive(Clone)]
-pub struct Results<S: System> {
+pub struct Results<V: LinearSpace> {
     ts: Vec<f64>,
-    ys: Vec<S::Vector>,
-    ys_prime: Vec<S::Vector>,
+    ys: Vec<V>,
+    ys_prime: Vec<V>,
+    dense: Vec<Option<DenseStep<V>>>,
+    events: Vec<EventRecord<V>>,
 }
 
-impl<S: System> Results<S> {
-    pub fn new(t0: f64, y0: S::Vector, y0_prime: S::Vector) -> Self {
+impl<V: LinearSpace> Results<V> {
+    /// Results without samples, filled by `Solver` starting from the initial state
+    pub fn empty() -> Self {
+        Self {
+            ts: Vec::new(),
+            ys: Vec::new(),
+            ys_prime: Vec::new(),
+            dense: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn new(t0: f64, y0: V, y0_prime: V) -> Self {
         Self {
             ts: vec![t0],
             ys: vec![y0],
             ys_prime: vec![y0_prime],
+            dense: Vec::new(),
+            events: Vec::new(),
         }
     }
 
-    pub fn update(&mut self, t: f64, y: S::Vector, y_prime: S::Vector) {
+    pub fn record_event(&mut self, record: EventRecord<V>) {
+        self.events.push(record);
+    }
+
+    pub fn get_events(&self) -> &[EventRecord<V>] {
+        &self.events
+    }
+
+    pub fn update(&mut self, t: f64, y: V, y_prime: V) {
         self.ts.push(t);
         self.ys.push(y);
         self.ys_prime.push(y_prime);
+        if self.ts.len() > 1 {
+            self.dense.push(None);
+        }
+    }
+
+    /// Records a step together with the integrator's interpolant over it
+    pub fn update_dense(&mut self, t: f64, y: V, y_prime: V, step: DenseStep<V>) {
+        self.update(t, y, y_prime);
+        if let Some(last) = self.dense.last_mut() {
+            *last = Some(step);
+        }
     }
 
-    pub fn get(&self, index: usize) -> (f64, S::Vector, S::Vector) {
+    /// `(y, y')` at any `t` covered by the results
+    ///
+    /// Uses the integrator's interpolant of the step when it was recorded,
+    /// otherwise the Hermite cubic through the stored `y` and `y'`.
+    pub fn eval(&self, t: f64) -> (V, V) {
+        let (t_first, t_last) = (self.ts[0], *self.ts.last().unwrap());
+        if !(t_first..=t_last).contains(&t) {
+            panic!("Results::eval: t = {t} outside [{t_first}, {t_last}]");
+        }
+        if self.ts.len() == 1 {
+            return (self.ys[0], self.ys_prime[0]);
+        }
+        let i = (self.ts.partition_point(|&s| s <= t) - 1).min(self.ts.len() - 2);
+        match &self.dense[i] {
+            Some(step) => step.eval(t),
+            None => DenseStep::hermite(self.get(i), self.get(i + 1), None).eval(t),
+        }
+    }
+
+    /// Interpolated `(ts, ys, ys_prime)` on the time grid `ts`
+    pub fn resample(&self, ts: &[f64]) -> Trajectory<V> {
+        let (ys, ys_prime) = ts.iter().map(|&t| self.eval(t)).unzip();
+        (ts.to_vec(), ys, ys_prime)
+    }
+
+    /// Resamples onto `n` evenly spaced times from the first to the last sample
+    pub fn resample_uniform(&self, n: usize) -> Trajectory<V> {
+        let (t_first, t_last) = (self.ts[0], *self.ts.last().unwrap());
+        let dt = (t_last - t_first) / (n.max(2) - 1) as f64;
+        let ts: Vec<f64> = (0..n)
+            .map(|i| {
+                if i == n - 1 {
+                    t_last
+                } else {
+                    t_first + i as f64 * dt
+                }
+            })
+            .collect();
+        self.resample(&ts)
+    }
+
+    pub fn get(&self, index: usize) -> (f64, V, V) {
         (self.ts[index], self.ys[index], self.ys_prime[index])
     }
 
-    pub fn get_f64(
-        &self,
-        index: usize,
-    ) -> (
-        f64,
-        <S::Vector as LinearSpace>::Data,
-        <S::Vector as LinearSpace>::Data,
-    ) {
+    pub fn get_f64(&self, index: usize) -> (f64, V::Data, V::Data) {
         (
             self.ts[index],
             self.ys[index].get_data(),
@@ -81,19 +404,19 @@ impl<S: System> Results<S> {
         self.ts.clone()
     }
 
-    pub fn get_ys(&self) -> Vec<S::Vector> {
+    pub fn get_ys(&self) -> Vec<V> {
         self.ys.clone()
     }
 
-    pub fn get_ys_f64(&self) -> Vec<<S::Vector as LinearSpace>::Data> {
+    pub fn get_ys_f64(&self) -> Vec<V::Data> {
         self.ys.iter().map(|y| y.get_data()).collect()
     }
 
-    pub fn get_ys_prime(&self) -> Vec<S::Vector> {
+    pub fn get_ys_prime(&self) -> Vec<V> {
         self.ys_prime.clone()
     }
 
-    pub fn get_ys_prime_f64(&self) -> Vec<<S::Vector as LinearSpace>::Data> {
+    pub fn get_ys_prime_f64(&self) -> Vec<V::Data> {
         self.ys_prime.iter().map(|y| y.get_data()).collect()
     }
 }
@@ -101,46 +424,17 @@ impl<S: System> Results<S> {
 impl<I, S> Solver<I, S>
 where
     I: Integrator,
-    S: System,
+    S: Problem,
 {
     pub fn new(integrator: I, system: S, y0: S::Vector, y0_prime: S::Vector) -> Self {
-        Self {
-            integrator: integrator,
-            system: system,
-            t: 0.0,
-            y: y0,
-            y_prime: y0_prime,
-            results: Results::new(0.0, y0, y0_prime),
-        }
+        Self::with_observer(integrator, system, y0, y0_prime, Results::empty())
     }
 
-    pub fn run(&mut self, h: f64, steps: usize) {
-        for _ in 0..steps {
-            let (y, y_prime) = self
-                .integrator
-                .step(&self.system, self.t, self.y, self.y_prime, h);
-            self.t = self.t + h;
-            self.y = y;
-            self.y_prime = y_prime;
-            self.update();
-        }
-    }
-
-    fn update(&mut self) {
-        self.results.update(self.t, self.y, self.y_prime);
-    }
-
-    pub fn get_results(&self) -> (Vec<f64>, Vec<S::Vector>, Vec<S::Vector>) {
+    pub fn get_results(&self) -> Trajectory<S::Vector> {
         (self.get_ts(), self.get_ys(), self.get_ys_prime())
     }
 
-    pub fn get_results_f64(
-        &self,
-    ) -> (
-        Vec<<f64 as LinearSpace>::Data>,
-        Vec<<S::Vector as LinearSpace>::Data>,
-        Vec<<S::Vector as LinearSpace>::Data>,
-    ) {
+    pub fn get_results_f64(&self) -> Trajectory<<S::Vector as LinearSpace>::Data> {
         (
             self.get_ts_f64(),
             self.get_ys_f64(),
@@ -148,35 +442,198 @@ where
         )
     }
 
+    pub fn get_events(&self) -> &[EventRecord<S::Vector>] {
+        self.observer.get_events()
+    }
+
+    pub fn results(&self) -> &Results<S::Vector> {
+        &self.observer
+    }
+
+    /// Interpolated `(y, y')` at any `t` reached so far
+    pub fn eval(&self, t: f64) -> (S::Vector, S::Vector) {
+        self.observer.eval(t)
+    }
+
+    pub fn resample(&self, ts: &[f64]) -> Trajectory<S::Vector> {
+        self.observer.resample(ts)
+    }
+
+    pub fn resample_uniform(&self, n: usize) -> Trajectory<S::Vector> {
+        self.observer.resample_uniform(n)
+    }
+
     fn get_ts(&self) -> Vec<f64> {
-        self.results.get_ts()
+        self.observer.get_ts()
     }
 
     fn get_ts_f64(&self) -> Vec<f64> {
-        self.results.get_ts_f64()
+        self.observer.get_ts_f64()
     }
 
     fn get_ys(&self) -> Vec<S::Vector> {
-        self.results.get_ys()
+        self.observer.get_ys()
     }
 
     fn get_ys_f64(&self) -> Vec<<S::Vector as LinearSpace>::Data> {
-        self.results.get_ys_f64()
+        self.observer.get_ys_f64()
     }
 
     fn get_ys_prime(&self) -> Vec<S::Vector> {
-        self.results.get_ys_prime()
+        self.observer.get_ys_prime()
     }
 
     fn get_ys_prime_f64(&self) -> Vec<<S::Vector as LinearSpace>::Data> {
-        self.results.get_ys_prime_f64()
+        self.observer.get_ys_prime_f64()
+    }
+}
+
+impl<I, S, O> Solver<I, S, O>
+where
+    I: Integrator,
+    S: Problem,
+    O: Observer<S::Vector>,
+{
+    /// Solver streaming the initial state and every step to `observer`
+    pub fn with_observer(
+        integrator: I,
+        system: S,
+        y0: S::Vector,
+        y0_prime: S::Vector,
+        mut observer: O,
+    ) -> Self {
+        observer.observe(0.0, y0, y0_prime);
+        Self {
+            integrator,
+            system,
+            t: 0.0,
+            y: y0,
+            y_prime: y0_prime,
+            events: Vec::new(),
+            terminated: false,
+            observer,
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
     }
 
+    pub fn into_observer(self) -> O {
+        self.observer
+    }
+
+    /// Watches `event` during the following runs, returns its index in the event records
+    pub fn add_event(&mut self, event: Event<S::Vector>) -> usize {
+        self.events.push(event);
+        self.events.len() - 1
+    }
+
+    /// Whether the last run was stopped by a terminal event
+    pub fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+
+    pub fn run(&mut self, h: f64, steps: usize) {
+        self.terminated = false;
+        for _ in 0..steps {
+            let (y, y_prime) =
+                self.system
+                    .advance(&mut self.integrator, self.t, self.y, self.y_prime, h);
+            self.advance_to(self.t + h, y, y_prime);
+            if self.terminated {
+                break;
+            }
+        }
+    }
+
+    /// Integrates up to `t_end` with adaptive steps, recording every accepted step
+    pub fn run_until(&mut self, t_end: f64)
+    where
+        I: AdaptiveIntegrator,
+    {
+        self.terminated = false;
+        while self.t < t_end && !self.terminated {
+            let remaining = t_end - self.t;
+            let (h, y, y_prime) = self.system.advance_adaptive(
+                &mut self.integrator,
+                self.t,
+                self.y,
+                self.y_prime,
+                remaining,
+            );
+            let t = if h >= remaining { t_end } else { self.t + h };
+            self.advance_to(t, y, y_prime);
+        }
+    }
+
+    /// Accepts the step to `(t, y, y')`, stopping early at a terminal event
+    fn advance_to(&mut self, t: f64, y: S::Vector, y_prime: S::Vector) {
+        let start = (self.t, self.y, self.y_prime);
+        let end = (t, y, y_prime);
+        let dense = self
+            .integrator
+            .dense_output()
+            .filter(|polynomial| polynomial.covers(self.t, t))
+            .map(|polynomial| self.system.dense_step(polynomial));
+        if !self.events.is_empty() {
+            let step = dense.clone().unwrap_or_else(|| {
+                let second_derivatives = self
+                    .system
+                    .second_derivative(self.t, self.y, self.y_prime)
+                    .zip(self.system.second_derivative(t, y, y_prime));
+                DenseStep::hermite(start, end, second_derivatives)
+            });
+            for record in Event::locate_all(&self.events, &step, start, end) {
+                self.observer.observe_event(record);
+                if self.events[record.index].is_terminal() {
+                    self.terminated = true;
+                    self.set_state(record.t, record.y, record.y_prime, dense);
+                    return;
+                }
+            }
+        }
+        self.set_state(t, y, y_prime, dense);
+    }
+
+    fn set_state(
+        &mut self,
+        t: f64,
+        y: S::Vector,
+        y_prime: S::Vector,
+        dense: Option<DenseStep<S::Vector>>,
+    ) {
+        self.t = t;
+        self.y = y;
+        self.y_prime = y_prime;
+        match dense {
+            Some(step) => self.observer.observe_dense(t, y, y_prime, step),
+            None => self.observer.observe(t, y, y_prime),
+        }
+    }
+
+    #[cfg(test)]
     fn get_current(&self) -> (f64, S::Vector, S::Vector) {
         (self.t, self.y, self.y_prime)
     }
 }
 
+impl<I, F> Solver<I, FirstOrder<F>>
+where
+    I: Integrator,
+    F: FirstOrderSystem,
+{
+    /// Solver of a first-order system, `y0_prime` is evaluated from the system
+    pub fn first_order(integrator: I, system: F, y0: F::Vector) -> Self {
+        let y0_prime = system.derivative(0.0, y0);
+        Self::new(integrator, FirstOrder(system), y0, y0_prime)
+    }
+}
+
 pub struct EulerMethod;
 pub struct RK4Method;
 
@@ -186,55 +643,41 @@ pub struct RK4Method;
 /// $$ y'' = f(t, y, _y') $$
 /// Stable for oscillatory motion.
 /// **System::derivative must not use y_prime in return.**
+///
+/// A `FirstOrderSystem` has no position/velocity splitting, so it is advanced
+/// with the implicit midpoint rule, the symplectic 2nd-order method of that case.
 pub struct LeapfrogMethod;
 
 impl Integrator for EulerMethod {
-    fn step<S>(
-        &mut self,
-        system: &S,
-        t: f64,
-        y: S::Vector,
-        y_prime: S::Vector,
-        h: f64,
-    ) -> (S::Vector, S::Vector)
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
     where
-        S: System,
+        F: FirstOrderSystem,
     {
-        (
-            y + y_prime * h,
-            y_prime + system.derivative(t, y, y_prime) * h,
-        )
+        y + system.derivative(t, y) * h
     }
 }
 
 impl Integrator for RK4Method {
-    fn step<S>(
-        &mut self,
-        system: &S,
-        t: f64,
-        y: S::Vector,
-        y_prime: S::Vector,
-        h: f64,
-    ) -> (S::Vector, S::Vector)
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
     where
-        S: System,
+        F: FirstOrderSystem,
     {
-        let k11 = y_prime;
-        let k12 = system.derivative(t, y, y_prime);
-        let k21 = y_prime + (k12 / 2.0) * h;
-        let k22 = system.derivative(t + h / 2.0, y + (k12 / 2.0) * h, y_prime + (k12 / 2.0) * h);
-        let k31 = y_prime + k22 * (h / 2.0);
-        let k32 = system.derivative(t + h / 2.0, y + (k21 / 2.0) * h, y_prime + (k22 / 2.0) * h);
-        let k41 = y_prime + k32 * h;
-        let k42 = system.derivative(t + h, y + k31 * h, y_prime + k32 * h);
-        (
-            y + (k11 + k21 * 2.0 + k31 * 2.0 + k41) * (h / 6.0),
-            y_prime + (k12 + k22 * 2.0 + k32 * 2.0 + k42) * (h / 6.0),
-        )
+        let k1 = system.derivative(t, y);
+        let k2 = system.derivative(t + h / 2.0, y + k1 * (h / 2.0));
+        let k3 = system.derivative(t + h / 2.0, y + k2 * (h / 2.0));
+        let k4 = system.derivative(t + h, y + k3 * h);
+        y + (k1 + k2 * 2.0 + k3 * 2.0 + k4) * (h / 6.0)
     }
 }
 
 impl Integrator for LeapfrogMethod {
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
+    where
+        F: FirstOrderSystem,
+    {
+        implicit_midpoint(&Newton::default(), system, t, y, h)
+    }
+
     fn step<S>(
         &mut self,
         system: &S,
@@ -259,6 +702,13 @@ impl Integrator for LeapfrogMethod {
 mod tests {
     use crate::math::complex::{Complex, ComplexSpace};
     use crate::math::core::ScalarSpace;
+    use crate::math::integrate::adaptive::{DormandPrince45, Tolerance};
+    use crate::math::integrate::implicit::{
+        BackwardEulerMethod, BdfMethod, ImplicitMidpointMethod,
+    };
+    use crate::math::integrate::symplectic::{
+        ForestRuthMethod, PefrlMethod, Yoshida4Method, Yoshida6Method,
+    };
 
     use super::*;
 
@@ -266,6 +716,40 @@ mod tests {
 
     struct TestHarmonicOscillator;
     struct TestComplexHarmonicOscillator;
+    struct TestRotation;
+    struct TestLotkaVolterra {
+        alpha: f64,
+        beta: f64,
+        gamma: f64,
+        delta: f64,
+    }
+
+    /// y' = i y
+    impl FirstOrderSystem for TestRotation {
+        type Vector = Complex;
+
+        fn derivative(&self, _t: f64, y: Self::Vector) -> Self::Vector {
+            Complex::i() * y
+        }
+    }
+
+    /// x' = alpha x - beta x y, y' = delta x y - gamma y
+    impl FirstOrderSystem for TestLotkaVolterra {
+        type Vector = Vector<f64, 2>;
+
+        fn derivative(&self, _t: f64, y: Self::Vector) -> Self::Vector {
+            Vector::new([
+                self.alpha * y[0] - self.beta * y[0] * y[1],
+                self.delta * y[0] * y[1] - self.gamma * y[1],
+            ])
+        }
+    }
+
+    impl TestLotkaVolterra {
+        fn invariant(&self, y: Vector<f64, 2>) -> f64 {
+            self.delta * y[0] - self.gamma * y[0].ln() + self.beta * y[1] - self.alpha * y[1].ln()
+        }
+    }
 
     /// y'' + y = 0
     impl System for TestHarmonicOscillator {
@@ -353,31 +837,71 @@ mod tests {
         )
     }
 
-    #[test]
-    fn test_leapfrog_energy_conservation() {
-        let system = TestHarmonicOscillator;
-        let integrator = LeapfrogMethod;
-
-        let y0 = 1.0;
-        let y0_prime = 0.0;
-        let h = 0.01;
-        let steps = 1000;
-
-        let initial_energy = 0.5 * y0_prime * y0_prime + 0.5 * y0 * y0;
+    /// Maximum energy error of the harmonic oscillator over the first and the last tenth
+    /// of 10^6 steps with h = 0.01
+    fn oscillator_energy_errors<I: Integrator>(integrator: I) -> (f64, f64) {
+        let (h, steps) = (0.01, 1_000_000);
+        let energy = |y: f64, y_prime: f64| 0.5 * y_prime * y_prime + 0.5 * y * y;
+        let (y0, y0_prime) = (1.0, 0.0);
+        let initial_energy = energy(y0, y0_prime);
 
-        let mut test_solver = Solver::new(integrator, system, y0, y0_prime);
+        let mut test_solver = Solver::new(integrator, TestHarmonicOscillator, y0, y0_prime);
         test_solver.run(h, steps);
-        let (_, y, y_prime) = test_solver.get_current();
-
-        let final_energy = 0.5 * y_prime * y_prime + 0.5 * y * y;
-
-        let error = (final_energy - initial_energy).abs();
-        println!(
-            "Initial Energy: {}, Final Energy: {}, Error: {}",
-            initial_energy, final_energy, error
-        );
+        let (_, ys, ys_prime) = test_solver.get_results();
+
+        let errors: Vec<f64> = ys
+            .iter()
+            .zip(ys_prime.iter())
+            .map(|(y, y_prime)| (energy(*y, *y_prime) - initial_energy).abs())
+            .collect();
+        let tenth = errors.len() / 10;
+        let first = errors[..tenth].iter().cloned().fold(0.0, f64::max);
+        let last = errors[errors.len() - tenth..]
+            .iter()
+            .cloned()
+            .fold(0.0, f64::max);
+        (first, last)
+    }
 
-        assert!(error < 1e-5, "LeapFrogMethod: Energy is not conserved");
+    #[test]
+    fn test_leapfrog_energy_conservation() {
+        // Symplectic schemes keep the energy error bounded over 10^6 steps,
+        // for leapfrog by h^2 / 8
+        let runs = [
+            (
+                "LeapfrogMethod",
+                oscillator_energy_errors(LeapfrogMethod),
+                2e-5,
+            ),
+            (
+                "Yoshida4Method",
+                oscillator_energy_errors(Yoshida4Method),
+                1e-8,
+            ),
+            (
+                "Yoshida6Method",
+                oscillator_energy_errors(Yoshida6Method),
+                1e-11,
+            ),
+            (
+                "ForestRuthMethod",
+                oscillator_energy_errors(ForestRuthMethod),
+                1e-8,
+            ),
+            ("PefrlMethod", oscillator_energy_errors(PefrlMethod), 1e-10),
+        ];
+        for (name, (first, last), bound) in runs {
+            println!("{name}: first tenth {first:e}, last tenth {last:e}");
+            assert!(
+                last < bound,
+                "{name}: Energy error {last:e} over 10^6 steps exceeds {bound:e}"
+            );
+            // Round-off accumulates as a random walk below ~1e-12
+            assert!(
+                last < 2.0 * first + 1e-12,
+                "{name}: Energy drifts. first tenth {first:e}, last tenth {last:e}"
+            );
+        }
     }
 
     #[test]
@@ -433,4 +957,103 @@ mod tests {
             "LeapFrogMethod: Energy is not conserved (using Complex)"
         );
     }
+
+    fn rotation_error<I: Integrator>(integrator: I) -> f64 {
+        let mut test_solver = Solver::first_order(integrator, TestRotation, Complex::one());
+        test_solver.run(0.01, 100);
+        let (_, y, _) = test_solver.get_current();
+        (y - Complex::cis(1.0)).abs()
+    }
+
+    #[test]
+    fn test_first_order_system_with_every_integrator() {
+        let errors = [
+            ("EulerMethod", rotation_error(EulerMethod), 1e-2),
+            ("RK4Method", rotation_error(RK4Method), 1e-9),
+            ("LeapfrogMethod", rotation_error(LeapfrogMethod), 1e-4),
+            ("Yoshida4Method", rotation_error(Yoshida4Method), 1e-8),
+            ("Yoshida6Method", rotation_error(Yoshida6Method), 1e-10),
+            ("ForestRuthMethod", rotation_error(ForestRuthMethod), 1e-8),
+            ("PefrlMethod", rotation_error(PefrlMethod), 1e-8),
+            (
+                "DormandPrince45",
+                rotation_error(DormandPrince45::new(Tolerance::new(1e-10, 1e-10))),
+                1e-8,
+            ),
+            (
+                "BackwardEulerMethod",
+                rotation_error(BackwardEulerMethod::default()),
+                1e-2,
+            ),
+            (
+                "ImplicitMidpointMethod",
+                rotation_error(ImplicitMidpointMethod::default()),
+                1e-4,
+            ),
+            (
+                "BdfMethod(4)",
+                rotation_error(BdfMethod::new(4, Default::default())),
+                1e-6,
+            ),
+        ];
+        for (name, error, bound) in errors {
+            assert!(
+                error < bound,
+                "{name}: first-order error too large: {error}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_first_order_results_store_derivative() {
+        let system = TestLotkaVolterra {
+            alpha: 1.1,
+            beta: 0.4,
+            gamma: 0.4,
+            delta: 0.1,
+        };
+        let y0 = Vector::new([10.0, 10.0]);
+        let initial_invariant = system.invariant(y0);
+
+        let integrator = DormandPrince45::new(Tolerance::new(1e-10, 1e-10));
+        let mut test_solver = Solver::first_order(integrator, system, y0);
+        test_solver.run_until(50.0);
+        let (ts, ys, ys_prime) = test_solver.get_results();
+
+        for ((t, y), y_prime) in ts.iter().zip(ys.iter()).zip(ys_prime.iter()) {
+            let error = (*y_prime - test_solver.system.0.derivative(*t, *y)).components();
+            assert!(
+                error.iter().all(|e| e.abs() < 1e-12),
+                "Results must store y' = f(t, y)"
+            );
+        }
+
+        let (_, y, _) = test_solver.get_current();
+        let error = (test_solver.system.0.invariant(y) - initial_invariant).abs();
+        assert!(error < 1e-7, "Lotka-Volterra invariant drifts: {error}");
+    }
+
+    #[test]
+    fn test_second_order_adapter() {
+        let y0 = 1.0;
+        let y0_prime = 0.0;
+        let h = 0.01;
+        let steps = 100;
+
+        let mut second_order_solver = Solver::new(RK4Method, TestHarmonicOscillator, y0, y0_prime);
+        second_order_solver.run(h, steps);
+        let (_, y, y_prime) = second_order_solver.get_current();
+
+        let mut first_order_solver = Solver::first_order(
+            RK4Method,
+            SecondOrder(TestHarmonicOscillator),
+            Vector::new([y0, y0_prime]),
+        );
+        first_order_solver.run(h, steps);
+        let (_, z, z_prime) = first_order_solver.get_current();
+
+        assert_eq!(z, Vector::new([y, y_prime]), "SecondOrder adapter state");
+        assert_eq!(z_prime[0], y_prime, "SecondOrder adapter derivative");
+        assert!((y - 1.0f64.cos()).abs() < 1e-9, "RK4 error too large");
+    }
 }
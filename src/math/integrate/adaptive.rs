@@ -0,0 +1,496 @@
+use crate::math::core::{LinearSpace, Vector};
+use crate::math::integrate::dense::StepPolynomial;
+use crate::math::integrate::{FirstOrderSystem, Integrator, SecondOrder, System};
+
+/// Absolute and relative error tolerance for adaptive step-size control
+///
+/// A step is accepted when the weighted RMS norm of the local error estimate
+/// $$ \sqrt{\frac{1}{n} \sum_i \left( \frac{e_i}{atol + rtol \cdot \max(|y_i|, |\tilde{y}_i|)} \right)^2} $$
+/// is at most 1.
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerance {
+    pub atol: f64,
+    pub rtol: f64,
+}
+
+impl Tolerance {
+    pub fn new(atol: f64, rtol: f64) -> Self {
+        Self { atol, rtol }
+    }
+
+    /// Weighted RMS norm of `err`, scaled by the old state `y` and new state `y_new`
+    pub fn error_norm<V: LinearSpace>(&self, y: V, y_new: V, err: V) -> f64 {
+        let (y, y_new, err) = (y.components(), y_new.components(), err.components());
+        let sum: f64 = (0..err.len())
+            .map(|i| {
+                let scale = self.atol + self.rtol * y[i].abs().max(y_new[i].abs());
+                (err[i] / scale).powi(2)
+            })
+            .sum();
+        (sum / (err.len().max(1) as f64)).sqrt()
+    }
+
+    /// Weighted RMS norm of `v`, scaled by the state `y`
+    pub fn norm<V: LinearSpace>(&self, y: V, v: V) -> f64 {
+        self.error_norm(y, y, v)
+    }
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self::new(1e-8, 1e-8)
+    }
+}
+
+/// Integrator with local error control
+///
+/// `Integrator::step` of an adaptive integrator covers the requested `h` with
+/// as many internal steps as the tolerance requires.
+pub trait AdaptiveIntegrator: Integrator {
+    /// Takes one accepted step of $$ y' = f(t, y) $$ no longer than `h_max`.
+    ///
+    /// Returns `(h, y)` where `h` is the step actually taken.
+    /// Rejected attempts are retried internally with a smaller step.
+    fn adaptive_step_first_order<F>(
+        &mut self,
+        system: &F,
+        t: f64,
+        y: F::Vector,
+        h_max: f64,
+    ) -> (f64, F::Vector)
+    where
+        F: FirstOrderSystem;
+
+    /// Takes one accepted step of $$ y'' = f(t, y, y') $$ no longer than `h_max`.
+    ///
+    /// Returns `(h, y, y_prime)` where `h` is the step actually taken.
+    fn adaptive_step<S>(
+        &mut self,
+        system: &S,
+        t: f64,
+        y: S::Vector,
+        y_prime: S::Vector,
+        h_max: f64,
+    ) -> (f64, S::Vector, S::Vector)
+    where
+        S: System,
+    {
+        let (h, z) = self.adaptive_step_first_order(
+            &SecondOrder(system),
+            t,
+            Vector::new([y, y_prime]),
+            h_max,
+        );
+        (h, z[0], z[1])
+    }
+}
+
+/// Covers `[t, t + h]` with consecutive adaptive steps
+pub(crate) fn cover_step<I, F>(
+    integrator: &mut I,
+    system: &F,
+    t: f64,
+    y: F::Vector,
+    h: f64,
+) -> F::Vector
+where
+    I: AdaptiveIntegrator,
+    F: FirstOrderSystem,
+{
+    let (mut t, mut y) = (t, y);
+    let mut remaining = h;
+    loop {
+        let (h_taken, y_new) = integrator.adaptive_step_first_order(system, t, y, remaining);
+        y = y_new;
+        if h_taken >= remaining {
+            return y;
+        }
+        t += h_taken;
+        remaining -= h_taken;
+    }
+}
+
+/// Initial step size guess (Hairer, Nørsett & Wanner, Solving ODE I, II.4)
+pub(crate) fn initial_step<F: FirstOrderSystem>(
+    system: &F,
+    t: f64,
+    y: F::Vector,
+    f0: F::Vector,
+    order: usize,
+    tolerance: &Tolerance,
+    h_max: f64,
+) -> f64 {
+    let d0 = tolerance.norm(y, y);
+    let d1 = tolerance.norm(y, f0);
+    let h0 = if d0 < 1e-5 || d1 < 1e-5 {
+        1e-6
+    } else {
+        0.01 * d0 / d1
+    }
+    .min(h_max);
+    let y1 = y + f0 * h0;
+    let d2 = tolerance.norm(y, system.derivative(t + h0, y1) - f0) / h0;
+    let h1 = if d1.max(d2) <= 1e-15 {
+        (h0 * 1e-3).max(1e-6)
+    } else {
+        (0.01 / d1.max(d2)).powf(1.0 / (order as f64 + 1.0))
+    };
+    (100.0 * h0).min(h1).min(h_max)
+}
+
+/// Dormand-Prince 5(4) embedded Runge-Kutta method
+///
+/// Advances with the 5th-order solution and estimates the local error with
+/// the embedded 4th-order solution.
+/// The step size is controlled by `Tolerance` and rejected steps are retried.
+/// Each accepted step has a 4th-order continuous extension (Hairer's DOPRI5).
+pub struct DormandPrince45 {
+    tolerance: Tolerance,
+    h: Option<f64>,
+    dense: Option<StepPolynomial>,
+    accepted: usize,
+    rejected: usize,
+}
+
+impl DormandPrince45 {
+    const C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+    const A: [[f64; 6]; 7] = [
+        [0.0; 6],
+        [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+        [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+        [
+            19372.0 / 6561.0,
+            -25360.0 / 2187.0,
+            64448.0 / 6561.0,
+            -212.0 / 729.0,
+            0.0,
+            0.0,
+        ],
+        [
+            9017.0 / 3168.0,
+            -355.0 / 33.0,
+            46732.0 / 5247.0,
+            49.0 / 176.0,
+            -5103.0 / 18656.0,
+            0.0,
+        ],
+        [
+            35.0 / 384.0,
+            0.0,
+            500.0 / 1113.0,
+            125.0 / 192.0,
+            -2187.0 / 6784.0,
+            11.0 / 84.0,
+        ],
+    ];
+    /// Difference between the 5th and 4th order weights
+    const E: [f64; 7] = [
+        71.0 / 57600.0,
+        0.0,
+        -71.0 / 16695.0,
+        71.0 / 1920.0,
+        -17253.0 / 339200.0,
+        22.0 / 525.0,
+        -1.0 / 40.0,
+    ];
+    /// Weights of the 5th coefficient of the continuous extension
+    const D: [f64; 7] = [
+        -12715105075.0 / 11282082432.0,
+        0.0,
+        87487479700.0 / 32700410799.0,
+        -10690763975.0 / 1880347072.0,
+        701980252875.0 / 199316789632.0,
+        -1453857185.0 / 822651844.0,
+        69997945.0 / 29380423.0,
+    ];
+
+    const SAFETY: f64 = 0.9;
+    const MIN_FACTOR: f64 = 0.2;
+    const MAX_FACTOR: f64 = 5.0;
+
+    pub fn new(tolerance: Tolerance) -> Self {
+        Self {
+            tolerance,
+            h: None,
+            dense: None,
+            accepted: 0,
+            rejected: 0,
+        }
+    }
+
+    /// Sets the first trial step instead of estimating it
+    pub fn with_initial_step(mut self, h: f64) -> Self {
+        self.h = Some(h);
+        self
+    }
+
+    pub fn tolerance(&self) -> Tolerance {
+        self.tolerance
+    }
+
+    pub fn accepted_steps(&self) -> usize {
+        self.accepted
+    }
+
+    pub fn rejected_steps(&self) -> usize {
+        self.rejected
+    }
+
+    /// One Dormand-Prince step, returns the 5th-order solution, the local error
+    /// estimate and the stages
+    fn attempt<F: FirstOrderSystem>(
+        system: &F,
+        t: f64,
+        y: F::Vector,
+        k1: F::Vector,
+        h: f64,
+    ) -> (F::Vector, F::Vector, [F::Vector; 7]) {
+        let zero = F::Vector::zero();
+        let mut k = [k1; 7];
+        for s in 1..7 {
+            let dy = (0..s).fold(zero, |acc, j| acc + k[j] * Self::A[s][j]);
+            k[s] = system.derivative(t + Self::C[s] * h, y + dy * h);
+        }
+        let y_new = y + (0..6).fold(zero, |acc, j| acc + k[j] * Self::A[6][j]) * h;
+        let err = (0..7).fold(zero, |acc, j| acc + k[j] * Self::E[j]) * h;
+        (y_new, err, k)
+    }
+
+    /// Continuous extension of an accepted step
+    ///
+    /// $$ y(\theta) = r_1 + \theta (r_2 + (1 - \theta)(r_3 + \theta (r_4 + (1 - \theta) r_5))) $$
+    /// stored in monomial form.
+    fn polynomial<V: LinearSpace>(t: f64, y: V, y_new: V, k: &[V; 7], h: f64) -> StepPolynomial {
+        let r2 = y_new - y;
+        let r3 = k[0] * h - r2;
+        let r4 = r2 - k[6] * h - r3;
+        let r5 = (0..7).fold(V::zero(), |acc, j| acc + k[j] * Self::D[j]) * h;
+        let coefficients = [y, r2 + r3, r4 + r5 - r3, -r4 - r5 * 2.0, r5];
+        StepPolynomial {
+            t0: t,
+            h,
+            coefficients: coefficients.iter().map(|c| c.components()).collect(),
+        }
+    }
+}
+
+impl Default for DormandPrince45 {
+    fn default() -> Self {
+        Self::new(Tolerance::default())
+    }
+}
+
+impl AdaptiveIntegrator for DormandPrince45 {
+    fn adaptive_step_first_order<F>(
+        &mut self,
+        system: &F,
+        t: f64,
+        y: F::Vector,
+        h_max: f64,
+    ) -> (f64, F::Vector)
+    where
+        F: FirstOrderSystem,
+    {
+        let k1 = system.derivative(t, y);
+        let h_suggested = match self.h {
+            Some(h) => h,
+            None => initial_step(system, t, y, k1, 5, &self.tolerance, h_max),
+        };
+        let mut h = h_suggested.min(h_max);
+        let mut clamped = h < h_suggested;
+        loop {
+            if h <= f64::EPSILON * t.abs().max(1.0) {
+                panic!("DormandPrince45: step size underflow at t = {t}");
+            }
+            let (y_new, err, k) = Self::attempt(system, t, y, k1, h);
+            let err_norm = self.tolerance.error_norm(y, y_new, err);
+            if err_norm <= 1.0 {
+                let factor = if err_norm == 0.0 {
+                    Self::MAX_FACTOR
+                } else {
+                    (Self::SAFETY * err_norm.powf(-0.2)).clamp(Self::MIN_FACTOR, Self::MAX_FACTOR)
+                };
+                // A step clamped by `h_max` must not shrink the next suggestion
+                self.h = Some(if clamped {
+                    (h * factor).max(h_suggested)
+                } else {
+                    h * factor
+                });
+                self.dense = Some(Self::polynomial(t, y, y_new, &k, h));
+                self.accepted += 1;
+                return (h, y_new);
+            }
+            self.rejected += 1;
+            clamped = false;
+            h *= (Self::SAFETY * err_norm.powf(-0.2)).clamp(Self::MIN_FACTOR, 1.0);
+        }
+    }
+}
+
+impl Integrator for DormandPrince45 {
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
+    where
+        F: FirstOrderSystem,
+    {
+        cover_step(self, system, t, y, h)
+    }
+
+    fn dense_output(&self) -> Option<&StepPolynomial> {
+        self.dense.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::complex::{Complex, ComplexSpace};
+    use crate::math::core::{ScalarSpace, VectorSpace};
+    use crate::math::integrate::{RK4Method, Solver};
+
+    use super::*;
+
+    struct TestHarmonicOscillator;
+    struct TestComplexHarmonicOscillator;
+    struct TestKeplerOrbit;
+
+    /// y'' + y = 0
+    impl System for TestHarmonicOscillator {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            -y
+        }
+    }
+
+    impl System for TestComplexHarmonicOscillator {
+        type Vector = Complex;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            -y
+        }
+    }
+
+    /// r'' = -r / |r|^3
+    impl System for TestKeplerOrbit {
+        type Vector = Vector<f64, 2>;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            let r_sq = y[0] * y[0] + y[1] * y[1];
+            -y / (r_sq * r_sq.sqrt())
+        }
+    }
+
+    #[test]
+    fn test_dormand_prince_run_until() {
+        let integrator = DormandPrince45::new(Tolerance::new(1e-10, 1e-10));
+        let mut test_solver = Solver::new(integrator, TestHarmonicOscillator, 1.0, 0.0);
+        test_solver.run_until(10.0);
+        let (t, y, y_prime) = test_solver.get_current();
+
+        assert_eq!(t, 10.0, "run_until must stop exactly at t_end");
+        let error = (y - 10.0f64.cos()).abs() + (y_prime + 10.0f64.sin()).abs();
+        assert!(error < 1e-8, "DormandPrince45 error too large: {}", error);
+    }
+
+    #[test]
+    fn test_dormand_prince_tolerance_controls_error() {
+        let mut errors = Vec::new();
+        let mut steps = Vec::new();
+        for tol in [1e-4, 1e-8] {
+            let integrator = DormandPrince45::new(Tolerance::new(tol, tol));
+            let mut test_solver = Solver::new(integrator, TestHarmonicOscillator, 1.0, 0.0);
+            test_solver.run_until(10.0);
+            let (_, y, _) = test_solver.get_current();
+            errors.push((y - 10.0f64.cos()).abs());
+            steps.push(test_solver.get_results().0.len());
+        }
+
+        assert!(
+            errors[1] < errors[0],
+            "Tighter tolerance must reduce error: {:?}",
+            errors
+        );
+        assert!(
+            steps[1] > steps[0],
+            "Tighter tolerance must take more steps: {:?}",
+            steps
+        );
+    }
+
+    #[test]
+    fn test_dormand_prince_eccentric_orbit() {
+        // Eccentricity 0.9: the step size must shrink near periapsis
+        let e: f64 = 0.9;
+        let y0 = Vector::new([1.0 - e, 0.0]);
+        let y0_prime = Vector::new([0.0, ((1.0 + e) / (1.0 - e)).sqrt()]);
+        let period = 2.0 * std::f64::consts::PI;
+
+        let integrator = DormandPrince45::new(Tolerance::new(1e-10, 1e-10)).with_initial_step(1.0);
+        let mut test_solver = Solver::new(integrator, TestKeplerOrbit, y0, y0_prime);
+        test_solver.run_until(period);
+        let (_, y, _) = test_solver.get_current();
+
+        let error = (y - y0).norm();
+        assert!(
+            error < 1e-6,
+            "Orbit is not closed after a period: {}",
+            error
+        );
+        assert!(
+            test_solver.integrator.rejected_steps() > 0,
+            "Oversized initial step must be rejected"
+        );
+
+        let (ts, _, _) = test_solver.get_results();
+        let hs: Vec<f64> = ts.windows(2).map(|w| w[1] - w[0]).collect();
+        let h_min = hs.iter().cloned().fold(f64::INFINITY, f64::min);
+        let h_max = hs.iter().cloned().fold(0.0, f64::max);
+        assert!(
+            h_max > 10.0 * h_min,
+            "Step size must adapt along the orbit: h_min {} h_max {}",
+            h_min,
+            h_max
+        );
+
+        // Fixed-step RK4 with the same number of steps is far less accurate
+        let steps = hs.len();
+        let mut rk4_solver = Solver::new(RK4Method, TestKeplerOrbit, y0, y0_prime);
+        rk4_solver.run(period / steps as f64, steps);
+        let (_, y_rk4, _) = rk4_solver.get_current();
+        assert!((y_rk4 - y0).norm() > error);
+    }
+
+    #[test]
+    fn test_dormand_prince_fixed_step_with_complex() {
+        let integrator = DormandPrince45::new(Tolerance::new(1e-10, 1e-10));
+        let y0 = Complex::new(1.0, 0.0);
+        let y0_prime = Complex::new(0.0, 1.0);
+
+        let mut test_solver = Solver::new(integrator, TestComplexHarmonicOscillator, y0, y0_prime);
+        test_solver.run(0.5, 2);
+        let (_, y, _) = test_solver.get_current();
+
+        let error = (y - Complex::cis(1.0)).abs();
+        assert!(error < 1e-8, "DormandPrince45 error too large: {}", error);
+        assert_eq!(test_solver.get_results().0.len(), 3);
+    }
+
+    #[test]
+    fn test_dormand_prince_step_after_rejection() {
+        // The oversized trial step is clamped by h_max, rejected, then must not come back
+        let mut integrator =
+            DormandPrince45::new(Tolerance::new(1e-10, 1e-10)).with_initial_step(2.0);
+        let (h, _, _) = integrator.adaptive_step(&TestHarmonicOscillator, 0.0, 1.0, 0.0, 1.0);
+        assert!(integrator.rejected_steps() > 0);
+        assert!(h < 1.0);
+
+        let suggested = integrator.h.unwrap();
+        assert!(
+            suggested <= h * DormandPrince45::MAX_FACTOR && suggested < 1.0,
+            "step {h} followed by {suggested}"
+        );
+        let rejected = integrator.rejected_steps();
+        integrator.adaptive_step(&TestHarmonicOscillator, h, h.cos(), -h.sin(), 1.0);
+        assert!(integrator.rejected_steps() <= rejected + 1);
+    }
+}
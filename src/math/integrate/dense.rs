@@ -0,0 +1,257 @@
+use crate::math::core::LinearSpace;
+
+/// Continuous extension of one step produced by an integrator
+///
+/// Polynomial in $$ \theta = (t - t_0) / h $$ with coefficients given as the
+/// `components` of the integrated state.
+#[derive(Clone, Debug)]
+pub struct StepPolynomial {
+    pub t0: f64,
+    pub h: f64,
+    pub coefficients: Vec<Vec<f64>>,
+}
+
+impl StepPolynomial {
+    /// Whether the polynomial covers exactly the step from `t0` to `t1`
+    pub fn covers(&self, t0: f64, t1: f64) -> bool {
+        let tolerance = 1e-12 * t1.abs().max(1.0);
+        (self.t0 - t0).abs() <= tolerance && (self.t0 + self.h - t1).abs() <= tolerance
+    }
+}
+
+/// Interpolant of `y` and `y'` over one step of a trajectory
+///
+/// Both are polynomials in $$ \theta = (t - t_0) / h $$, evaluated by Horner's rule.
+#[derive(Clone, Debug)]
+pub struct DenseStep<V: LinearSpace> {
+    t0: f64,
+    h: f64,
+    y: Vec<V>,
+    y_prime: Vec<V>,
+}
+
+impl<V: LinearSpace> DenseStep<V> {
+    pub fn new(t0: f64, h: f64, y: Vec<V>, y_prime: Vec<V>) -> Self {
+        Self { t0, h, y, y_prime }
+    }
+
+    /// Cubic Hermite interpolant from `(t0, y0, y0')` to `(t1, y1, y1')`
+    ///
+    /// `y'` is interpolated from the 2nd derivatives `(y0'', y1'')` when they are
+    /// known, otherwise it is the derivative of the cubic.
+    pub fn hermite(
+        (t0, y0, d0): (f64, V, V),
+        (t1, y1, d1): (f64, V, V),
+        second_derivatives: Option<(V, V)>,
+    ) -> Self {
+        let h = t1 - t0;
+        let y = hermite_coefficients(h, y0, d0, y1, d1);
+        let y_prime = match second_derivatives {
+            Some((a0, a1)) => hermite_coefficients(h, d0, a0, d1, a1),
+            None => differentiate(&y, h),
+        };
+        Self::new(t0, h, y, y_prime)
+    }
+
+    /// Converts the polynomial of a first-order state $$ y $$
+    pub fn from_first_order(polynomial: &StepPolynomial) -> Self {
+        let y: Vec<V> = polynomial
+            .coefficients
+            .iter()
+            .map(|c| V::from_components(c))
+            .collect();
+        let y_prime = differentiate(&y, polynomial.h);
+        Self::new(polynomial.t0, polynomial.h, y, y_prime)
+    }
+
+    /// Converts the polynomial of a phase-space state $$ (y, y') $$
+    pub fn from_phase(polynomial: &StepPolynomial) -> Self {
+        let dim = V::dim();
+        let (y, y_prime) = polynomial
+            .coefficients
+            .iter()
+            .map(|c| (V::from_components(&c[..dim]), V::from_components(&c[dim..])))
+            .unzip();
+        Self::new(polynomial.t0, polynomial.h, y, y_prime)
+    }
+
+    pub fn t0(&self) -> f64 {
+        self.t0
+    }
+
+    pub fn t1(&self) -> f64 {
+        self.t0 + self.h
+    }
+
+    pub fn eval(&self, t: f64) -> (V, V) {
+        let s = if self.h == 0.0 {
+            1.0
+        } else {
+            (t - self.t0) / self.h
+        };
+        (horner(&self.y, s), horner(&self.y_prime, s))
+    }
+}
+
+fn horner<V: LinearSpace>(coefficients: &[V], s: f64) -> V {
+    coefficients
+        .iter()
+        .rev()
+        .fold(V::zero(), |acc, &c| acc * s + c)
+}
+
+/// Coefficients in $$ \theta $$ of $$ d/dt $$ of a polynomial in $$ \theta $$
+fn differentiate<V: LinearSpace>(coefficients: &[V], h: f64) -> Vec<V> {
+    if h == 0.0 {
+        return vec![V::zero()];
+    }
+    coefficients
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(j, &c)| c * (j as f64 / h))
+        .collect()
+}
+
+/// Monomial coefficients of the Hermite cubic through `p0, p1` with slopes `m0, m1`
+fn hermite_coefficients<V: LinearSpace>(h: f64, p0: V, m0: V, p1: V, m1: V) -> Vec<V> {
+    let (m0, m1) = (m0 * h, m1 * h);
+    vec![
+        p0,
+        m0,
+        (p1 - p0) * 3.0 - m0 * 2.0 - m1,
+        (p0 - p1) * 2.0 + m0 + m1,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::math::integrate::adaptive::{DormandPrince45, Tolerance};
+    use crate::math::integrate::{FirstOrderSystem, RK4Method, Solver, System};
+
+    use super::*;
+
+    struct TestHarmonicOscillator;
+    struct TestDecay;
+
+    /// y'' + y = 0
+    impl System for TestHarmonicOscillator {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            -y
+        }
+    }
+
+    /// y' = -y
+    impl FirstOrderSystem for TestDecay {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector) -> Self::Vector {
+            -y
+        }
+    }
+
+    fn max_error(ts: &[f64], ys: &[f64], exact: impl Fn(f64) -> f64) -> f64 {
+        ts.iter()
+            .zip(ys)
+            .map(|(&t, &y)| (y - exact(t)).abs())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_hermite_reproduces_cubic() {
+        // y = t^3 - 2t is reproduced exactly by a cubic Hermite interpolant
+        let y = |t: f64| t * t * t - 2.0 * t;
+        let d = |t: f64| 3.0 * t * t - 2.0;
+        let step = DenseStep::hermite((1.0, y(1.0), d(1.0)), (3.0, y(3.0), d(3.0)), None);
+        for t in [1.0, 1.3, 2.0, 2.9, 3.0] {
+            let (y_t, d_t) = step.eval(t);
+            assert!((y_t - y(t)).abs() < 1e-12, "y({t}) = {y_t}");
+            assert!((d_t - d(t)).abs() < 1e-12, "y'({t}) = {d_t}");
+        }
+    }
+
+    #[test]
+    fn test_hermite_eval_between_fixed_steps() {
+        let mut test_solver = Solver::new(RK4Method, TestHarmonicOscillator, 1.0, 0.0);
+        test_solver.run(0.01, 1000);
+
+        let grid: Vec<f64> = (0..=999).map(|i| i as f64 * 0.01 + 0.005).collect();
+        let (ts, ys, ys_prime) = test_solver.resample(&grid);
+        assert_eq!(ts, grid);
+        assert!(
+            max_error(&ts, &ys, f64::cos) < 1e-8,
+            "Hermite cubic is 4th order"
+        );
+        assert!(max_error(&ts, &ys_prime, |t| -t.sin()) < 1e-8);
+    }
+
+    #[test]
+    fn test_dormand_prince_dense_output() {
+        let integrator = DormandPrince45::new(Tolerance::new(1e-10, 1e-10));
+        let mut test_solver = Solver::new(integrator, TestHarmonicOscillator, 1.0, 0.0);
+        test_solver.run_until(4.0 * PI);
+
+        let (ts, _, _) = test_solver.get_results();
+        assert!(ts.len() < 500, "Resampled grid is finer than the steps");
+
+        let (grid, ys, ys_prime) = test_solver.resample_uniform(1001);
+        assert_eq!(grid[0], 0.0);
+        assert_eq!(grid[1000], 4.0 * PI);
+        assert!(max_error(&grid, &ys, f64::cos) < 1e-8);
+        assert!(max_error(&grid, &ys_prime, |t| -t.sin()) < 1e-8);
+    }
+
+    #[test]
+    fn test_dense_output_beats_hermite_on_long_steps() {
+        // Hermite from the samples only is much worse than the DP45 continuous extension
+        let integrator = DormandPrince45::new(Tolerance::new(1e-6, 1e-6));
+        let mut test_solver = Solver::first_order(integrator, TestDecay, 1.0);
+        test_solver.run_until(10.0);
+
+        let results = test_solver.results();
+        let (ts, ys, ys_prime) = test_solver.get_results();
+        let mut dense_error: f64 = 0.0;
+        let mut hermite_error: f64 = 0.0;
+        for i in 0..ts.len() - 1 {
+            let t = 0.5 * (ts[i] + ts[i + 1]);
+            let hermite = DenseStep::hermite(
+                (ts[i], ys[i], ys_prime[i]),
+                (ts[i + 1], ys[i + 1], ys_prime[i + 1]),
+                None,
+            );
+            dense_error = dense_error.max((results.eval(t).0 - (-t).exp()).abs());
+            hermite_error = hermite_error.max((hermite.eval(t).0 - (-t).exp()).abs());
+        }
+        assert!(dense_error < 1e-5, "Dense output error {dense_error}");
+        assert!(
+            dense_error < hermite_error,
+            "Dense {dense_error} vs Hermite {hermite_error}"
+        );
+    }
+
+    #[test]
+    fn test_eval_on_samples_returns_samples() {
+        let mut test_solver = Solver::new(RK4Method, TestHarmonicOscillator, 1.0, 0.0);
+        test_solver.run(0.1, 10);
+
+        let (ts, ys, ys_prime) = test_solver.get_results();
+        let results = test_solver.results();
+        for i in 0..ts.len() {
+            let (y, y_prime) = results.eval(ts[i]);
+            assert!((y - ys[i]).abs() < 1e-15);
+            assert!((y_prime - ys_prime[i]).abs() < 1e-15);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_eval_outside_results_panics() {
+        let mut test_solver = Solver::new(RK4Method, TestHarmonicOscillator, 1.0, 0.0);
+        test_solver.run(0.1, 10);
+        test_solver.results().eval(1.5);
+    }
+}
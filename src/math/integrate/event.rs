@@ -0,0 +1,326 @@
+use crate::math::core::LinearSpace;
+use crate::math::integrate::dense::DenseStep;
+
+/// Direction of a zero crossing of an event function
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From negative to positive
+    Rising,
+    /// From positive to negative
+    Falling,
+    /// Either direction (filter only)
+    Both,
+}
+
+/// Zero crossing of `g(t, y, y')` watched by `Solver`
+///
+/// The crossing time is located inside the step on the dense output of the step.
+/// A terminal event stops the run at the crossing.
+/// Crossings are detected by a sign change over a step, so two crossings within
+/// one step cancel out.
+pub struct Event<V: LinearSpace> {
+    function: Box<dyn Fn(f64, V, V) -> f64>,
+    direction: Direction,
+    terminal: bool,
+}
+
+/// A located zero crossing of the `index`-th event of `Solver`
+#[derive(Clone, Copy, Debug)]
+pub struct EventRecord<V: LinearSpace> {
+    pub index: usize,
+    pub t: f64,
+    pub y: V,
+    pub y_prime: V,
+    pub direction: Direction,
+}
+
+impl<V: LinearSpace> Event<V> {
+    /// Non-terminal event triggered in both directions
+    pub fn new<G>(function: G) -> Self
+    where
+        G: Fn(f64, V, V) -> f64 + 'static,
+    {
+        Self {
+            function: Box::new(function),
+            direction: Direction::Both,
+            terminal: false,
+        }
+    }
+
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn terminal(mut self, terminal: bool) -> Self {
+        self.terminal = terminal;
+        self
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        self.terminal
+    }
+
+    pub fn evaluate(&self, t: f64, y: V, y_prime: V) -> f64 {
+        (self.function)(t, y, y_prime)
+    }
+
+    /// Crossing direction over a step from `g0` to `g1`, if it passes the filter
+    ///
+    /// A zero at the start of the step belongs to the previous step.
+    fn crossing(&self, g0: f64, g1: f64) -> Option<Direction> {
+        let direction = if g0 < 0.0 && g1 >= 0.0 {
+            Direction::Rising
+        } else if g0 > 0.0 && g1 <= 0.0 {
+            Direction::Falling
+        } else {
+            return None;
+        };
+        match self.direction {
+            Direction::Both => Some(direction),
+            filter if filter == direction => Some(direction),
+            _ => None,
+        }
+    }
+
+    /// Locates the crossings of the events over one step, in time order
+    ///
+    /// Stops after the first terminal event.
+    pub(crate) fn locate_all(
+        events: &[Self],
+        step: &DenseStep<V>,
+        (t0, y0, y0_prime): (f64, V, V),
+        (t1, y1, y1_prime): (f64, V, V),
+    ) -> Vec<EventRecord<V>> {
+        let mut records: Vec<EventRecord<V>> = events
+            .iter()
+            .enumerate()
+            .filter_map(|(index, event)| {
+                let g0 = event.evaluate(t0, y0, y0_prime);
+                let g1 = event.evaluate(t1, y1, y1_prime);
+                let direction = event.crossing(g0, g1)?;
+                let t = event.locate(step, (t0, g0), (t1, g1));
+                let (y, y_prime) = step.eval(t);
+                Some(EventRecord {
+                    index,
+                    t,
+                    y,
+                    y_prime,
+                    direction,
+                })
+            })
+            .collect();
+        records.sort_by(|a, b| a.t.total_cmp(&b.t));
+        if let Some(first_terminal) = records.iter().position(|r| events[r.index].terminal) {
+            records.truncate(first_terminal + 1);
+        }
+        records
+    }
+
+    /// Illinois (modified regula falsi) iteration on the interpolated event function
+    fn locate(&self, step: &DenseStep<V>, (t0, g0): (f64, f64), (t1, g1): (f64, f64)) -> f64 {
+        const MAX_ITERATIONS: usize = 100;
+        let g = |t: f64| {
+            let (y, y_prime) = step.eval(t);
+            self.evaluate(t, y, y_prime)
+        };
+        let tolerance = 4.0 * f64::EPSILON * t1.abs().max(1.0);
+        let (mut a, mut ga) = (t0, g0);
+        let (mut b, mut gb) = (t1, g1);
+        let mut side = 0;
+        for _ in 0..MAX_ITERATIONS {
+            if gb == 0.0 || (b - a).abs() <= tolerance {
+                break;
+            }
+            let c = (a * gb - b * ga) / (gb - ga);
+            let gc = g(c);
+            if gc == 0.0 {
+                return c;
+            }
+            if gc.signum() == gb.signum() {
+                b = c;
+                gb = gc;
+                if side == -1 {
+                    ga /= 2.0;
+                }
+                side = -1;
+            } else {
+                a = c;
+                ga = gc;
+                if side == 1 {
+                    gb /= 2.0;
+                }
+                side = 1;
+            }
+        }
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::math::core::{InnerProduct, Vector, VectorSpace};
+    use crate::math::integrate::adaptive::{DormandPrince45, Tolerance};
+    use crate::math::integrate::{FirstOrderSystem, RK4Method, Solver, System};
+
+    use super::*;
+
+    struct TestHarmonicOscillator;
+    struct TestFreeFall;
+    struct TestKeplerOrbit;
+    struct TestDecay;
+
+    /// y'' + y = 0
+    impl System for TestHarmonicOscillator {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            -y
+        }
+    }
+
+    /// y'' = -9.8
+    impl System for TestFreeFall {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, _y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            -9.8
+        }
+    }
+
+    /// r'' = -r / |r|^3
+    impl System for TestKeplerOrbit {
+        type Vector = Vector<f64, 2>;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            let r_sq = y.norm_sq();
+            -y / (r_sq * r_sq.sqrt())
+        }
+    }
+
+    /// y' = -y
+    impl FirstOrderSystem for TestDecay {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector) -> Self::Vector {
+            -y
+        }
+    }
+
+    #[test]
+    fn test_event_crossing_located_inside_step() {
+        let mut test_solver = Solver::new(RK4Method, TestHarmonicOscillator, 1.0, 0.0);
+        test_solver.add_event(Event::new(|_t, y, _y_prime| y));
+        test_solver.run(0.05, 200);
+
+        let events = test_solver.get_events();
+        assert_eq!(events.len(), 3, "cos(t) has 3 zeros in [0, 10]");
+        for (k, event) in events.iter().enumerate() {
+            let exact_t = PI / 2.0 + k as f64 * PI;
+            assert!(
+                (event.t - exact_t).abs() < 1e-6,
+                "Event time {} expected {}",
+                event.t,
+                exact_t
+            );
+            assert!(event.y.abs() < 1e-6, "Event state must be on the crossing");
+        }
+        assert_eq!(events[0].direction, Direction::Falling);
+        assert_eq!(events[1].direction, Direction::Rising);
+    }
+
+    #[test]
+    fn test_event_exact_root() {
+        // The first secant step of a linear g lands exactly on the root at t = 1
+        let event = Event::new(|_t, y: f64, _y_prime| y);
+        for (y0, y1) in [(1.0, -3.0), (-1.0, 3.0)] {
+            let slope = (y1 - y0) / 4.0;
+            let step = DenseStep::hermite((0.0, y0, slope), (4.0, y1, slope), None);
+            assert_eq!(step.eval(1.0).0, 0.0);
+            assert_eq!(
+                event.locate(&step, (0.0, y0), (4.0, y1)),
+                1.0,
+                "{y0} -> {y1}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_event_direction_filter() {
+        let mut test_solver = Solver::new(RK4Method, TestHarmonicOscillator, 1.0, 0.0);
+        test_solver.add_event(Event::new(|_t, y, _y_prime| y).direction(Direction::Rising));
+        test_solver.run(0.05, 200);
+
+        let events = test_solver.get_events();
+        assert_eq!(events.len(), 1, "Only rising crossings are recorded");
+        assert!((events[0].t - 1.5 * PI).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_terminal_event_stops_run() {
+        let mut test_solver = Solver::new(RK4Method, TestFreeFall, 10.0, 0.0);
+        let wall = test_solver.add_event(Event::new(|_t, y, _y_prime| y).terminal(true));
+        test_solver.run(0.1, 1000);
+
+        let exact_t = (2.0 * 10.0 / 9.8f64).sqrt();
+        let (t, y, y_prime) = test_solver.get_current();
+        assert!(
+            test_solver.is_terminated(),
+            "Terminal event must stop the run"
+        );
+        assert!(
+            (t - exact_t).abs() < 1e-12,
+            "Stopped at {t}, expected {exact_t}"
+        );
+        assert!(y.abs() < 1e-12, "Particle must stop on the wall");
+        assert!((y_prime + 9.8 * exact_t).abs() < 1e-12);
+
+        let events = test_solver.get_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].index, wall);
+        let (ts, _, _) = test_solver.get_results();
+        assert_eq!(*ts.last().unwrap(), t, "Results end at the event");
+    }
+
+    #[test]
+    fn test_periapsis_event_with_adaptive_steps() {
+        // Start at apoapsis, r . v crosses zero upward at periapsis
+        let e: f64 = 0.6;
+        let y0 = Vector::new([1.0 + e, 0.0]);
+        let y0_prime = Vector::new([0.0, ((1.0 - e) / (1.0 + e)).sqrt()]);
+        let period = 2.0 * PI;
+
+        let integrator = DormandPrince45::new(Tolerance::new(1e-10, 1e-10));
+        let mut test_solver = Solver::new(integrator, TestKeplerOrbit, y0, y0_prime);
+        test_solver.add_event(
+            Event::new(|_t, y: Vector<f64, 2>, y_prime| y.dot(y_prime))
+                .direction(Direction::Rising),
+        );
+        test_solver.run_until(2.0 * period);
+
+        let events = test_solver.get_events();
+        assert_eq!(events.len(), 2, "Two periapsis passages");
+        for (k, event) in events.iter().enumerate() {
+            let exact_t = (k as f64 + 0.5) * period;
+            assert!(
+                (event.t - exact_t).abs() < 1e-6,
+                "Periapsis at {} expected {}",
+                event.t,
+                exact_t
+            );
+            assert!((event.y.norm() - (1.0 - e)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_first_order_threshold_event() {
+        let mut test_solver = Solver::first_order(RK4Method, TestDecay, 1.0);
+        test_solver.add_event(Event::new(|_t, y, _y_prime| y - 0.5).terminal(true));
+        test_solver.run(0.25, 100);
+
+        let (t, _, _) = test_solver.get_current();
+        assert!((t - 2.0f64.ln()).abs() < 1e-4, "Half-life {t}");
+    }
+}
@@ -0,0 +1,375 @@
+use crate::math::integrate::adaptive::{AdaptiveIntegrator, Tolerance, cover_step, initial_step};
+use crate::math::integrate::{FirstOrderSystem, Integrator};
+
+/// Gragg-Bulirsch-Stoer extrapolation method
+///
+/// Row `j` of the extrapolation table is the modified midpoint rule with
+/// $$ n_j = 2j $$ substeps, extrapolated to $$ h \to 0 $$ in powers of $$ h^2 $$
+/// (Aitken-Neville), so column `j` has order `2j`.
+/// The error of the diagonal is estimated from the previous column.
+/// The step size and the number of columns are chosen to minimize the work per
+/// unit step (Hairer, Nørsett & Wanner, Solving ODE I, II.9).
+pub struct BulirschStoer {
+    tolerance: Tolerance,
+    h: Option<f64>,
+    columns: usize,
+    max_columns: usize,
+    accepted: usize,
+    rejected: usize,
+}
+
+impl BulirschStoer {
+    const SAFETY: f64 = 0.94;
+    const TARGET: f64 = 0.65;
+    const MIN_FACTOR: f64 = 0.02;
+    const MAX_FACTOR: f64 = 4.0;
+
+    pub fn new(tolerance: Tolerance) -> Self {
+        Self {
+            tolerance,
+            h: None,
+            columns: 4,
+            max_columns: 8,
+            accepted: 0,
+            rejected: 0,
+        }
+    }
+
+    /// Sets the first trial step instead of estimating it
+    pub fn with_initial_step(mut self, h: f64) -> Self {
+        self.h = Some(h);
+        self
+    }
+
+    /// Limits the extrapolation table to `max_columns` (order `2 max_columns`)
+    pub fn with_max_columns(mut self, max_columns: usize) -> Self {
+        if max_columns < 3 {
+            panic!("BulirschStoer: max_columns must be at least 3, got {max_columns}");
+        }
+        self.max_columns = max_columns;
+        self.columns = self.columns.min(max_columns - 1);
+        self
+    }
+
+    pub fn tolerance(&self) -> Tolerance {
+        self.tolerance
+    }
+
+    /// Target number of columns for the next step
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn accepted_steps(&self) -> usize {
+        self.accepted
+    }
+
+    pub fn rejected_steps(&self) -> usize {
+        self.rejected
+    }
+
+    fn substeps(j: usize) -> usize {
+        2 * j
+    }
+
+    /// Derivative evaluations for rows `1..=j`, sharing $$ f(t, y) $$
+    fn work(j: usize) -> f64 {
+        (1 + (1..=j).map(Self::substeps).sum::<usize>()) as f64
+    }
+
+    /// Gragg's modified midpoint rule with `n` substeps and the final smoothing step
+    fn modified_midpoint<F: FirstOrderSystem>(
+        system: &F,
+        t: f64,
+        y: F::Vector,
+        f0: F::Vector,
+        h: f64,
+        n: usize,
+    ) -> F::Vector {
+        let dt = h / n as f64;
+        let mut z0 = y;
+        let mut z1 = y + f0 * dt;
+        for i in 1..n {
+            let z2 = z0 + system.derivative(t + i as f64 * dt, z1) * (2.0 * dt);
+            z0 = z1;
+            z1 = z2;
+        }
+        (z0 + z1 + system.derivative(t + h, z1) * dt) * 0.5
+    }
+}
+
+impl Default for BulirschStoer {
+    fn default() -> Self {
+        Self::new(Tolerance::default())
+    }
+}
+
+impl AdaptiveIntegrator for BulirschStoer {
+    fn adaptive_step_first_order<F>(
+        &mut self,
+        system: &F,
+        t: f64,
+        y: F::Vector,
+        h_max: f64,
+    ) -> (f64, F::Vector)
+    where
+        F: FirstOrderSystem,
+    {
+        let f0 = system.derivative(t, y);
+        let h_suggested = match self.h {
+            Some(h) => h,
+            None => initial_step(system, t, y, f0, 2 * self.columns, &self.tolerance, h_max),
+        };
+        let mut h = h_suggested.min(h_max);
+        let mut clamped = h < h_suggested;
+        loop {
+            if h <= f64::EPSILON * t.abs().max(1.0) {
+                panic!("BulirschStoer: step size underflow at t = {t}");
+            }
+            let k = self.columns;
+            let last = (k + 1).min(self.max_columns);
+            // h_opt[j] and work[j] of row j, estimated from its error
+            let mut h_opt = vec![0.0; last + 1];
+            let mut work = vec![f64::INFINITY; last + 1];
+            let mut previous: Vec<F::Vector> = Vec::new();
+            let mut accepted = None;
+            for j in 1..=last {
+                let n = Self::substeps(j);
+                let mut row = vec![Self::modified_midpoint(system, t, y, f0, h, n)];
+                for m in 1..j {
+                    let ratio = (n as f64 / Self::substeps(j - m) as f64).powi(2);
+                    let correction = (row[m - 1] - previous[m - 1]) / (ratio - 1.0);
+                    row.push(row[m - 1] + correction);
+                }
+                if j >= 2 {
+                    let err = self
+                        .tolerance
+                        .error_norm(y, row[j - 1], row[j - 1] - row[j - 2]);
+                    let factor = if err == 0.0 {
+                        Self::MAX_FACTOR
+                    } else {
+                        (Self::SAFETY * (Self::TARGET / err).powf(1.0 / (2 * j - 1) as f64))
+                            .clamp(Self::MIN_FACTOR, Self::MAX_FACTOR)
+                    };
+                    h_opt[j] = h * factor;
+                    work[j] = Self::work(j) / h_opt[j];
+                    if j + 1 >= k && err <= 1.0 {
+                        accepted = Some((j, row[j - 1]));
+                        break;
+                    }
+                }
+                previous = row;
+            }
+
+            let Some((j, y_new)) = accepted else {
+                self.rejected += 1;
+                clamped = false;
+                self.columns = (k - 1).max(2);
+                h = h_opt[last].min(0.5 * h);
+                continue;
+            };
+
+            // Order and step size for the next step
+            let (columns, h_next) = if j >= 3 && work[j - 1] < 0.8 * work[j] {
+                (j - 1, h_opt[j - 1])
+            } else if j < self.max_columns - 1 && (j == 2 || work[j] < 0.9 * work[j - 1]) {
+                (j + 1, h_opt[j] * Self::work(j + 1) / Self::work(j))
+            } else {
+                (j, h_opt[j])
+            };
+            self.columns = columns.max(2);
+            // A step clamped by `h_max` must not shrink the next suggestion
+            self.h = Some(if clamped {
+                h_next.max(h_suggested)
+            } else {
+                h_next
+            });
+            self.accepted += 1;
+            return (h, y_new);
+        }
+    }
+}
+
+impl Integrator for BulirschStoer {
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
+    where
+        F: FirstOrderSystem,
+    {
+        cover_step(self, system, t, y, h)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::f64::consts::PI;
+
+    use crate::math::complex::{Complex, ComplexSpace};
+    use crate::math::core::{LinearSpace, ScalarSpace, Vector, VectorSpace};
+    use crate::math::integrate::adaptive::DormandPrince45;
+    use crate::math::integrate::{Solver, System};
+
+    use super::*;
+
+    /// r'' = -r / |r|^3, counting derivative evaluations
+    struct TestKeplerOrbit {
+        evaluations: Cell<usize>,
+    }
+
+    impl TestKeplerOrbit {
+        fn new() -> Self {
+            Self {
+                evaluations: Cell::new(0),
+            }
+        }
+    }
+
+    impl System for TestKeplerOrbit {
+        type Vector = Vector<f64, 2>;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            self.evaluations.set(self.evaluations.get() + 1);
+            let r_sq = y[0] * y[0] + y[1] * y[1];
+            -y / (r_sq * r_sq.sqrt())
+        }
+    }
+
+    /// z' = i z
+    struct TestRotation;
+
+    impl FirstOrderSystem for TestRotation {
+        type Vector = Complex;
+
+        fn derivative(&self, _t: f64, y: Self::Vector) -> Self::Vector {
+            Complex::i() * y
+        }
+    }
+
+    fn kepler_initial_state(e: f64) -> (Vector<f64, 2>, Vector<f64, 2>) {
+        (
+            Vector::new([1.0 - e, 0.0]),
+            Vector::new([0.0, ((1.0 + e) / (1.0 - e)).sqrt()]),
+        )
+    }
+
+    #[test]
+    fn test_extrapolation_order() {
+        // One step without control: each extra column gains two orders
+        let errors: Vec<f64> = (1..=4)
+            .map(|j| {
+                let y0 = Complex::new(1.0, 0.0);
+                let h = 0.5;
+                let mut previous: Vec<Complex> = Vec::new();
+                let mut row = Vec::new();
+                for i in 1..=j {
+                    let n = BulirschStoer::substeps(i);
+                    let f0 = TestRotation.derivative(0.0, y0);
+                    row = vec![BulirschStoer::modified_midpoint(
+                        &TestRotation,
+                        0.0,
+                        y0,
+                        f0,
+                        h,
+                        n,
+                    )];
+                    for m in 1..i {
+                        let ratio = (n as f64 / BulirschStoer::substeps(i - m) as f64).powi(2);
+                        row.push(row[m - 1] + (row[m - 1] - previous[m - 1]) / (ratio - 1.0));
+                    }
+                    previous = row.clone();
+                }
+                (row[j - 1] - Complex::cis(h)).abs()
+            })
+            .collect();
+        for j in 1..errors.len() {
+            assert!(
+                errors[j] < 0.05 * errors[j - 1],
+                "Column {} does not improve: {:?}",
+                j + 1,
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_bulirsch_stoer_complex() {
+        let integrator = BulirschStoer::new(Tolerance::new(1e-12, 1e-12));
+        let mut test_solver = Solver::first_order(integrator, TestRotation, Complex::new(1.0, 0.0));
+        test_solver.run_until(10.0);
+
+        let (t, z, _) = test_solver.get_current();
+        assert_eq!(t, 10.0);
+        assert!((z - Complex::cis(10.0)).abs() < 1e-10, "z = {z:?}");
+    }
+
+    #[test]
+    fn test_bulirsch_stoer_kepler_beats_dormand_prince() {
+        // e = 0.5, ten periods to 1e-12: far fewer evaluations than DP45
+        let (y0, y0_prime) = kepler_initial_state(0.5);
+        let t_end = 10.0 * 2.0 * PI;
+        let tolerance = Tolerance::new(1e-13, 1e-13);
+
+        let mut bs_solver = Solver::new(
+            BulirschStoer::new(tolerance),
+            TestKeplerOrbit::new(),
+            y0,
+            y0_prime,
+        );
+        bs_solver.run_until(t_end);
+        let (_, y_bs, _) = bs_solver.get_current();
+        let bs_error = (y_bs - y0).norm();
+        let bs_evaluations = bs_solver.system.evaluations.get();
+
+        let mut dp_solver = Solver::new(
+            DormandPrince45::new(tolerance),
+            TestKeplerOrbit::new(),
+            y0,
+            y0_prime,
+        );
+        dp_solver.run_until(t_end);
+        let dp_evaluations = dp_solver.system.evaluations.get();
+
+        assert!(bs_error < 1e-9, "Orbit is not closed: {bs_error}");
+        assert!(
+            bs_evaluations < dp_evaluations,
+            "Bulirsch-Stoer {bs_evaluations} vs Dormand-Prince {dp_evaluations} evaluations"
+        );
+        assert!(
+            bs_solver.integrator.columns() >= 4,
+            "High accuracy selects high order: {}",
+            bs_solver.integrator.columns()
+        );
+    }
+
+    #[test]
+    fn test_bulirsch_stoer_tolerance_controls_error() {
+        let (y0, y0_prime) = kepler_initial_state(0.9);
+        let mut errors = Vec::new();
+        for tol in [1e-6, 1e-11] {
+            let integrator = BulirschStoer::new(Tolerance::new(tol, tol)).with_initial_step(1.0);
+            let mut test_solver = Solver::new(integrator, TestKeplerOrbit::new(), y0, y0_prime);
+            test_solver.run_until(2.0 * PI);
+            let (_, y, _) = test_solver.get_current();
+            errors.push((y - y0).norm());
+            assert!(
+                test_solver.integrator.rejected_steps() > 0,
+                "Oversized initial step must be rejected"
+            );
+        }
+        assert!(errors[1] < 1e-7, "Errors {errors:?}");
+        assert!(errors[1] < errors[0] * 1e-2, "Errors {errors:?}");
+    }
+
+    #[test]
+    fn test_bulirsch_stoer_fixed_step() {
+        let integrator = BulirschStoer::new(Tolerance::new(1e-12, 1e-12)).with_max_columns(5);
+        let (y0, y0_prime) = kepler_initial_state(0.0);
+        let mut test_solver = Solver::new(integrator, TestKeplerOrbit::new(), y0, y0_prime);
+        test_solver.run(PI / 2.0, 4);
+
+        let (_, y, y_prime) = test_solver.get_current();
+        assert!((y - y0).norm() < 1e-10);
+        assert!((y_prime - y0_prime).norm() < 1e-10);
+        assert_eq!(test_solver.get_results().0.len(), 5);
+    }
+}
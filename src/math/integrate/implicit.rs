@@ -0,0 +1,521 @@
+use crate::math::core::LinearSpace;
+use crate::math::integrate::adaptive::Tolerance;
+use crate::math::integrate::{FirstOrderSystem, Integrator};
+
+/// Newton iteration settings for the implicit stage equations
+#[derive(Clone, Copy, Debug)]
+pub struct Newton {
+    /// Convergence is reached when the weighted norm of the Newton update is at most 1
+    pub tolerance: Tolerance,
+    /// Iterations before the Jacobian is re-evaluated at the current iterate
+    pub max_iterations: usize,
+}
+
+impl Newton {
+    /// Jacobian re-evaluations before giving up
+    const MAX_REFRESHES: usize = 4;
+
+    pub fn new(tolerance: Tolerance, max_iterations: usize) -> Self {
+        Self {
+            tolerance,
+            max_iterations,
+        }
+    }
+
+    /// Solves the stage equation $$ z = r + \gamma h F(t, z) $$ starting from `guess`
+    ///
+    /// Simplified Newton iteration: the Jacobian is evaluated once and refreshed
+    /// only when the iteration stalls.
+    pub(crate) fn solve<F: FirstOrderSystem>(
+        &self,
+        system: &F,
+        t: f64,
+        r: F::Vector,
+        gamma_h: f64,
+        guess: F::Vector,
+    ) -> F::Vector {
+        let n = F::Vector::dim();
+        let mut z = guess;
+        for _ in 0..=Self::MAX_REFRESHES {
+            let mut m = jacobian(system, t, z);
+            for (i, m_ij) in m.iter_mut().enumerate() {
+                *m_ij = if i / n == i % n { 1.0 } else { 0.0 } - gamma_h * *m_ij;
+            }
+            let pivots = lu_decompose(&mut m, n);
+
+            for _ in 0..self.max_iterations {
+                let mut delta = (r + system.derivative(t, z) * gamma_h - z).components();
+                lu_solve(&m, &pivots, &mut delta);
+                let delta = F::Vector::from_components(&delta);
+                z = z + delta;
+                if self.tolerance.norm(z, delta) <= 1.0 {
+                    return z;
+                }
+            }
+        }
+        panic!("Newton iteration did not converge at t = {t}");
+    }
+}
+
+impl Default for Newton {
+    fn default() -> Self {
+        Self::new(Tolerance::new(1e-12, 1e-12), 10)
+    }
+}
+
+/// Jacobian of `system` at `(t, y)`, analytic if supplied, otherwise by forward differences
+pub(crate) fn jacobian<F: FirstOrderSystem>(system: &F, t: f64, y: F::Vector) -> Vec<f64> {
+    system
+        .jacobian(t, y)
+        .unwrap_or_else(|| numerical_jacobian(system, t, y))
+}
+
+/// Forward-difference Jacobian, row-major over the real components
+pub(crate) fn numerical_jacobian<F: FirstOrderSystem>(
+    system: &F,
+    t: f64,
+    y: F::Vector,
+) -> Vec<f64> {
+    let n = F::Vector::dim();
+    let f0 = system.derivative(t, y).components();
+    let mut yc = y.components();
+    let mut jacobian = vec![0.0; n * n];
+    for j in 0..n {
+        let y_j = yc[j];
+        let delta = f64::EPSILON.sqrt() * y_j.abs().max(1.0);
+        yc[j] = y_j + delta;
+        let f1 = system
+            .derivative(t, F::Vector::from_components(&yc))
+            .components();
+        yc[j] = y_j;
+        for i in 0..n {
+            jacobian[i * n + j] = (f1[i] - f0[i]) / delta;
+        }
+    }
+    jacobian
+}
+
+/// One implicit midpoint step, also the base step of the symplectic compositions
+pub(crate) fn implicit_midpoint<F: FirstOrderSystem>(
+    newton: &Newton,
+    system: &F,
+    t: f64,
+    y: F::Vector,
+    h: f64,
+) -> F::Vector {
+    let w = newton.solve(system, t + h / 2.0, y, h / 2.0, y);
+    w * 2.0 - y
+}
+
+/// In-place LU decomposition with partial pivoting of a row-major `n x n` matrix
+///
+/// Returns the row permutation.
+pub(crate) fn lu_decompose(a: &mut [f64], n: usize) -> Vec<usize> {
+    let mut pivots: Vec<usize> = (0..n).collect();
+    for k in 0..n {
+        let p = (k..n)
+            .max_by(|&i, &j| a[i * n + k].abs().total_cmp(&a[j * n + k].abs()))
+            .unwrap();
+        if a[p * n + k] == 0.0 {
+            panic!("LU decomposition: singular matrix");
+        }
+        if p != k {
+            for j in 0..n {
+                a.swap(k * n + j, p * n + j);
+            }
+            pivots.swap(k, p);
+        }
+        for i in (k + 1)..n {
+            let l = a[i * n + k] / a[k * n + k];
+            a[i * n + k] = l;
+            for j in (k + 1)..n {
+                a[i * n + j] -= l * a[k * n + j];
+            }
+        }
+    }
+    pivots
+}
+
+/// Solves `A x = b` in place with the output of `lu_decompose`
+pub(crate) fn lu_solve(lu: &[f64], pivots: &[usize], b: &mut [f64]) {
+    let n = pivots.len();
+    let mut x: Vec<f64> = pivots.iter().map(|&p| b[p]).collect();
+    for i in 0..n {
+        for j in 0..i {
+            x[i] -= lu[i * n + j] * x[j];
+        }
+    }
+    for i in (0..n).rev() {
+        for j in (i + 1)..n {
+            x[i] -= lu[i * n + j] * x[j];
+        }
+        x[i] /= lu[i * n + i];
+    }
+    b.copy_from_slice(&x);
+}
+
+/// Backward Euler Integration
+///
+/// $$ z_{n+1} = z_n + h F(t_{n+1}, z_{n+1}) $$
+/// 1st-order, L-stable. Solves the stage equation by Newton iteration.
+pub struct BackwardEulerMethod {
+    newton: Newton,
+}
+
+/// Implicit Midpoint Integration
+///
+/// $$ z_{n+1} = z_n + h F\left(t_n + \frac{h}{2}, \frac{z_n + z_{n+1}}{2}\right) $$
+/// 2nd-order, A-stable and symplectic.
+pub struct ImplicitMidpointMethod {
+    newton: Newton,
+}
+
+/// Backward Differentiation Formula (BDF2 - BDF5)
+///
+/// $$ z_{n+1} = \sum_{j=1}^{k} \alpha_j z_{n+1-j} + \beta h F(t_{n+1}, z_{n+1}) $$
+/// Multistep method for stiff systems with a fixed step size.
+/// The first `k - 1` steps (and every restart after the step size or the
+/// state changes outside the integrator) use an L-stable SDIRK4 method.
+pub struct BdfMethod {
+    order: usize,
+    newton: Newton,
+    h: f64,
+    history: Vec<Vec<f64>>,
+}
+
+impl BackwardEulerMethod {
+    pub fn new(newton: Newton) -> Self {
+        Self { newton }
+    }
+}
+
+impl Default for BackwardEulerMethod {
+    fn default() -> Self {
+        Self::new(Newton::default())
+    }
+}
+
+impl ImplicitMidpointMethod {
+    pub fn new(newton: Newton) -> Self {
+        Self { newton }
+    }
+}
+
+impl Default for ImplicitMidpointMethod {
+    fn default() -> Self {
+        Self::new(Newton::default())
+    }
+}
+
+impl BdfMethod {
+    /// `alpha_j` for `z_{n+1-j}` and `beta` of each order
+    const ALPHA: [&'static [f64]; 5] = [
+        &[1.0],
+        &[4.0 / 3.0, -1.0 / 3.0],
+        &[18.0 / 11.0, -9.0 / 11.0, 2.0 / 11.0],
+        &[48.0 / 25.0, -36.0 / 25.0, 16.0 / 25.0, -3.0 / 25.0],
+        &[
+            300.0 / 137.0,
+            -300.0 / 137.0,
+            200.0 / 137.0,
+            -75.0 / 137.0,
+            12.0 / 137.0,
+        ],
+    ];
+    const BETA: [f64; 5] = [1.0, 2.0 / 3.0, 6.0 / 11.0, 12.0 / 25.0, 60.0 / 137.0];
+
+    pub fn new(order: usize, newton: Newton) -> Self {
+        if !(2..=5).contains(&order) {
+            panic!("BDF order must be in 2..=5, got {order}");
+        }
+        Self {
+            order,
+            newton,
+            h: 0.0,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+}
+
+/// L-stable, stiffly accurate SDIRK method of order 4 (Hairer & Wanner, Solving ODE II, IV.6)
+fn sdirk4<F: FirstOrderSystem>(
+    newton: &Newton,
+    system: &F,
+    t: f64,
+    z: F::Vector,
+    h: f64,
+) -> F::Vector {
+    const GAMMA: f64 = 1.0 / 4.0;
+    const C: [f64; 5] = [1.0 / 4.0, 3.0 / 4.0, 11.0 / 20.0, 1.0 / 2.0, 1.0];
+    const A: [[f64; 4]; 5] = [
+        [0.0, 0.0, 0.0, 0.0],
+        [1.0 / 2.0, 0.0, 0.0, 0.0],
+        [17.0 / 50.0, -1.0 / 25.0, 0.0, 0.0],
+        [371.0 / 1360.0, -137.0 / 2720.0, 15.0 / 544.0, 0.0],
+        [25.0 / 24.0, -49.0 / 48.0, 125.0 / 16.0, -85.0 / 12.0],
+    ];
+
+    let zero = F::Vector::zero();
+    let mut k = [zero; 5];
+    let mut stage = z;
+    for i in 0..5 {
+        let r = z + (0..i).fold(zero, |acc, j| acc + k[j] * A[i][j]) * h;
+        stage = newton.solve(system, t + C[i] * h, r, GAMMA * h, stage);
+        k[i] = (stage - r) / (GAMMA * h);
+    }
+    stage
+}
+
+impl Integrator for BackwardEulerMethod {
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
+    where
+        F: FirstOrderSystem,
+    {
+        self.newton.solve(system, t + h, y, h, y)
+    }
+}
+
+impl Integrator for ImplicitMidpointMethod {
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
+    where
+        F: FirstOrderSystem,
+    {
+        implicit_midpoint(&self.newton, system, t, y, h)
+    }
+}
+
+impl Integrator for BdfMethod {
+    fn step_first_order<F>(&mut self, system: &F, t: f64, z: F::Vector, h: f64) -> F::Vector
+    where
+        F: FirstOrderSystem,
+    {
+        let zc = z.components();
+        if h != self.h || self.history.last() != Some(&zc) {
+            self.history = vec![zc];
+            self.h = h;
+        }
+
+        let z_new = if self.history.len() < self.order {
+            sdirk4(&self.newton, system, t, z, h)
+        } else {
+            let k = self.order;
+            let r = Self::ALPHA[k - 1]
+                .iter()
+                .zip(self.history.iter().rev())
+                .fold(F::Vector::zero(), |acc, (alpha, z_j)| {
+                    acc + F::Vector::from_components(z_j) * *alpha
+                });
+            // Extrapolated predictor from the last two steps
+            let guess = z * 2.0 - F::Vector::from_components(&self.history[self.history.len() - 2]);
+            self.newton
+                .solve(system, t + h, r, Self::BETA[k - 1] * h, guess)
+        };
+
+        self.history.push(z_new.components());
+        if self.history.len() > self.order {
+            self.history.remove(0);
+        }
+        z_new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::math::integrate::{Jacobian, RK4Method, Solver, System};
+    use crate::physics::harmonic_oscillator::DampedHarmonicOscillator;
+
+    use super::*;
+
+    struct TestHarmonicOscillator;
+
+    /// y'' + y = 0
+    impl System for TestHarmonicOscillator {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            -y
+        }
+    }
+
+    /// y'' = mu (1 - y^2) y' - y
+    struct TestVanDerPol {
+        mu: f64,
+        jacobian_calls: Cell<usize>,
+        analytic: bool,
+    }
+
+    impl System for TestVanDerPol {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, y_prime: Self::Vector) -> Self::Vector {
+            self.mu * (1.0 - y * y) * y_prime - y
+        }
+
+        fn jacobian(&self, _t: f64, y: Self::Vector, y_prime: Self::Vector) -> Option<Jacobian> {
+            if !self.analytic {
+                return None;
+            }
+            self.jacobian_calls.set(self.jacobian_calls.get() + 1);
+            Some(Jacobian {
+                df_dy: vec![-2.0 * self.mu * y * y_prime - 1.0],
+                df_dy_prime: vec![self.mu * (1.0 - y * y)],
+            })
+        }
+    }
+
+    /// Exact solution of y'' + b y' + k y = 0 (overdamped)
+    fn overdamped_exact(k: f64, b: f64, y0: f64, y0_prime: f64, t: f64) -> f64 {
+        let d = (b * b - 4.0 * k).sqrt();
+        let (r1, r2) = ((-b + d) / 2.0, (-b - d) / 2.0);
+        let c2 = (y0_prime - r1 * y0) / (r2 - r1);
+        let c1 = y0 - c2;
+        c1 * (r1 * t).exp() + c2 * (r2 * t).exp()
+    }
+
+    fn oscillator_error<I: Integrator>(integrator: I, h: f64) -> f64 {
+        let steps = (2.0 / h).round() as usize;
+        let mut test_solver = Solver::new(integrator, TestHarmonicOscillator, 1.0, 0.0);
+        test_solver.run(h, steps);
+        let (_, y, _) = test_solver.get_current();
+        (y - 2.0f64.cos()).abs()
+    }
+
+    fn assert_convergence_order<I: Integrator, F: Fn() -> I>(
+        name: &str,
+        integrator: F,
+        order: f64,
+    ) {
+        let coarse = oscillator_error(integrator(), 0.02);
+        let fine = oscillator_error(integrator(), 0.01);
+        let measured = (coarse / fine).log2();
+        assert!(
+            (measured - order).abs() < 0.3,
+            "{name}: expected order {order}, measured {measured}"
+        );
+    }
+
+    #[test]
+    fn test_implicit_convergence_order() {
+        assert_convergence_order("BackwardEulerMethod", BackwardEulerMethod::default, 1.0);
+        assert_convergence_order(
+            "ImplicitMidpointMethod",
+            ImplicitMidpointMethod::default,
+            2.0,
+        );
+        for order in 2..=5 {
+            assert_convergence_order(
+                &format!("BdfMethod({order})"),
+                || BdfMethod::new(order, Newton::default()),
+                order as f64,
+            );
+        }
+    }
+
+    #[test]
+    fn test_stiff_damped_oscillator() {
+        let (k, b) = (1.0, 1000.0);
+        let (y0, y0_prime) = (1.0, 0.0);
+        let h = 0.1;
+        let steps = 20;
+        let exact_y = overdamped_exact(k, b, y0, y0_prime, h * steps as f64);
+
+        let mut rk4_solver =
+            Solver::new(RK4Method, DampedHarmonicOscillator { k, b }, y0, y0_prime);
+        rk4_solver.run(h, steps);
+        let (_, y_rk4, _) = rk4_solver.get_current();
+        assert!(
+            !y_rk4.is_finite() || y_rk4.abs() > 1e3,
+            "RK4 is expected to be unstable at h * b = {}",
+            h * b
+        );
+
+        let mut be_solver = Solver::new(
+            BackwardEulerMethod::default(),
+            DampedHarmonicOscillator { k, b },
+            y0,
+            y0_prime,
+        );
+        be_solver.run(h, steps);
+        let (_, y_be, _) = be_solver.get_current();
+        let error = (y_be - exact_y).abs();
+        assert!(error < 1e-3, "BackwardEulerMethod error too large: {error}");
+
+        for order in 2..=5 {
+            let integrator = BdfMethod::new(order, Newton::default());
+            let mut bdf_solver =
+                Solver::new(integrator, DampedHarmonicOscillator { k, b }, y0, y0_prime);
+            bdf_solver.run(h, steps);
+            let (_, y_bdf, _) = bdf_solver.get_current();
+            let error = (y_bdf - exact_y).abs();
+            assert!(error < 1e-4, "BdfMethod({order}) error too large: {error}");
+        }
+    }
+
+    #[test]
+    fn test_implicit_midpoint_energy_conservation() {
+        let y0 = 1.0;
+        let y0_prime = 0.0;
+        let mut test_solver = Solver::new(
+            ImplicitMidpointMethod::default(),
+            TestHarmonicOscillator,
+            y0,
+            y0_prime,
+        );
+        test_solver.run(0.1, 1000);
+        let (_, y, y_prime) = test_solver.get_current();
+
+        // Quadratic invariants are conserved exactly by the implicit midpoint rule
+        let error = (0.5 * y_prime * y_prime + 0.5 * y * y - 0.5).abs();
+        assert!(
+            error < 1e-10,
+            "ImplicitMidpointMethod: Energy is not conserved: {error}"
+        );
+    }
+
+    #[test]
+    fn test_analytic_jacobian_matches_finite_differences() {
+        let run = |analytic: bool| {
+            let system = TestVanDerPol {
+                mu: 1000.0,
+                jacobian_calls: Cell::new(0),
+                analytic,
+            };
+            let mut test_solver =
+                Solver::new(BdfMethod::new(3, Newton::default()), system, 2.0, 0.0);
+            test_solver.run(0.01, 100);
+            let (_, y, _) = test_solver.get_current();
+            (y, test_solver.system.jacobian_calls.get())
+        };
+        let (y_analytic, calls) = run(true);
+        let (y_numerical, _) = run(false);
+
+        assert!(calls > 0, "System::jacobian must be used when supplied");
+        assert!(
+            y_analytic.abs() <= 2.0,
+            "Van der Pol solution left the slow manifold: {y_analytic}"
+        );
+        assert!(
+            (y_analytic - y_numerical).abs() < 1e-6,
+            "Analytic: {y_analytic}  Finite differences: {y_numerical}"
+        );
+    }
+
+    #[test]
+    fn test_lu_solve() {
+        let mut a = vec![0.0, 2.0, 1.0, 1.0, 1.0, 0.0, 2.0, 0.0, 3.0];
+        let mut b = vec![7.0, 3.0, 11.0];
+        let pivots = lu_decompose(&mut a, 3);
+        lu_solve(&a, &pivots, &mut b);
+
+        let expected = [1.0, 2.0, 3.0];
+        for i in 0..3 {
+            assert!((b[i] - expected[i]).abs() < 1e-12, "LU solve: {:?}", b);
+        }
+    }
+}
@@ -0,0 +1,461 @@
+use crate::math::core::LinearSpace;
+use crate::math::integrate::{FirstOrderSystem, Integrator, RK4Method};
+
+/// Derivative evaluations of a predictor-corrector step
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdamsMode {
+    /// Predict, Evaluate, Correct: 1 evaluation per step,
+    /// the next step uses the derivative at the predicted state
+    Pec,
+    /// Predict, Evaluate, Correct, Evaluate: 2 evaluations per step
+    Pece,
+}
+
+/// Adams-Bashforth-Moulton predictor-corrector method of order `k`
+///
+/// Predicts with the explicit `k`-step Adams-Bashforth formula
+/// $$ y_{n+1}^* = y_n + h \sum_{j=0}^{k-1} \beta_j f_{n-j} $$
+/// and corrects once with the `(k-1)`-step Adams-Moulton formula, both of order `k`.
+/// The derivative history is started (and restarted after a change of `h` or
+/// of the state between calls) with `RK4Method`.
+///
+/// With `with_variable_order` the order is chosen after every step from the
+/// local error estimates of the correctors of order `k - 1`, `k` and `k + 1`,
+/// $$ E_k = h \lVert \gamma_k^* \nabla^k f_{n+1} \rVert $$
+pub struct AdamsBashforthMoultonMethod {
+    order: usize,
+    max_order: usize,
+    variable: bool,
+    mode: AdamsMode,
+    /// Weights of orders `1..=MAX_ORDER`
+    predictors: Vec<Vec<f64>>,
+    /// Weights of orders `1..=MAX_ORDER + 1`, the last one for the error estimate
+    correctors: Vec<Vec<f64>>,
+    h: f64,
+    y: Vec<f64>,
+    history: Vec<Vec<f64>>,
+}
+
+impl AdamsBashforthMoultonMethod {
+    /// The RK4 startup is accurate to $$ O(h^5) $$, limiting the global order
+    pub const MAX_ORDER: usize = 5;
+
+    pub fn new(order: usize, mode: AdamsMode) -> Self {
+        if !(1..=Self::MAX_ORDER).contains(&order) {
+            panic!(
+                "Adams-Bashforth-Moulton order must be in 1..={}, got {order}",
+                Self::MAX_ORDER
+            );
+        }
+        // f_n, f_{n-1}, ... at s = 0, -1, ... and f_{n+1}, f_n, ... at s = 1, 0, ...
+        let predictors = (1..=Self::MAX_ORDER)
+            .map(|k| adams_weights(&(0..k).map(|j| -(j as f64)).collect::<Vec<_>>()))
+            .collect();
+        let correctors = (1..=Self::MAX_ORDER + 1)
+            .map(|k| adams_weights(&(0..k).map(|j| 1.0 - j as f64).collect::<Vec<_>>()))
+            .collect();
+        Self {
+            order,
+            max_order: order,
+            variable: false,
+            mode,
+            predictors,
+            correctors,
+            h: 0.0,
+            y: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Selects the order in `1..=max_order` after every step, starting from the
+    /// order given to `new`
+    pub fn with_variable_order(mut self, max_order: usize) -> Self {
+        if !(self.order..=Self::MAX_ORDER).contains(&max_order) {
+            panic!(
+                "Maximum order must be in {}..={}, got {max_order}",
+                self.order,
+                Self::MAX_ORDER
+            );
+        }
+        self.max_order = max_order;
+        self.variable = true;
+        self
+    }
+
+    /// Order of the next step
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn max_order(&self) -> usize {
+        self.max_order
+    }
+
+    pub fn mode(&self) -> AdamsMode {
+        self.mode
+    }
+
+    /// Weights of $$ f_n, f_{n-1}, \dots $$ in the predictor
+    pub fn predictor_weights(&self) -> &[f64] {
+        &self.predictors[self.order - 1]
+    }
+
+    /// Weights of $$ f_{n+1}, f_n, \dots $$ in the corrector
+    pub fn corrector_weights(&self) -> &[f64] {
+        &self.correctors[self.order - 1]
+    }
+
+    /// Local error estimate of the order `k` corrector over the newest history,
+    /// the difference to the order `k + 1` corrector
+    fn error_estimate(&self, k: usize) -> Option<f64> {
+        if self.history.len() < k + 1 {
+            return None;
+        }
+        let (lower, higher) = (&self.correctors[k - 1], &self.correctors[k]);
+        let newest = self.history.iter().rev();
+        let mut difference = vec![0.0; self.history[0].len()];
+        for (i, f) in newest.take(k + 1).enumerate() {
+            let weight = higher[i] - lower.get(i).unwrap_or(&0.0);
+            for (d, f) in difference.iter_mut().zip(f) {
+                *d += weight * f;
+            }
+        }
+        Some(self.h * difference.iter().fold(0.0, |max, d| d.abs().max(max)))
+    }
+
+    /// Moves to the neighbouring order with the smallest error estimate
+    fn select_order(&mut self) {
+        let lowest = self.order.saturating_sub(1).max(1);
+        let highest = (self.order + 1).min(self.max_order);
+        let mut best = (self.order, self.error_estimate(self.order));
+        for k in lowest..=highest {
+            if let Some(error) = self.error_estimate(k)
+                && best.1.is_none_or(|best| error < best)
+            {
+                best = (k, Some(error));
+            }
+        }
+        self.order = best.0;
+    }
+}
+
+impl Default for AdamsBashforthMoultonMethod {
+    fn default() -> Self {
+        Self::new(4, AdamsMode::Pece)
+    }
+}
+
+/// $$ \int_0^1 L_j(s) ds $$ for the Lagrange basis polynomials on `nodes`
+fn adams_weights(nodes: &[f64]) -> Vec<f64> {
+    (0..nodes.len())
+        .map(|j| {
+            let mut polynomial = vec![1.0];
+            for (m, &node) in nodes.iter().enumerate() {
+                if m == j {
+                    continue;
+                }
+                let scale = nodes[j] - node;
+                let mut next = vec![0.0; polynomial.len() + 1];
+                for (i, c) in polynomial.iter().enumerate() {
+                    next[i + 1] += c / scale;
+                    next[i] -= c * node / scale;
+                }
+                polynomial = next;
+            }
+            polynomial
+                .iter()
+                .enumerate()
+                .map(|(i, c)| c / (i as f64 + 1.0))
+                .sum()
+        })
+        .collect()
+}
+
+fn weighted_sum<V: LinearSpace>(weights: &[f64], fs: impl Iterator<Item = V>) -> V {
+    weights
+        .iter()
+        .zip(fs)
+        .fold(V::zero(), |acc, (w, f)| acc + f * *w)
+}
+
+impl Integrator for AdamsBashforthMoultonMethod {
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
+    where
+        F: FirstOrderSystem,
+    {
+        let yc = y.components();
+        if h != self.h || self.y != yc {
+            self.history = vec![system.derivative(t, y).components()];
+            self.h = h;
+        }
+
+        let f = |fc: &Vec<f64>| F::Vector::from_components(fc);
+        let (y_new, f_new) = if self.history.len() < self.order {
+            let y_new = RK4Method.step_first_order(system, t, y, h);
+            (y_new, system.derivative(t + h, y_new))
+        } else {
+            let (predictor, corrector) = (self.predictor_weights(), self.corrector_weights());
+            let y_predicted = y + weighted_sum(predictor, self.history.iter().rev().map(f)) * h;
+            let f_predicted = system.derivative(t + h, y_predicted);
+            let past = self.history.iter().rev().map(f);
+            let y_new = y + (f_predicted * corrector[0] + weighted_sum(&corrector[1..], past)) * h;
+            match self.mode {
+                AdamsMode::Pec => (y_new, f_predicted),
+                AdamsMode::Pece => (y_new, system.derivative(t + h, y_new)),
+            }
+        };
+
+        self.history.push(f_new.components());
+        // One more than the order for the error estimate of order k + 1
+        if self.history.len() > self.max_order + 1 {
+            self.history.remove(0);
+        }
+        if self.variable {
+            self.select_order();
+        }
+        self.y = y_new.components();
+        y_new
+    }
+
+    /// The newest history entry in `Pece` mode
+    ///
+    /// `Pec` evaluates at the predicted state only, so the driver evaluates the
+    /// derivative at the corrected state itself.
+    fn last_derivative(&self) -> Option<&[f64]> {
+        match self.mode {
+            AdamsMode::Pec => None,
+            AdamsMode::Pece => self.history.last().map(Vec::as_slice),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::math::complex::{Complex, ComplexSpace};
+    use crate::math::core::ScalarSpace;
+    use crate::math::integrate::{Solver, System};
+
+    use super::*;
+
+    /// y'' + y = 0, counting derivative evaluations
+    struct TestHarmonicOscillator {
+        evaluations: Cell<usize>,
+    }
+
+    impl TestHarmonicOscillator {
+        fn new() -> Self {
+            Self {
+                evaluations: Cell::new(0),
+            }
+        }
+    }
+
+    impl System for TestHarmonicOscillator {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            self.evaluations.set(self.evaluations.get() + 1);
+            -y
+        }
+    }
+
+    /// z' = i z
+    struct TestRotation;
+
+    impl FirstOrderSystem for TestRotation {
+        type Vector = Complex;
+
+        fn derivative(&self, _t: f64, y: Self::Vector) -> Self::Vector {
+            Complex::i() * y
+        }
+    }
+
+    /// y' = -y, counting derivative evaluations
+    struct TestDecay {
+        evaluations: Cell<usize>,
+    }
+
+    impl TestDecay {
+        fn new() -> Self {
+            Self {
+                evaluations: Cell::new(0),
+            }
+        }
+    }
+
+    impl FirstOrderSystem for TestDecay {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector) -> Self::Vector {
+            self.evaluations.set(self.evaluations.get() + 1);
+            -y
+        }
+    }
+
+    /// y' = cos t + |t - 1|, with a kink in the derivative at t = 1
+    struct TestKink;
+
+    impl FirstOrderSystem for TestKink {
+        type Vector = f64;
+
+        fn derivative(&self, t: f64, _y: Self::Vector) -> Self::Vector {
+            t.cos() + (t - 1.0).abs()
+        }
+    }
+
+    fn decay_error(order: usize, mode: AdamsMode, h: f64) -> f64 {
+        let steps = (2.0 / h).round() as usize;
+        let integrator = AdamsBashforthMoultonMethod::new(order, mode);
+        let mut test_solver = Solver::first_order(integrator, TestDecay::new(), 1.0);
+        test_solver.run(h, steps);
+        let (_, y, _) = test_solver.get_current();
+        (y - (-2.0f64).exp()).abs()
+    }
+
+    #[test]
+    fn test_adams_weights() {
+        let abm4 = AdamsBashforthMoultonMethod::new(4, AdamsMode::Pece);
+        let predictor = [55.0 / 24.0, -59.0 / 24.0, 37.0 / 24.0, -9.0 / 24.0];
+        let corrector = [9.0 / 24.0, 19.0 / 24.0, -5.0 / 24.0, 1.0 / 24.0];
+        for j in 0..4 {
+            assert!((abm4.predictor_weights()[j] - predictor[j]).abs() < 1e-14);
+            assert!((abm4.corrector_weights()[j] - corrector[j]).abs() < 1e-14);
+        }
+        for order in 1..=AdamsBashforthMoultonMethod::MAX_ORDER {
+            let abm = AdamsBashforthMoultonMethod::new(order, AdamsMode::Pec);
+            let sum: f64 = abm.predictor_weights().iter().sum();
+            assert!((sum - 1.0).abs() < 1e-9, "Order {order} is consistent");
+        }
+    }
+
+    #[test]
+    fn test_convergence_order() {
+        for mode in [AdamsMode::Pec, AdamsMode::Pece] {
+            for order in 1..=AdamsBashforthMoultonMethod::MAX_ORDER {
+                let h = 0.025;
+                let ratio = decay_error(order, mode, h) / decay_error(order, mode, h / 2.0);
+                let observed = ratio.log2();
+                assert!(
+                    (observed - order as f64).abs() < 0.25,
+                    "{mode:?} order {order}: observed {observed}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluations_per_step() {
+        for (mode, per_step) in [(AdamsMode::Pec, 1), (AdamsMode::Pece, 2)] {
+            let integrator = AdamsBashforthMoultonMethod::new(4, mode);
+            let mut test_solver = Solver::new(integrator, TestHarmonicOscillator::new(), 1.0, 0.0);
+            // Startup: f(y0) and 3 RK4 steps of 5 evaluations
+            test_solver.run(0.01, 3);
+            let startup = test_solver.system.evaluations.get();
+            assert_eq!(startup, 1 + 3 * 5);
+
+            test_solver.run(0.01, 100);
+            let evaluations = test_solver.system.evaluations.get() - startup;
+            assert_eq!(evaluations, 100 * per_step, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn test_first_order_evaluations_per_step() {
+        // Pec leaves y' at the corrected state to the solver
+        for (mode, per_step) in [(AdamsMode::Pec, 2), (AdamsMode::Pece, 2)] {
+            let integrator = AdamsBashforthMoultonMethod::new(4, mode);
+            let mut test_solver = Solver::first_order(integrator, TestDecay::new(), 1.0);
+            // y0' for the solver, f(y0) for the history and 3 RK4 steps of 5 evaluations
+            test_solver.run(0.01, 3);
+            let startup = test_solver.system.0.evaluations.get();
+            let reevaluated = if mode == AdamsMode::Pec { 3 } else { 0 };
+            assert_eq!(startup, 2 + 3 * 5 + reevaluated, "{mode:?}");
+
+            test_solver.run(0.01, 100);
+            let evaluations = test_solver.system.0.evaluations.get() - startup;
+            assert_eq!(evaluations, 100 * per_step, "{mode:?}");
+
+            let (t, y, y_prime) = test_solver.get_current();
+            assert!((y - (-t).exp()).abs() < 1e-8);
+            assert_eq!(y_prime, -y, "{mode:?}: y' belongs to the corrected state");
+        }
+    }
+
+    #[test]
+    fn test_variable_order_rises() {
+        let integrator =
+            AdamsBashforthMoultonMethod::new(1, AdamsMode::Pece).with_variable_order(5);
+        let mut test_solver = Solver::first_order(integrator, TestRotation, Complex::new(1.0, 0.0));
+        test_solver.run(0.01, 10);
+        assert_eq!(test_solver.integrator.order(), 5);
+
+        let errors: Vec<f64> = [
+            AdamsBashforthMoultonMethod::new(3, AdamsMode::Pece),
+            AdamsBashforthMoultonMethod::new(3, AdamsMode::Pece).with_variable_order(5),
+        ]
+        .into_iter()
+        .map(|integrator| {
+            let mut test_solver =
+                Solver::first_order(integrator, TestRotation, Complex::new(1.0, 0.0));
+            test_solver.run(0.01, 1000);
+            let (t, z, _) = test_solver.get_current();
+            (z - Complex::cis(t)).abs()
+        })
+        .collect();
+        assert!(errors[1] < 0.25 * errors[0], "{errors:?}");
+    }
+
+    #[test]
+    fn test_variable_order_drops_at_kink() {
+        let integrator =
+            AdamsBashforthMoultonMethod::new(5, AdamsMode::Pece).with_variable_order(5);
+        let mut test_solver = Solver::first_order(integrator, TestKink, 0.0);
+        let orders: Vec<usize> = (0..200)
+            .map(|_| {
+                test_solver.run(0.01, 1);
+                test_solver.integrator.order()
+            })
+            .collect();
+        assert!(orders[10..95].iter().all(|&k| k == 5), "{orders:?}");
+        assert!(orders[98..108].iter().any(|&k| k < 4), "{orders:?}");
+        assert_eq!(orders[199], 5);
+
+        let (t, y, _) = test_solver.get_current();
+        let exact = t.sin() + 0.5 + 0.5 * (t - 1.0).powi(2);
+        // The kink costs every order a local error of O(h^2)
+        assert!((y - exact).abs() < 2e-5, "{}", y - exact);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_max_order_below_order() {
+        AdamsBashforthMoultonMethod::new(4, AdamsMode::Pec).with_variable_order(3);
+    }
+
+    #[test]
+    fn test_restart_on_step_change() {
+        let integrator = AdamsBashforthMoultonMethod::default();
+        let mut test_solver = Solver::new(integrator, TestHarmonicOscillator::new(), 1.0, 0.0);
+        test_solver.run(0.01, 100);
+        test_solver.run(0.02, 50);
+        test_solver.run(0.005, 200);
+
+        let (t, y, y_prime) = test_solver.get_current();
+        assert!((t - 3.0).abs() < 1e-12);
+        assert!((y - t.cos()).abs() < 1e-8);
+        assert!((y_prime + t.sin()).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_complex_first_order() {
+        let integrator = AdamsBashforthMoultonMethod::new(5, AdamsMode::Pece);
+        let mut test_solver = Solver::first_order(integrator, TestRotation, Complex::new(1.0, 0.0));
+        test_solver.run(0.01, 1000);
+
+        let (t, z, _) = test_solver.get_current();
+        let exact = Complex::cis(t);
+        assert!((z - exact).abs() < 1e-9, "z = {z:?}");
+    }
+}
@@ -0,0 +1,514 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::math::core::LinearSpace;
+use crate::math::integrate::dense::DenseStep;
+use crate::math::integrate::event::EventRecord;
+use crate::math::integrate::{Results, Trajectory};
+
+/// Receives the initial state and every accepted step `(t, y, y')` of a `Solver`
+pub trait Observer<V: LinearSpace> {
+    fn observe(&mut self, t: f64, y: V, y_prime: V);
+
+    /// Step with the integrator's interpolant over it, defaults to `observe`
+    fn observe_dense(&mut self, t: f64, y: V, y_prime: V, _step: DenseStep<V>) {
+        self.observe(t, y, y_prime);
+    }
+
+    /// Located event crossing, reported before the step containing it
+    fn observe_event(&mut self, _record: EventRecord<V>) {}
+}
+
+/// Stores every step, with dense output and events
+impl<V: LinearSpace> Observer<V> for Results<V> {
+    fn observe(&mut self, t: f64, y: V, y_prime: V) {
+        self.update(t, y, y_prime);
+    }
+
+    fn observe_dense(&mut self, t: f64, y: V, y_prime: V, step: DenseStep<V>) {
+        self.update_dense(t, y, y_prime, step);
+    }
+
+    fn observe_event(&mut self, record: EventRecord<V>) {
+        self.record_event(record);
+    }
+}
+
+impl<V: LinearSpace, O: Observer<V>> Observer<V> for &mut O {
+    fn observe(&mut self, t: f64, y: V, y_prime: V) {
+        (**self).observe(t, y, y_prime);
+    }
+
+    fn observe_dense(&mut self, t: f64, y: V, y_prime: V, step: DenseStep<V>) {
+        (**self).observe_dense(t, y, y_prime, step);
+    }
+
+    fn observe_event(&mut self, record: EventRecord<V>) {
+        (**self).observe_event(record);
+    }
+}
+
+/// Passes every step to both observers
+impl<V: LinearSpace, A: Observer<V>, B: Observer<V>> Observer<V> for (A, B) {
+    fn observe(&mut self, t: f64, y: V, y_prime: V) {
+        self.0.observe(t, y, y_prime);
+        self.1.observe(t, y, y_prime);
+    }
+
+    fn observe_dense(&mut self, t: f64, y: V, y_prime: V, step: DenseStep<V>) {
+        self.0.observe_dense(t, y, y_prime, step.clone());
+        self.1.observe_dense(t, y, y_prime, step);
+    }
+
+    fn observe_event(&mut self, record: EventRecord<V>) {
+        self.0.observe_event(record);
+        self.1.observe_event(record);
+    }
+}
+
+/// Passes the initial state and every `every`-th step to `inner`
+///
+/// Events are always passed. Dense output is dropped, since one step's
+/// interpolant does not cover the skipped steps.
+pub struct Decimate<O> {
+    every: usize,
+    count: usize,
+    inner: O,
+}
+
+impl<O> Decimate<O> {
+    pub fn new(every: usize, inner: O) -> Self {
+        if every == 0 {
+            panic!("Decimate: every must be positive");
+        }
+        Self {
+            every,
+            count: 0,
+            inner,
+        }
+    }
+
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> O {
+        self.inner
+    }
+}
+
+impl<V: LinearSpace, O: Observer<V>> Observer<V> for Decimate<O> {
+    fn observe(&mut self, t: f64, y: V, y_prime: V) {
+        if self.count.is_multiple_of(self.every) {
+            self.inner.observe(t, y, y_prime);
+        }
+        self.count += 1;
+    }
+
+    fn observe_event(&mut self, record: EventRecord<V>) {
+        self.inner.observe_event(record);
+    }
+}
+
+/// Keeps only the last `capacity` steps
+pub struct RingBuffer<V: LinearSpace> {
+    capacity: usize,
+    samples: VecDeque<(f64, V, V)>,
+}
+
+impl<V: LinearSpace> RingBuffer<V> {
+    pub fn new(capacity: usize) -> Self {
+        if capacity == 0 {
+            panic!("RingBuffer: capacity must be positive");
+        }
+        Self {
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Samples from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = &(f64, V, V)> {
+        self.samples.iter()
+    }
+
+    pub fn latest(&self) -> Option<(f64, V, V)> {
+        self.samples.back().copied()
+    }
+
+    pub fn get_results(&self) -> Trajectory<V> {
+        let ts = self.samples.iter().map(|s| s.0).collect();
+        let ys = self.samples.iter().map(|s| s.1).collect();
+        let ys_prime = self.samples.iter().map(|s| s.2).collect();
+        (ts, ys, ys_prime)
+    }
+}
+
+impl<V: LinearSpace> Observer<V> for RingBuffer<V> {
+    fn observe(&mut self, t: f64, y: V, y_prime: V) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((t, y, y_prime));
+    }
+}
+
+/// Writes each step as a whitespace-separated line `t y... y'...`
+///
+/// Values are written with the shortest representation that round-trips.
+/// The first I/O error stops the output and is returned by `finish`.
+pub struct FileWriter<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl FileWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> FileWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    /// Flushes the output and returns the writer, or the first I/O error
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_line<V: LinearSpace>(&mut self, t: f64, y: V, y_prime: V) -> io::Result<()> {
+        write!(self.writer, "{t}")?;
+        for value in y.components().into_iter().chain(y_prime.components()) {
+            write!(self.writer, " {value}")?;
+        }
+        writeln!(self.writer)
+    }
+}
+
+impl<V: LinearSpace, W: Write> Observer<V> for FileWriter<W> {
+    fn observe(&mut self, t: f64, y: V, y_prime: V) {
+        if self.error.is_none() {
+            self.error = self.write_line(t, y, y_prime).err();
+        }
+    }
+}
+
+/// Component-wise count, mean, variance, minimum and maximum (Welford's algorithm)
+#[derive(Clone, Debug)]
+pub struct Statistics<V: LinearSpace> {
+    count: usize,
+    mean: Vec<f64>,
+    m2: Vec<f64>,
+    min: Vec<f64>,
+    max: Vec<f64>,
+    _vector: PhantomData<V>,
+}
+
+impl<V: LinearSpace> Statistics<V> {
+    pub fn new() -> Self {
+        let dim = V::dim();
+        Self {
+            count: 0,
+            mean: vec![0.0; dim],
+            m2: vec![0.0; dim],
+            min: vec![f64::INFINITY; dim],
+            max: vec![f64::NEG_INFINITY; dim],
+            _vector: PhantomData,
+        }
+    }
+
+    pub fn update(&mut self, v: V) {
+        self.count += 1;
+        let n = self.count as f64;
+        for (i, x) in v.components().into_iter().enumerate() {
+            let delta = x - self.mean[i];
+            self.mean[i] += delta / n;
+            self.m2[i] += delta * (x - self.mean[i]);
+            self.min[i] = self.min[i].min(x);
+            self.max[i] = self.max[i].max(x);
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> V {
+        V::from_components(&self.mean)
+    }
+
+    /// Population variance $$ \frac{1}{n} \sum (x - \bar{x})^2 $$
+    pub fn variance(&self) -> V {
+        let n = self.count.max(1) as f64;
+        let variance: Vec<f64> = self.m2.iter().map(|m2| m2 / n).collect();
+        V::from_components(&variance)
+    }
+
+    pub fn std_dev(&self) -> V {
+        let n = self.count.max(1) as f64;
+        let std_dev: Vec<f64> = self.m2.iter().map(|m2| (m2 / n).sqrt()).collect();
+        V::from_components(&std_dev)
+    }
+
+    pub fn min(&self) -> V {
+        V::from_components(&self.min)
+    }
+
+    pub fn max(&self) -> V {
+        V::from_components(&self.max)
+    }
+}
+
+impl<V: LinearSpace> Default for Statistics<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Running `Statistics` of `y` and `y'` over all steps
+#[derive(Clone, Debug)]
+pub struct RunningStatistics<V: LinearSpace> {
+    y: Statistics<V>,
+    y_prime: Statistics<V>,
+}
+
+impl<V: LinearSpace> RunningStatistics<V> {
+    pub fn new() -> Self {
+        Self {
+            y: Statistics::new(),
+            y_prime: Statistics::new(),
+        }
+    }
+
+    pub fn y(&self) -> &Statistics<V> {
+        &self.y
+    }
+
+    pub fn y_prime(&self) -> &Statistics<V> {
+        &self.y_prime
+    }
+}
+
+impl<V: LinearSpace> Default for RunningStatistics<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: LinearSpace> Observer<V> for RunningStatistics<V> {
+    fn observe(&mut self, _t: f64, y: V, y_prime: V) {
+        self.y.update(y);
+        self.y_prime.update(y_prime);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::math::core::Vector;
+    use crate::math::integrate::adaptive::DormandPrince45;
+    use crate::math::integrate::event::Event;
+    use crate::math::integrate::{RK4Method, Solver, System};
+
+    use super::*;
+
+    struct TestHarmonicOscillator;
+
+    /// y'' + y = 0
+    impl System for TestHarmonicOscillator {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            -y
+        }
+    }
+
+    fn full_results(h: f64, steps: usize) -> Trajectory<f64> {
+        let mut test_solver = Solver::new(RK4Method, TestHarmonicOscillator, 1.0, 0.0);
+        test_solver.run(h, steps);
+        test_solver.get_results()
+    }
+
+    #[test]
+    fn test_results_is_an_observer() {
+        let mut test_solver = Solver::with_observer(
+            RK4Method,
+            TestHarmonicOscillator,
+            1.0,
+            0.0,
+            Results::empty(),
+        );
+        test_solver.run(0.1, 100);
+        let results = test_solver.into_observer();
+
+        assert_eq!(
+            (results.get_ts(), results.get_ys(), results.get_ys_prime()),
+            full_results(0.1, 100)
+        );
+    }
+
+    #[test]
+    fn test_decimate() {
+        let mut test_solver = Solver::with_observer(
+            RK4Method,
+            TestHarmonicOscillator,
+            1.0,
+            0.0,
+            Decimate::new(10, Results::empty()),
+        );
+        test_solver.run(0.1, 100);
+
+        let (ts, ys, _) = full_results(0.1, 100);
+        let decimated = test_solver.observer().inner();
+        assert_eq!(decimated.get_ts().len(), 11);
+        for (k, (t, y)) in decimated
+            .get_ts()
+            .iter()
+            .zip(decimated.get_ys())
+            .enumerate()
+        {
+            assert_eq!(*t, ts[10 * k]);
+            assert_eq!(y, ys[10 * k]);
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_keeps_last_steps() {
+        let mut test_solver = Solver::with_observer(
+            RK4Method,
+            TestHarmonicOscillator,
+            1.0,
+            0.0,
+            RingBuffer::new(5),
+        );
+        test_solver.run(0.001, 100_000);
+
+        let buffer = test_solver.observer();
+        assert_eq!(buffer.len(), buffer.capacity(), "Memory stays bounded");
+        let (t_last, y_last, _) = buffer.latest().unwrap();
+        assert!((t_last - 100.0).abs() < 1e-9);
+        assert!((y_last - 100.0f64.cos()).abs() < 1e-9);
+
+        let (ts, ys, ys_prime) = full_results(0.1, 100);
+        let mut test_solver = Solver::with_observer(
+            RK4Method,
+            TestHarmonicOscillator,
+            1.0,
+            0.0,
+            RingBuffer::new(5),
+        );
+        test_solver.run(0.1, 100);
+        assert_eq!(
+            test_solver.observer().get_results(),
+            (
+                ts[96..].to_vec(),
+                ys[96..].to_vec(),
+                ys_prime[96..].to_vec()
+            )
+        );
+    }
+
+    #[test]
+    fn test_file_writer() {
+        let mut test_solver = Solver::with_observer(
+            RK4Method,
+            TestHarmonicOscillator,
+            1.0,
+            0.0,
+            FileWriter::new(Vec::new()),
+        );
+        test_solver.run(0.1, 20);
+        let output = String::from_utf8(test_solver.into_observer().finish().unwrap()).unwrap();
+
+        let (ts, ys, ys_prime) = full_results(0.1, 20);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 21);
+        for (i, line) in lines.iter().enumerate() {
+            let values: Vec<f64> = line
+                .split_whitespace()
+                .map(|v| v.parse().unwrap())
+                .collect();
+            assert_eq!(values, vec![ts[i], ys[i], ys_prime[i]], "Values round-trip");
+        }
+    }
+
+    #[test]
+    fn test_file_writer_vector_state() {
+        let mut writer = FileWriter::new(Vec::new());
+        writer.observe(0.5, Vector::new([1.0, 2.0]), Vector::new([3.0, -4.5]));
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(output, "0.5 1 2 3 -4.5\n");
+    }
+
+    #[test]
+    fn test_running_statistics() {
+        // 10 periods of cos(t): <y> = 0, <y^2> = 1/2
+        let steps = 10_000;
+        let h = 20.0 * PI / steps as f64;
+        let mut test_solver = Solver::with_observer(
+            RK4Method,
+            TestHarmonicOscillator,
+            1.0,
+            0.0,
+            (RunningStatistics::new(), Results::empty()),
+        );
+        test_solver.run(h, steps - 1);
+
+        let (statistics, results) = test_solver.observer();
+        let ys = results.get_ys();
+        let n = ys.len() as f64;
+        let mean = ys.iter().sum::<f64>() / n;
+        let variance = ys.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / n;
+
+        assert_eq!(statistics.y().count(), steps);
+        assert!((statistics.y().mean() - mean).abs() < 1e-12);
+        assert!((statistics.y().variance() - variance).abs() < 1e-12);
+        assert!(statistics.y().mean().abs() < 1e-3);
+        assert!((statistics.y().variance() - 0.5).abs() < 1e-3);
+        assert!((statistics.y_prime().variance() - 0.5).abs() < 1e-3);
+        assert!((statistics.y().max() - 1.0).abs() < 1e-9);
+        assert!((statistics.y().min() + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_events_reach_observer() {
+        let mut test_solver = Solver::with_observer(
+            DormandPrince45::default(),
+            TestHarmonicOscillator,
+            1.0,
+            0.0,
+            Decimate::new(1000, Results::empty()),
+        );
+        test_solver.add_event(Event::new(|_t, y, _y_prime| y));
+        test_solver.run_until(10.0);
+
+        let results = test_solver.observer().inner();
+        assert_eq!(results.get_ts().len(), 1, "Only the initial state is kept");
+        assert_eq!(results.get_events().len(), 3, "Events are not decimated");
+    }
+}
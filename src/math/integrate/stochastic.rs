@@ -0,0 +1,439 @@
+use crate::math::core::{LinearSpace, Vector};
+use crate::math::integrate::observer::Observer;
+use crate::math::integrate::{Phase, Results, System, Trajectory};
+use crate::math::random::Rng;
+
+/// Itô stochastic differential equation with diagonal noise
+///
+/// $$ dy = a(t, y) dt + b(t, y) dW $$
+/// where `b` multiplies the independent Wiener increments component-wise.
+pub trait StochasticSystem {
+    type Vector: LinearSpace + Copy;
+
+    /// Drift $$ a(t, y) $$
+    fn drift(&self, t: f64, y: Self::Vector) -> Self::Vector;
+
+    /// Diffusion $$ b(t, y) $$, one amplitude per component
+    fn diffusion(&self, t: f64, y: Self::Vector) -> Self::Vector;
+
+    /// $$ \partial b_i / \partial y_i $$ for the Milstein correction
+    ///
+    /// Defaults to central finite differences.
+    fn diffusion_derivative(&self, t: f64, y: Self::Vector) -> Self::Vector {
+        let yc = y.components();
+        let derivative: Vec<f64> = (0..yc.len())
+            .map(|i| {
+                let eps = f64::EPSILON.cbrt() * yc[i].abs().max(1.0);
+                let (mut plus, mut minus) = (yc.clone(), yc.clone());
+                plus[i] += eps;
+                minus[i] -= eps;
+                let b_plus = self.diffusion(t, Self::Vector::from_components(&plus));
+                let b_minus = self.diffusion(t, Self::Vector::from_components(&minus));
+                (b_plus.components()[i] - b_minus.components()[i]) / (2.0 * eps)
+            })
+            .collect();
+        Self::Vector::from_components(&derivative)
+    }
+}
+
+impl<S: StochasticSystem> StochasticSystem for &S {
+    type Vector = S::Vector;
+
+    fn drift(&self, t: f64, y: Self::Vector) -> Self::Vector {
+        (*self).drift(t, y)
+    }
+
+    fn diffusion(&self, t: f64, y: Self::Vector) -> Self::Vector {
+        (*self).diffusion(t, y)
+    }
+
+    fn diffusion_derivative(&self, t: f64, y: Self::Vector) -> Self::Vector {
+        (*self).diffusion_derivative(t, y)
+    }
+}
+
+/// Integrator of the `StochasticSystem` `S` drawing its own seeded Wiener increments
+///
+/// Generic methods implement it for every `S`, splitting methods only for the
+/// systems whose structure they use.
+pub trait StochasticIntegrator<S: StochasticSystem> {
+    fn step_stochastic(&mut self, system: &S, t: f64, y: S::Vector, h: f64) -> S::Vector;
+}
+
+/// Euler-Maruyama method, strong order 1/2
+///
+/// $$ y_{n+1} = y_n + a h + b \Delta W $$
+pub struct EulerMaruyamaMethod {
+    rng: Rng,
+}
+
+/// Milstein method for diagonal noise, strong order 1
+///
+/// $$ y_{n+1} = y_n + a h + b \Delta W + \frac{1}{2} b b' (\Delta W^2 - h) $$
+pub struct MilsteinMethod {
+    rng: Rng,
+}
+
+impl EulerMaruyamaMethod {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl MilsteinMethod {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+/// Component-wise product
+fn hadamard<V: LinearSpace>(a: V, b: V) -> V {
+    let (a, b) = (a.components(), b.components());
+    let product: Vec<f64> = a.iter().zip(&b).map(|(x, y)| x * y).collect();
+    V::from_components(&product)
+}
+
+impl<S: StochasticSystem> StochasticIntegrator<S> for EulerMaruyamaMethod {
+    fn step_stochastic(&mut self, system: &S, t: f64, y: S::Vector, h: f64) -> S::Vector {
+        let dw: S::Vector = self.rng.normal_vector::<S::Vector>() * h.sqrt();
+        y + system.drift(t, y) * h + hadamard(system.diffusion(t, y), dw)
+    }
+}
+
+impl<S: StochasticSystem> StochasticIntegrator<S> for MilsteinMethod {
+    fn step_stochastic(&mut self, system: &S, t: f64, y: S::Vector, h: f64) -> S::Vector {
+        let dw: S::Vector = self.rng.normal_vector::<S::Vector>() * h.sqrt();
+        let b = system.diffusion(t, y);
+        let bb_prime = hadamard(b, system.diffusion_derivative(t, y));
+        let dw_sq_minus_h: Vec<f64> = dw.components().iter().map(|w| w * w - h).collect();
+        y + system.drift(t, y) * h
+            + hadamard(b, dw)
+            + hadamard(bb_prime, S::Vector::from_components(&dw_sq_minus_h)) * 0.5
+    }
+}
+
+/// Langevin dynamics of a 2nd-order `System` in a heat bath
+///
+/// $$ dx = v dt, \quad dv = f(t, x, v) dt - \gamma v dt + \sqrt{2 \gamma T} dW $$
+/// where `temperature` is $$ k_B T / m $$.
+pub struct Langevin<S: System> {
+    pub system: S,
+    pub gamma: f64,
+    pub temperature: f64,
+}
+
+impl<S: System> StochasticSystem for Langevin<S> {
+    type Vector = Phase<S::Vector>;
+
+    fn drift(&self, t: f64, y: Self::Vector) -> Self::Vector {
+        let (x, v) = (y[0], y[1]);
+        Vector::new([v, self.system.derivative(t, x, v) - v * self.gamma])
+    }
+
+    fn diffusion(&self, _t: f64, _y: Self::Vector) -> Self::Vector {
+        let sigma = vec![(2.0 * self.gamma * self.temperature).sqrt(); S::Vector::dim()];
+        Vector::new([S::Vector::zero(), S::Vector::from_components(&sigma)])
+    }
+
+    fn diffusion_derivative(&self, _t: f64, _y: Self::Vector) -> Self::Vector {
+        Self::Vector::zero()
+    }
+}
+
+/// BAOAB splitting of Langevin dynamics (Leimkuhler & Matthews)
+///
+/// Kick (B), drift (A) and the exact Ornstein-Uhlenbeck velocity update (O) in
+/// the order B A O A B. Configurational averages of harmonic systems are exact
+/// for any stable `h`. Integrates only `Langevin` systems, which carry the
+/// friction and temperature.
+pub struct BaoabMethod {
+    rng: Rng,
+}
+
+impl BaoabMethod {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl<S: System> StochasticIntegrator<Langevin<S>> for BaoabMethod {
+    fn step_stochastic(
+        &mut self,
+        langevin: &Langevin<S>,
+        t: f64,
+        y: Phase<S::Vector>,
+        h: f64,
+    ) -> Phase<S::Vector> {
+        let system = &langevin.system;
+        let c1 = (-langevin.gamma * h).exp();
+        let c2 = ((1.0 - c1 * c1) * langevin.temperature).sqrt();
+
+        let (x, v) = (y[0], y[1]);
+        let mut v = v + system.derivative(t, x, v) * (h / 2.0);
+        let mut x = x + v * (h / 2.0);
+        v = v * c1 + self.rng.normal_vector::<S::Vector>() * c2;
+        x = x + v * (h / 2.0);
+        v = v + system.derivative(t + h, x, v) * (h / 2.0);
+        Vector::new([x, v])
+    }
+}
+
+/// Drives a `StochasticIntegrator`, passing `(t, y, a(t, y))` to an `Observer`
+pub struct StochasticSolver<I, S, O = Results<<S as StochasticSystem>::Vector>>
+where
+    I: StochasticIntegrator<S>,
+    S: StochasticSystem,
+    O: Observer<S::Vector>,
+{
+    integrator: I,
+    system: S,
+    t: f64,
+    y: S::Vector,
+    observer: O,
+}
+
+impl<I, S> StochasticSolver<I, S>
+where
+    I: StochasticIntegrator<S>,
+    S: StochasticSystem,
+{
+    pub fn new(integrator: I, system: S, y0: S::Vector) -> Self {
+        Self::with_observer(integrator, system, y0, Results::empty())
+    }
+
+    /// `(ts, ys, drifts)` of every step
+    pub fn get_results(&self) -> Trajectory<S::Vector> {
+        (
+            self.observer.get_ts(),
+            self.observer.get_ys(),
+            self.observer.get_ys_prime(),
+        )
+    }
+}
+
+impl<I, S, O> StochasticSolver<I, S, O>
+where
+    I: StochasticIntegrator<S>,
+    S: StochasticSystem,
+    O: Observer<S::Vector>,
+{
+    pub fn with_observer(integrator: I, system: S, y0: S::Vector, mut observer: O) -> Self {
+        observer.observe(0.0, y0, system.drift(0.0, y0));
+        Self {
+            integrator,
+            system,
+            t: 0.0,
+            y: y0,
+            observer,
+        }
+    }
+
+    pub fn run(&mut self, h: f64, steps: usize) {
+        for _ in 0..steps {
+            self.y = self
+                .integrator
+                .step_stochastic(&self.system, self.t, self.y, h);
+            self.t += h;
+            self.observer
+                .observe(self.t, self.y, self.system.drift(self.t, self.y));
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn into_observer(self) -> O {
+        self.observer
+    }
+
+    #[cfg(test)]
+    fn get_current(&self) -> (f64, S::Vector) {
+        (self.t, self.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::integrate::observer::RunningStatistics;
+    use crate::physics::harmonic_oscillator::SimpleHarmonicOscillator;
+
+    use super::*;
+
+    /// dX = mu X dt + sigma X dW
+    struct TestGeometricBrownianMotion {
+        mu: f64,
+        sigma: f64,
+    }
+
+    impl StochasticSystem for TestGeometricBrownianMotion {
+        type Vector = f64;
+
+        fn drift(&self, _t: f64, y: Self::Vector) -> Self::Vector {
+            self.mu * y
+        }
+
+        fn diffusion(&self, _t: f64, y: Self::Vector) -> Self::Vector {
+            self.sigma * y
+        }
+    }
+
+    /// dX = -theta X dt + sigma dW, 8 independent components
+    struct TestOrnsteinUhlenbeck {
+        theta: f64,
+        sigma: f64,
+    }
+
+    impl StochasticSystem for TestOrnsteinUhlenbeck {
+        type Vector = Vector<f64, 8>;
+
+        fn drift(&self, _t: f64, y: Self::Vector) -> Self::Vector {
+            y * -self.theta
+        }
+
+        fn diffusion(&self, _t: f64, _y: Self::Vector) -> Self::Vector {
+            Vector::new([self.sigma; 8])
+        }
+    }
+
+    /// Mean |X_T - exact| over paths, the exact solution using the same increments
+    fn strong_error<I, M>(method: M, steps: usize, paths: u64) -> f64
+    where
+        I: for<'a> StochasticIntegrator<&'a TestGeometricBrownianMotion>,
+        M: Fn(u64) -> I,
+    {
+        let system = TestGeometricBrownianMotion {
+            mu: 1.0,
+            sigma: 0.5,
+        };
+        let h = 1.0 / steps as f64;
+        (0..paths)
+            .map(|seed| {
+                let mut test_solver = StochasticSolver::new(method(seed), &system, 1.0);
+                test_solver.run(h, steps);
+                let (t, x) = test_solver.get_current();
+
+                let mut rng = Rng::new(seed);
+                let w: f64 = (0..steps).map(|_| rng.normal() * h.sqrt()).sum();
+                let exact = ((system.mu - 0.5 * system.sigma.powi(2)) * t + system.sigma * w).exp();
+                (x - exact).abs()
+            })
+            .sum::<f64>()
+            / paths as f64
+    }
+
+    #[test]
+    fn test_strong_order() {
+        for (name, expected, errors) in [
+            (
+                "Euler-Maruyama",
+                0.5,
+                [64, 1024].map(|n| strong_error(EulerMaruyamaMethod::new, n, 200)),
+            ),
+            (
+                "Milstein",
+                1.0,
+                [64, 1024].map(|n| strong_error(MilsteinMethod::new, n, 200)),
+            ),
+        ] {
+            let observed = (errors[0] / errors[1]).log2() / 4.0;
+            assert!(
+                (observed - expected).abs() < 0.2,
+                "{name}: observed strong order {observed}, errors {errors:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_seed_reproducible() {
+        let system = TestOrnsteinUhlenbeck {
+            theta: 1.0,
+            sigma: 1.0,
+        };
+        let run = |seed| {
+            let mut test_solver =
+                StochasticSolver::new(EulerMaruyamaMethod::new(seed), &system, Vector::zero());
+            test_solver.run(0.01, 100);
+            test_solver.get_results().1
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn test_ornstein_uhlenbeck_stationary_variance() {
+        // Var X = sigma^2 / (2 theta)
+        let system = TestOrnsteinUhlenbeck {
+            theta: 1.0,
+            sigma: 2.0_f64.sqrt(),
+        };
+        let mut test_solver = StochasticSolver::with_observer(
+            EulerMaruyamaMethod::new(11),
+            system,
+            Vector::zero(),
+            RunningStatistics::new(),
+        );
+        test_solver.run(0.01, 50_000);
+
+        let variance = test_solver.observer().y().variance();
+        let mean_variance = variance.get_data().iter().sum::<f64>() / 8.0;
+        assert!((mean_variance - 1.0).abs() < 0.1, "Var X = {mean_variance}");
+    }
+
+    #[test]
+    fn test_baoab_equipartition() {
+        // <v^2> = T and omega^2 <x^2> = T
+        let omega_square = 4.0;
+        let temperature = 0.5;
+        let langevin = Langevin {
+            system: SimpleHarmonicOscillator { omega_square },
+            gamma: 1.0,
+            temperature,
+        };
+        let mut test_solver = StochasticSolver::with_observer(
+            BaoabMethod::new(2024),
+            langevin,
+            Vector::zero(),
+            RunningStatistics::new(),
+        );
+        test_solver.run(0.1, 200_000);
+
+        let variance = test_solver.observer().y().variance();
+        let kinetic = variance[1];
+        let potential = omega_square * variance[0];
+        assert!(
+            (kinetic - temperature).abs() < 0.05 * temperature,
+            "<v^2> = {kinetic}"
+        );
+        assert!(
+            (potential - temperature).abs() < 0.05 * temperature,
+            "omega^2 <x^2> = {potential}"
+        );
+    }
+
+    #[test]
+    fn test_langevin_with_euler_maruyama() {
+        let langevin = Langevin {
+            system: SimpleHarmonicOscillator { omega_square: 1.0 },
+            gamma: 1.0,
+            temperature: 2.0,
+        };
+        let mut test_solver = StochasticSolver::with_observer(
+            EulerMaruyamaMethod::new(5),
+            langevin,
+            Vector::zero(),
+            RunningStatistics::new(),
+        );
+        test_solver.run(0.01, 200_000);
+
+        let variance = test_solver.observer().y().variance();
+        assert!((variance[0] - 2.0).abs() < 0.2, "<x^2> = {}", variance[0]);
+        assert!((variance[1] - 2.0).abs() < 0.2, "<v^2> = {}", variance[1]);
+    }
+}
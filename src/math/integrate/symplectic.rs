@@ -0,0 +1,413 @@
+use crate::math::integrate::implicit::{Newton, implicit_midpoint};
+use crate::math::integrate::{FirstOrderSystem, Integrator, System};
+
+/// Alternating drift/kick sequence of a splitting method
+///
+/// drift `c[0]`, kick `d[0]`, drift `c[1]`, ..., kick `d[m-1]`, drift `c[m]`
+/// $$ y \leftarrow y + c_i h y', \quad y' \leftarrow y' + d_i h f(t, y) $$
+fn drift_kick<S: System>(
+    system: &S,
+    t: f64,
+    y: S::Vector,
+    y_prime: S::Vector,
+    h: f64,
+    drifts: &[f64],
+    kicks: &[f64],
+) -> (S::Vector, S::Vector) {
+    let (mut t, mut y, mut y_prime) = (t, y, y_prime);
+    for (c, d) in drifts.iter().zip(kicks.iter()) {
+        y = y + y_prime * (c * h);
+        t += c * h;
+        y_prime = y_prime + system.derivative(t, y, y_prime) * (d * h);
+    }
+    let c = drifts[drifts.len() - 1];
+    (y + y_prime * (c * h), y_prime)
+}
+
+/// Composition of implicit midpoint steps of sizes `w_i h`
+///
+/// A `FirstOrderSystem` has no position/velocity splitting, so the methods
+/// below compose the (symmetric, symplectic) implicit midpoint rule instead of Leapfrog.
+fn compose_midpoint<F: FirstOrderSystem>(
+    system: &F,
+    t: f64,
+    y: F::Vector,
+    h: f64,
+    weights: &[f64],
+) -> F::Vector {
+    let newton = Newton::default();
+    let (mut t, mut y) = (t, y);
+    for w in weights {
+        y = implicit_midpoint(&newton, system, t, y, w * h);
+        t += w * h;
+    }
+    y
+}
+
+const CBRT_2: f64 = 1.259_921_049_894_873_2;
+
+/// Triple-jump weight $$ 1 / (2 - 2^{1/3}) $$
+const TRIPLE_JUMP: f64 = 1.0 / (2.0 - CBRT_2);
+const TRIPLE_JUMP_WEIGHTS: [f64; 3] = [TRIPLE_JUMP, 1.0 - 2.0 * TRIPLE_JUMP, TRIPLE_JUMP];
+
+/// Yoshida 4th-order Integration
+///
+/// Triple-jump composition of three Leapfrog (kick-drift-kick) steps with weights
+/// $$ w_1 = \frac{1}{2 - 2^{1/3}}, \quad w_0 = -\frac{2^{1/3}}{2 - 2^{1/3}} $$
+/// Symplectic and time-reversible, 4 derivative evaluations per step: the closing
+/// half kick is not reused as the next step's opening one.
+/// **System::derivative must not use y_prime in return.**
+pub struct Yoshida4Method;
+
+/// Yoshida 6th-order Integration (solution A)
+///
+/// Composition of seven Leapfrog (kick-drift-kick) steps.
+/// Symplectic and time-reversible, 8 derivative evaluations per step.
+/// **System::derivative must not use y_prime in return.**
+pub struct Yoshida6Method;
+
+/// Forest-Ruth Integration
+///
+/// 4th-order triple-jump composition of position Verlet (drift-kick-drift) steps.
+/// Symplectic and time-reversible, 3 derivative evaluations per step.
+/// **System::derivative must not use y_prime in return.**
+///
+/// A `FirstOrderSystem` has no drift/kick splitting, so it gets the same triple
+/// jump of implicit midpoint steps as `Yoshida4Method`.
+pub struct ForestRuthMethod;
+
+/// Position-Extended Forest-Ruth-Like Integration (Omelyan, Mryglod & Folk, 2002)
+///
+/// 4th-order, with an error constant about 100 times smaller than Forest-Ruth.
+/// Symplectic and time-reversible, 4 derivative evaluations per step.
+/// **System::derivative must not use y_prime in return.**
+///
+/// PEFRL is not a composition of a symmetric method, so a `FirstOrderSystem`
+/// gets the triple jump of implicit midpoint steps of `Yoshida4Method`, without
+/// the smaller error constant.
+pub struct PefrlMethod;
+
+impl Yoshida4Method {
+    const W1: f64 = TRIPLE_JUMP;
+    const W0: f64 = 1.0 - 2.0 * TRIPLE_JUMP;
+
+    const DRIFTS: [f64; 5] = [0.0, Self::W1, Self::W0, Self::W1, 0.0];
+    const KICKS: [f64; 4] = [
+        Self::W1 / 2.0,
+        (Self::W1 + Self::W0) / 2.0,
+        (Self::W0 + Self::W1) / 2.0,
+        Self::W1 / 2.0,
+    ];
+}
+
+impl Yoshida6Method {
+    const W1: f64 = -1.177_679_984_178_87;
+    const W2: f64 = 0.235_573_213_359_357;
+    const W3: f64 = 0.784_513_610_477_560;
+    const W0: f64 = 1.0 - 2.0 * (Self::W1 + Self::W2 + Self::W3);
+
+    const W: [f64; 7] = [
+        Self::W3,
+        Self::W2,
+        Self::W1,
+        Self::W0,
+        Self::W1,
+        Self::W2,
+        Self::W3,
+    ];
+    const DRIFTS: [f64; 9] = [
+        0.0,
+        Self::W[0],
+        Self::W[1],
+        Self::W[2],
+        Self::W[3],
+        Self::W[4],
+        Self::W[5],
+        Self::W[6],
+        0.0,
+    ];
+    const KICKS: [f64; 8] = [
+        Self::W[0] / 2.0,
+        (Self::W[0] + Self::W[1]) / 2.0,
+        (Self::W[1] + Self::W[2]) / 2.0,
+        (Self::W[2] + Self::W[3]) / 2.0,
+        (Self::W[3] + Self::W[4]) / 2.0,
+        (Self::W[4] + Self::W[5]) / 2.0,
+        (Self::W[5] + Self::W[6]) / 2.0,
+        Self::W[6] / 2.0,
+    ];
+}
+
+impl ForestRuthMethod {
+    const THETA: f64 = TRIPLE_JUMP;
+
+    const DRIFTS: [f64; 4] = [
+        Self::THETA / 2.0,
+        (1.0 - Self::THETA) / 2.0,
+        (1.0 - Self::THETA) / 2.0,
+        Self::THETA / 2.0,
+    ];
+    const KICKS: [f64; 3] = [Self::THETA, 1.0 - 2.0 * Self::THETA, Self::THETA];
+}
+
+impl PefrlMethod {
+    const XI: f64 = 0.178_617_895_844_809_1;
+    const LAMBDA: f64 = -0.212_341_831_062_605_4;
+    const CHI: f64 = -0.066_264_582_669_818_5;
+
+    const DRIFTS: [f64; 5] = [
+        Self::XI,
+        Self::CHI,
+        1.0 - 2.0 * (Self::CHI + Self::XI),
+        Self::CHI,
+        Self::XI,
+    ];
+    const KICKS: [f64; 4] = [
+        (1.0 - 2.0 * Self::LAMBDA) / 2.0,
+        Self::LAMBDA,
+        Self::LAMBDA,
+        (1.0 - 2.0 * Self::LAMBDA) / 2.0,
+    ];
+}
+
+impl Integrator for Yoshida4Method {
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
+    where
+        F: FirstOrderSystem,
+    {
+        compose_midpoint(system, t, y, h, &TRIPLE_JUMP_WEIGHTS)
+    }
+
+    fn step<S>(
+        &mut self,
+        system: &S,
+        t: f64,
+        y: S::Vector,
+        y_prime: S::Vector,
+        h: f64,
+    ) -> (S::Vector, S::Vector)
+    where
+        S: System,
+    {
+        drift_kick(system, t, y, y_prime, h, &Self::DRIFTS, &Self::KICKS)
+    }
+}
+
+impl Integrator for Yoshida6Method {
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
+    where
+        F: FirstOrderSystem,
+    {
+        compose_midpoint(system, t, y, h, &Self::W)
+    }
+
+    fn step<S>(
+        &mut self,
+        system: &S,
+        t: f64,
+        y: S::Vector,
+        y_prime: S::Vector,
+        h: f64,
+    ) -> (S::Vector, S::Vector)
+    where
+        S: System,
+    {
+        drift_kick(system, t, y, y_prime, h, &Self::DRIFTS, &Self::KICKS)
+    }
+}
+
+impl Integrator for ForestRuthMethod {
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
+    where
+        F: FirstOrderSystem,
+    {
+        compose_midpoint(system, t, y, h, &TRIPLE_JUMP_WEIGHTS)
+    }
+
+    fn step<S>(
+        &mut self,
+        system: &S,
+        t: f64,
+        y: S::Vector,
+        y_prime: S::Vector,
+        h: f64,
+    ) -> (S::Vector, S::Vector)
+    where
+        S: System,
+    {
+        drift_kick(system, t, y, y_prime, h, &Self::DRIFTS, &Self::KICKS)
+    }
+}
+
+impl Integrator for PefrlMethod {
+    fn step_first_order<F>(&mut self, system: &F, t: f64, y: F::Vector, h: f64) -> F::Vector
+    where
+        F: FirstOrderSystem,
+    {
+        compose_midpoint(system, t, y, h, &TRIPLE_JUMP_WEIGHTS)
+    }
+
+    fn step<S>(
+        &mut self,
+        system: &S,
+        t: f64,
+        y: S::Vector,
+        y_prime: S::Vector,
+        h: f64,
+    ) -> (S::Vector, S::Vector)
+    where
+        S: System,
+    {
+        drift_kick(system, t, y, y_prime, h, &Self::DRIFTS, &Self::KICKS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::math::core::{LinearSpace, Vector, VectorSpace};
+    use crate::math::integrate::{LeapfrogMethod, Solver};
+
+    use super::*;
+
+    struct TestHarmonicOscillator;
+    struct TestKeplerOrbit;
+
+    /// y'' + y = 0
+    impl System for TestHarmonicOscillator {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            -y
+        }
+    }
+
+    /// r'' = -r / |r|^3
+    impl System for TestKeplerOrbit {
+        type Vector = Vector<f64, 2>;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            let r_sq = y.norm_sq();
+            -y / (r_sq * r_sq.sqrt())
+        }
+    }
+
+    /// y'' + y = 0, counting derivative evaluations
+    struct TestCountingOscillator {
+        evaluations: Cell<usize>,
+    }
+
+    impl System for TestCountingOscillator {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector, _y_prime: Self::Vector) -> Self::Vector {
+            self.evaluations.set(self.evaluations.get() + 1);
+            -y
+        }
+    }
+
+    fn evaluations_per_step<I: Integrator>(mut integrator: I) -> usize {
+        let system = TestCountingOscillator {
+            evaluations: Cell::new(0),
+        };
+        integrator.step(&system, 0.0, 1.0, 0.0, 0.01);
+        system.evaluations.get()
+    }
+
+    fn kepler_energy(y: Vector<f64, 2>, y_prime: Vector<f64, 2>) -> f64 {
+        0.5 * y_prime.norm_sq() - 1.0 / y.norm()
+    }
+
+    fn oscillator_error<I: Integrator>(integrator: I, h: f64) -> f64 {
+        let steps = (1.0 / h).round() as usize;
+        let mut test_solver = Solver::new(integrator, TestHarmonicOscillator, 1.0, 0.0);
+        test_solver.run(h, steps);
+        let (_, y, _) = test_solver.get_current();
+        (y - 1.0f64.cos()).abs()
+    }
+
+    fn assert_convergence_order<I: Integrator, F: Fn() -> I>(
+        name: &str,
+        integrator: F,
+        order: f64,
+    ) {
+        let coarse = oscillator_error(integrator(), 0.1);
+        let fine = oscillator_error(integrator(), 0.05);
+        let measured = (coarse / fine).log2();
+        assert!(
+            (measured - order).abs() < 0.3,
+            "{name}: expected order {order}, measured {measured}"
+        );
+    }
+
+    #[test]
+    fn test_symplectic_convergence_order() {
+        assert_convergence_order("LeapfrogMethod", || LeapfrogMethod, 2.0);
+        assert_convergence_order("Yoshida4Method", || Yoshida4Method, 4.0);
+        assert_convergence_order("Yoshida6Method", || Yoshida6Method, 6.0);
+        assert_convergence_order("ForestRuthMethod", || ForestRuthMethod, 4.0);
+        assert_convergence_order("PefrlMethod", || PefrlMethod, 4.0);
+    }
+
+    /// y' = -y
+    struct TestDecay;
+
+    impl FirstOrderSystem for TestDecay {
+        type Vector = f64;
+
+        fn derivative(&self, _t: f64, y: Self::Vector) -> Self::Vector {
+            -y
+        }
+    }
+
+    #[test]
+    fn test_evaluations_per_step() {
+        assert_eq!(evaluations_per_step(Yoshida4Method), 4);
+        assert_eq!(evaluations_per_step(Yoshida6Method), 8);
+        assert_eq!(evaluations_per_step(ForestRuthMethod), 3);
+        assert_eq!(evaluations_per_step(PefrlMethod), 4);
+    }
+
+    #[test]
+    fn test_first_order_fallback_to_triple_jump() {
+        let yoshida4 = Yoshida4Method.step_first_order(&TestDecay, 0.0, 1.0, 0.1);
+        let forest_ruth = ForestRuthMethod.step_first_order(&TestDecay, 0.0, 1.0, 0.1);
+        let pefrl = PefrlMethod.step_first_order(&TestDecay, 0.0, 1.0, 0.1);
+        assert_eq!(forest_ruth, yoshida4);
+        assert_eq!(pefrl, yoshida4);
+        assert!((yoshida4 - (-0.1f64).exp()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_pefrl_more_accurate_than_forest_ruth() {
+        let error_fr = oscillator_error(ForestRuthMethod, 0.1);
+        let error_pefrl = oscillator_error(PefrlMethod, 0.1);
+        assert!(
+            error_pefrl < error_fr / 10.0,
+            "PEFRL: {error_pefrl}  Forest-Ruth: {error_fr}"
+        );
+    }
+
+    #[test]
+    fn test_yoshida4_kepler_orbit_energy() {
+        let e: f64 = 0.5;
+        let y0 = Vector::new([1.0 - e, 0.0]);
+        let y0_prime = Vector::new([0.0, ((1.0 + e) / (1.0 - e)).sqrt()]);
+        let initial_energy = kepler_energy(y0, y0_prime);
+
+        let mut test_solver = Solver::new(Yoshida4Method, TestKeplerOrbit, y0, y0_prime);
+        test_solver.run(0.01, 20_000);
+        let (_, ys, ys_prime) = test_solver.get_results();
+        let error = ys
+            .iter()
+            .zip(ys_prime.iter())
+            .map(|(y, y_prime)| (kepler_energy(*y, *y_prime) - initial_energy).abs())
+            .fold(0.0, f64::max);
+
+        assert!(
+            error < 1e-5,
+            "Yoshida4Method: Kepler energy error {error:e}"
+        );
+    }
+}
@@ -0,0 +1,462 @@
+use crate::math::{
+    complex::{Complex, ComplexSpace},
+    core::{LinearSpace, ScalarSpace},
+    fft::wavenumbers,
+    field::Field,
+    grid::Grid,
+};
+
+/// Interpolation scheme, from cheapest to most accurate
+///
+/// Schemes act as tensor products over the axes, so `Linear` is trilinear and
+/// `Cubic` tricubic on a 3D grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Value of the closest sample, with zero gradient
+    Nearest,
+    /// Piecewise linear, $$ O(\Delta x^2) $$
+    Linear,
+    /// Catmull-Rom cubic through 4 samples per axis, $$ O(\Delta x^3) $$ and C1
+    Cubic,
+    /// Trigonometric interpolant of all samples, exact for band-limited fields
+    Fourier,
+}
+
+/// Interpolated value and gradient
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub value: f64,
+    pub gradient: Vec<f64>,
+}
+
+/// Samples a real grid at arbitrary physical positions of its domain
+///
+/// A periodic interpolator (the default) wraps positions outside the domain
+/// around. A non-periodic one extends the samples linearly past the first and
+/// last one of each axis and panics outside them.
+#[derive(Clone, Debug)]
+pub struct Interpolator {
+    method: Interpolation,
+    grid: Grid<f64>,
+    periodic: bool,
+    /// Unnormalized spectrum for `Fourier`
+    spectrum: Option<Grid<Complex>>,
+}
+
+impl Interpolator {
+    pub fn new(grid: Grid<f64>, method: Interpolation) -> Self {
+        let spectrum = (method == Interpolation::Fourier).then(|| {
+            let mut spectrum = grid.map(|x| Complex::new(x, 0.0));
+            spectrum.fft();
+            spectrum
+        });
+        Self {
+            method,
+            grid,
+            periodic: true,
+            spectrum,
+        }
+    }
+
+    /// Non-periodic grids need a local method, the Fourier interpolant is periodic,
+    /// and two samples per axis to extrapolate from
+    pub fn with_periodic(mut self, periodic: bool) -> Self {
+        if !periodic && self.method == Interpolation::Fourier {
+            panic!("Fourier interpolation of a non-periodic grid");
+        }
+        if !periodic && self.grid.shape().iter().any(|&n| n < 2) {
+            panic!(
+                "Non-periodic grid of shape {:?} needs two samples per axis",
+                self.grid.shape()
+            );
+        }
+        self.periodic = periodic;
+        self
+    }
+
+    pub fn is_periodic(&self) -> bool {
+        self.periodic
+    }
+
+    pub fn method(&self) -> Interpolation {
+        self.method
+    }
+
+    pub fn grid(&self) -> &Grid<f64> {
+        &self.grid
+    }
+
+    pub fn value(&self, position: &[f64]) -> f64 {
+        self.sample(position).value
+    }
+
+    pub fn gradient(&self, position: &[f64]) -> Vec<f64> {
+        self.sample(position).gradient
+    }
+
+    pub fn sample(&self, position: &[f64]) -> Sample {
+        if position.len() != self.grid.ndim() {
+            panic!(
+                "Position {position:?} on a grid of shape {:?}",
+                self.grid.shape()
+            );
+        }
+        match &self.spectrum {
+            Some(spectrum) => self.fourier(spectrum, position),
+            None => self.local(position),
+        }
+    }
+
+    /// Tensor product of 1D kernels over the neighbouring samples
+    fn local(&self, position: &[f64]) -> Sample {
+        let ndim = self.grid.ndim();
+        let spacing = self.grid.spacing();
+        let kernels: Vec<Vec<Tap>> = (0..ndim)
+            .map(|axis| {
+                let n = self.grid.shape()[axis];
+                let s = (position[axis] - self.grid.domain().min()[axis]) / spacing[axis];
+                if self.periodic {
+                    return kernel(self.method, s.rem_euclid(n as f64), n, true);
+                }
+                if !(0.0..=(n - 1) as f64).contains(&s) {
+                    panic!("Position {position:?} outside the samples of a non-periodic grid");
+                }
+                kernel(self.method, s, n, false)
+            })
+            .collect();
+        let taps: Vec<usize> = kernels.iter().map(|k| k.len()).collect();
+
+        let mut sample = Sample {
+            value: 0.0,
+            gradient: vec![0.0; ndim],
+        };
+        let mut index = vec![0; ndim];
+        loop {
+            let offset: usize = (0..ndim)
+                .map(|axis| kernels[axis][index[axis]].index * self.grid.strides()[axis])
+                .sum();
+            let u = self.grid.as_slice()[offset];
+            let weights: Vec<f64> = (0..ndim).map(|a| kernels[a][index[a]].weight).collect();
+            sample.value += u * weights.iter().product::<f64>();
+            for axis in 0..ndim {
+                let others: f64 = (0..ndim)
+                    .filter(|&b| b != axis)
+                    .map(|b| weights[b])
+                    .product();
+                sample.gradient[axis] +=
+                    u * kernels[axis][index[axis]].slope / spacing[axis] * others;
+            }
+            if !advance(&mut index, &taps) {
+                return sample;
+            }
+        }
+    }
+
+    /// Sum of all Fourier modes at the position
+    ///
+    /// The Nyquist mode of an even axis is taken as a cosine, which keeps the
+    /// interpolant real and its gradient zero there.
+    fn fourier(&self, spectrum: &Grid<Complex>, position: &[f64]) -> Sample {
+        let ndim = self.grid.ndim();
+        let shape = self.grid.shape();
+        let lengths = self.grid.domain().lengths();
+        // Basis functions of every mode per axis, and their x-derivatives
+        let (basis, slopes): (Vec<Vec<Complex>>, Vec<Vec<Complex>>) = (0..ndim)
+            .map(|axis| {
+                let n = shape[axis];
+                let x = position[axis] - self.grid.domain().min()[axis];
+                wavenumbers(n, lengths[axis])
+                    .into_iter()
+                    .enumerate()
+                    .map(|(m, k)| {
+                        if n.is_multiple_of(2) && m == n / 2 {
+                            (
+                                Complex::new((k * x).cos(), 0.0),
+                                Complex::new(-k * (k * x).sin(), 0.0),
+                            )
+                        } else {
+                            let phase = Complex::cis(k * x);
+                            (phase, Complex::new(0.0, k) * phase)
+                        }
+                    })
+                    .unzip()
+            })
+            .unzip();
+
+        let mut value = Complex::zero();
+        let mut gradient = vec![Complex::zero(); ndim];
+        let mut index = vec![0; ndim];
+        for &c in spectrum.as_slice() {
+            let factors: Vec<Complex> = (0..ndim).map(|a| basis[a][index[a]]).collect();
+            value = value + c * factors.iter().fold(Complex::one(), |p, &f| p * f);
+            for (axis, gradient) in gradient.iter_mut().enumerate() {
+                let term = (0..ndim).fold(c, |p, b| {
+                    p * if b == axis {
+                        slopes[b][index[b]]
+                    } else {
+                        factors[b]
+                    }
+                });
+                *gradient = *gradient + term;
+            }
+            advance(&mut index, shape);
+        }
+        let total = self.grid.len() as f64;
+        Sample {
+            value: value.re() / total,
+            gradient: gradient.iter().map(|g| g.re() / total).collect(),
+        }
+    }
+}
+
+/// Sample of a 1D kernel: wrapped index, weight and d(weight)/ds
+struct Tap {
+    index: usize,
+    weight: f64,
+    slope: f64,
+}
+
+/// 1D kernel at `s` in units of the spacing, in [0, n) if periodic and in
+/// [0, n - 1] otherwise
+///
+/// Without periodicity the samples beyond the ends are the linear extrapolations
+/// $$ u_{-1} = 2 u_0 - u_1 $$ and $$ u_n = 2 u_{n-1} - u_{n-2} $$.
+fn kernel(method: Interpolation, s: f64, n: usize, periodic: bool) -> Vec<Tap> {
+    let base = if periodic {
+        s.floor()
+    } else {
+        s.floor().min(n.saturating_sub(2) as f64)
+    };
+    let t = s - base;
+    // (offset from base, weight, slope)
+    let taps: Vec<(isize, f64, f64)> = match method {
+        Interpolation::Nearest => vec![(if t < 0.5 { 0 } else { 1 }, 1.0, 0.0)],
+        Interpolation::Linear => vec![(0, 1.0 - t, -1.0), (1, t, 1.0)],
+        Interpolation::Cubic => {
+            let (t2, t3) = (t * t, t * t * t);
+            let weights = [
+                (-t3 + 2.0 * t2 - t) / 2.0,
+                (3.0 * t3 - 5.0 * t2 + 2.0) / 2.0,
+                (-3.0 * t3 + 4.0 * t2 + t) / 2.0,
+                (t3 - t2) / 2.0,
+            ];
+            let slopes = [
+                (-3.0 * t2 + 4.0 * t - 1.0) / 2.0,
+                (9.0 * t2 - 10.0 * t) / 2.0,
+                (-9.0 * t2 + 8.0 * t + 1.0) / 2.0,
+                (3.0 * t2 - 2.0 * t) / 2.0,
+            ];
+            (0..4)
+                .map(|j| (j as isize - 1, weights[j], slopes[j]))
+                .collect()
+        }
+        Interpolation::Fourier => unreachable!(),
+    };
+    let n = n as isize;
+    taps.into_iter()
+        .flat_map(|(offset, weight, slope)| {
+            let i = base as isize + offset;
+            let samples = if periodic {
+                vec![(i.rem_euclid(n), 1.0)]
+            } else if i < 0 {
+                vec![(0, 2.0), (1, -1.0)]
+            } else if i >= n {
+                vec![(n - 1, 2.0), (n - 2, -1.0)]
+            } else {
+                vec![(i, 1.0)]
+            };
+            samples.into_iter().map(move |(index, c)| Tap {
+                index: index as usize,
+                weight: c * weight,
+                slope: c * slope,
+            })
+        })
+        .collect()
+}
+
+/// Next multi-index below `shape` in row-major order, false after the last
+fn advance(index: &mut [usize], shape: &[usize]) -> bool {
+    for axis in (0..index.len()).rev() {
+        index[axis] += 1;
+        if index[axis] < shape[axis] {
+            return true;
+        }
+        index[axis] = 0;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::math::grid::Domain;
+    use crate::math::random::Rng;
+
+    use super::*;
+
+    const METHODS: [Interpolation; 4] = [
+        Interpolation::Nearest,
+        Interpolation::Linear,
+        Interpolation::Cubic,
+        Interpolation::Fourier,
+    ];
+
+    /// f = sin(2 pi x / 2) cos(2 pi y / 3) + cos(2 pi z) on [0, 2) x [-1, 2) x [0, 1)
+    fn wave(x: &[f64]) -> f64 {
+        (PI * x[0]).sin() * (2.0 * PI * x[1] / 3.0).cos() + (2.0 * PI * x[2]).cos()
+    }
+
+    fn wave_gradient(x: &[f64]) -> Vec<f64> {
+        vec![
+            PI * (PI * x[0]).cos() * (2.0 * PI * x[1] / 3.0).cos(),
+            -2.0 * PI / 3.0 * (PI * x[0]).sin() * (2.0 * PI * x[1] / 3.0).sin(),
+            -2.0 * PI * (2.0 * PI * x[2]).sin(),
+        ]
+    }
+
+    fn sampled(shape: &[usize]) -> Grid<f64> {
+        let domain = Domain::new(&[0.0, -1.0, 0.0], &[2.0, 2.0, 1.0]);
+        let grid = Grid::<f64>::new(shape).with_domain(domain);
+        Grid::from_fn(shape, |i| wave(&grid.position(i))).with_domain(grid.domain().clone())
+    }
+
+    fn max_error(method: Interpolation, n: usize, points: &[Vec<f64>]) -> (f64, f64) {
+        let interpolator = Interpolator::new(sampled(&[n, n, n]), method);
+        points.iter().fold((0.0, 0.0), |(value, gradient), x| {
+            let sample = interpolator.sample(x);
+            let gradient_error = sample
+                .gradient
+                .iter()
+                .zip(wave_gradient(x))
+                .map(|(a, b)| (a - b).abs())
+                .fold(gradient, f64::max);
+            (value.max((sample.value - wave(x)).abs()), gradient_error)
+        })
+    }
+
+    fn random_points(count: usize) -> Vec<Vec<f64>> {
+        let mut rng = Rng::new(21);
+        (0..count)
+            .map(|_| {
+                vec![
+                    2.0 * rng.uniform(),
+                    3.0 * rng.uniform() - 1.0,
+                    rng.uniform(),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_exact_at_samples() {
+        let grid = sampled(&[6, 5, 4]);
+        for method in METHODS {
+            let interpolator = Interpolator::new(grid.clone(), method);
+            for offset in 0..grid.len() {
+                let position = grid.position(&grid.unravel(offset));
+                let value = interpolator.value(&position);
+                assert!(
+                    (value - grid.as_slice()[offset]).abs() < 1e-12,
+                    "{method:?} at {position:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_convergence() {
+        let points = random_points(200);
+        let linear = |n| max_error(Interpolation::Linear, n, &points);
+        let cubic = |n| max_error(Interpolation::Cubic, n, &points);
+        let order = |coarse: f64, fine: f64| (coarse / fine).log2();
+
+        let (coarse, fine) = (linear(16), linear(32));
+        assert!(order(coarse.0, fine.0) > 1.8, "linear value");
+        assert!(order(coarse.1, fine.1) > 0.8, "linear gradient");
+        let (coarse, fine) = (cubic(16), cubic(32));
+        assert!(order(coarse.0, fine.0) > 2.8, "cubic value");
+        assert!(order(coarse.1, fine.1) > 1.8, "cubic gradient");
+        assert!(fine.0 < 1e-3);
+    }
+
+    #[test]
+    fn test_fourier_band_limited() {
+        let points = random_points(20);
+        let (value, gradient) = max_error(Interpolation::Fourier, 6, &points);
+        assert!(value < 1e-12, "{value}");
+        assert!(gradient < 1e-11, "{gradient}");
+    }
+
+    #[test]
+    fn test_nearest_and_linear() {
+        let grid = Grid::from_vec(&[4], vec![0.0, 1.0, 4.0, 9.0]);
+        let nearest = Interpolator::new(grid.clone(), Interpolation::Nearest);
+        assert_eq!(nearest.value(&[1.4]), 1.0);
+        assert_eq!(nearest.value(&[1.6]), 4.0);
+        assert_eq!(nearest.sample(&[3.7]).value, 0.0);
+        assert_eq!(nearest.gradient(&[2.2]), vec![0.0]);
+
+        let linear = Interpolator::new(grid, Interpolation::Linear);
+        assert!((linear.value(&[2.25]) - 5.25).abs() < 1e-15);
+        assert!((linear.gradient(&[2.25])[0] - 5.0).abs() < 1e-15);
+        // Wraps from the last sample back to the first
+        assert!((linear.value(&[3.5]) - 4.5).abs() < 1e-15);
+        assert!((linear.value(&[-0.5]) - 4.5).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_non_periodic() {
+        // Linear extrapolation past the ends reproduces linear data up to the last sample
+        let grid = Grid::from_fn(&[5], |i| 2.0 * i[0] as f64 + 1.0);
+        for method in [Interpolation::Linear, Interpolation::Cubic] {
+            let interpolator = Interpolator::new(grid.clone(), method).with_periodic(false);
+            assert!(!interpolator.is_periodic());
+            for x in [0.0, 0.3, 2.5, 3.6, 4.0] {
+                let sample = interpolator.sample(&[x]);
+                assert!(
+                    (sample.value - (2.0 * x + 1.0)).abs() < 1e-12,
+                    "{method:?} at {x}"
+                );
+                assert!(
+                    (sample.gradient[0] - 2.0).abs() < 1e-12,
+                    "{method:?} at {x}"
+                );
+            }
+        }
+        // The periodic cubic reads the first sample past the last one
+        let periodic = Interpolator::new(grid, Interpolation::Cubic);
+        assert!((periodic.value(&[3.5]) - 8.0).abs() > 0.1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_non_periodic_outside() {
+        let grid = Grid::from_vec(&[4], vec![0.0, 1.0, 4.0, 9.0]);
+        Interpolator::new(grid, Interpolation::Linear)
+            .with_periodic(false)
+            .value(&[3.5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_non_periodic_fourier() {
+        let grid = Grid::from_vec(&[4], vec![0.0, 1.0, 4.0, 9.0]);
+        Interpolator::new(grid, Interpolation::Fourier).with_periodic(false);
+    }
+
+    #[test]
+    fn test_periodic_wrap() {
+        let grid = sampled(&[8, 6, 5]);
+        let x = [0.3, 0.7, 0.45];
+        let shifted = [0.3 - 2.0, 0.7 + 6.0, 0.45 + 1.0];
+        for method in METHODS {
+            let interpolator = Interpolator::new(grid.clone(), method);
+            let (a, b) = (interpolator.sample(&x), interpolator.sample(&shifted));
+            assert!((a.value - b.value).abs() < 1e-12, "{method:?}");
+            for (a, b) in a.gradient.iter().zip(&b.gradient) {
+                assert!((a - b).abs() < 1e-10, "{method:?}");
+            }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt;
 use std::ops::{Add, Div, Mul, Neg, Sub};
 
-use crate::math::core::{LinearSpace, ScalarSpace, Vector, VectorSpace};
+use crate::math::core::{LinearSpace, ScalarSpace, Vector};
 
 pub trait MatrixSpace<S: ScalarSpace, const N: usize, const M: usize>:
     LinearSpace + MatMul<Vector<S, M>, Output = Vector<S, N>> + Mul<Vector<S, M>, Output = Vector<S, N>>
@@ -52,7 +52,7 @@ impl<S: ScalarSpace, const N: usize, const M: usize> LinearSpace for Matrix<S, N
     type Data = [[S; M]; N];
 
     fn new(data: Self::Data) -> Self {
-        Self { data: data }
+        Self { data }
     }
     fn zero() -> Self {
         Self {
@@ -65,13 +65,34 @@ impl<S: ScalarSpace, const N: usize, const M: usize> LinearSpace for Matrix<S, N
     fn get_data(&self) -> Self::Data {
         self.data
     }
+    fn dim() -> usize {
+        N * M * S::dim()
+    }
+    fn components(&self) -> Vec<f64> {
+        self.data
+            .iter()
+            .flatten()
+            .flat_map(|e| e.components())
+            .collect()
+    }
+    fn from_components(components: &[f64]) -> Self {
+        let d = S::dim();
+        Self {
+            data: std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    let k = i * M + j;
+                    S::from_components(&components[k * d..(k + 1) * d])
+                })
+            }),
+        }
+    }
 }
 
 impl<S: ScalarSpace, const N: usize> LinearSpace for SquareMatrix<S, N> {
     type Data = [[S; N]; N];
 
     fn new(data: Self::Data) -> Self {
-        Self { data: data }
+        Self { data }
     }
     fn zero() -> Self {
         Self {
@@ -84,6 +105,27 @@ impl<S: ScalarSpace, const N: usize> LinearSpace for SquareMatrix<S, N> {
     fn get_data(&self) -> Self::Data {
         self.data
     }
+    fn dim() -> usize {
+        N * N * S::dim()
+    }
+    fn components(&self) -> Vec<f64> {
+        self.data
+            .iter()
+            .flatten()
+            .flat_map(|e| e.components())
+            .collect()
+    }
+    fn from_components(components: &[f64]) -> Self {
+        let d = S::dim();
+        Self {
+            data: std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    let k = i * N + j;
+                    S::from_components(&components[k * d..(k + 1) * d])
+                })
+            }),
+        }
+    }
 }
 
 impl<S: ScalarSpace, const N: usize, const M: usize, const L: usize> MatMul<Matrix<S, M, L>>
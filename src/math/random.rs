@@ -0,0 +1,118 @@
+use std::f64::consts::PI;
+
+use crate::math::core::LinearSpace;
+
+/// Seeded pseudo-random number generator (xoshiro256++)
+///
+/// The state is initialized from the seed with SplitMix64, so equal seeds give
+/// equal sequences on every platform.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: [u64; 4],
+    spare: Option<f64>,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut x = seed;
+        let mut split_mix = || {
+            x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+        Self {
+            state: [split_mix(), split_mix(), split_mix(), split_mix()],
+            spare: None,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Uniform in $$ [0, 1) $$ with 53 random bits
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal $$ N(0, 1) $$ by the Box-Muller transform
+    pub fn normal(&mut self) -> f64 {
+        if let Some(z) = self.spare.take() {
+            return z;
+        }
+        let r = (-2.0 * (1.0 - self.uniform()).ln()).sqrt();
+        let theta = 2.0 * PI * self.uniform();
+        self.spare = Some(r * theta.sin());
+        r * theta.cos()
+    }
+
+    /// Independent standard normal components
+    pub fn normal_vector<V: LinearSpace>(&mut self) -> V {
+        let components: Vec<f64> = (0..V::dim()).map(|_| self.normal()).collect();
+        V::from_components(&components)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::complex::{Complex, ComplexSpace};
+
+    use super::*;
+
+    #[test]
+    fn test_seed_reproducible() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+        let xs: Vec<u64> = (0..100).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..100).map(|_| b.next_u64()).collect();
+        let zs: Vec<u64> = (0..100).map(|_| c.next_u64()).collect();
+        assert_eq!(xs, ys);
+        assert_ne!(xs, zs);
+    }
+
+    #[test]
+    fn test_uniform_moments() {
+        let mut rng = Rng::new(1);
+        let n = 100_000;
+        let xs: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+        assert!(xs.iter().all(|&x| (0.0..1.0).contains(&x)));
+        let mean = xs.iter().sum::<f64>() / n as f64;
+        let variance = xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+        assert!((mean - 0.5).abs() < 0.01, "mean {mean}");
+        assert!((variance - 1.0 / 12.0).abs() < 0.002, "variance {variance}");
+    }
+
+    #[test]
+    fn test_normal_moments() {
+        let mut rng = Rng::new(2);
+        let n = 100_000;
+        let xs: Vec<f64> = (0..n).map(|_| rng.normal()).collect();
+        let mean = xs.iter().sum::<f64>() / n as f64;
+        let variance = xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+        let kurtosis = xs.iter().map(|x| (x - mean).powi(4)).sum::<f64>() / n as f64;
+        assert!(mean.abs() < 0.02, "mean {mean}");
+        assert!((variance - 1.0).abs() < 0.02, "variance {variance}");
+        assert!((kurtosis - 3.0).abs() < 0.1, "kurtosis {kurtosis}");
+    }
+
+    #[test]
+    fn test_normal_vector() {
+        let mut rng = Rng::new(3);
+        let z: Complex = rng.normal_vector();
+        let mut rng = Rng::new(3);
+        assert_eq!(z.re(), rng.normal());
+        assert_eq!(z.im(), rng.normal());
+    }
+}
@@ -0,0 +1,337 @@
+use std::f64::consts::PI;
+
+use crate::math::{
+    complex::Complex,
+    core::{LinearSpace, ScalarSpace},
+    fft::RealFftPlan,
+};
+
+/// Taper applied to each segment before the FFT
+///
+/// Coefficients are periodic (DFT-even), the convention for spectral analysis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+    /// Kaiser window with shape parameter $$ \beta $$
+    Kaiser(f64),
+}
+
+impl Window {
+    pub fn coefficients(&self, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|j| {
+                let phase = 2.0 * PI * j as f64 / n as f64;
+                match *self {
+                    Window::Rectangular => 1.0,
+                    Window::Hann => 0.5 - 0.5 * phase.cos(),
+                    Window::Hamming => 0.54 - 0.46 * phase.cos(),
+                    Window::Blackman => 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos(),
+                    Window::Kaiser(beta) => {
+                        let r = 2.0 * j as f64 / n as f64 - 1.0;
+                        bessel_i0(beta * (1.0 - r * r).sqrt()) / bessel_i0(beta)
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Modified Bessel function of the first kind $$ I_0(x) = \sum_k ((x/2)^k / k!)^2 $$
+fn bessel_i0(x: f64) -> f64 {
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    let mut k = 1.0;
+    while term > 1e-17 * sum {
+        term *= (x / (2.0 * k)).powi(2);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+/// Frequencies $$ f_k = k / (N \Delta t) $$ of the one-sided spectrum, k = 0..=N/2
+pub fn rfft_frequencies(n: usize, dt: f64) -> Vec<f64> {
+    (0..=n / 2).map(|k| k as f64 / (n as f64 * dt)).collect()
+}
+
+/// One-sided power spectral density
+///
+/// `frequencies` are in cycles per unit time ($$ \omega = 2 \pi f $$) and `power` is a
+/// density, so $$ \sum_k P_k \Delta f $$ is the mean square of the windowed signal.
+#[derive(Clone, Debug)]
+pub struct Spectrum {
+    pub frequencies: Vec<f64>,
+    pub power: Vec<f64>,
+}
+
+impl Spectrum {
+    /// Frequency resolution $$ \Delta f $$
+    pub fn resolution(&self) -> f64 {
+        self.frequencies[1] - self.frequencies[0]
+    }
+
+    /// Frequency of the strongest non-DC peak, refined by a parabola through the
+    /// logarithm of the three highest bins
+    pub fn peak_frequency(&self) -> Option<f64> {
+        let k = (1..self.power.len())
+            .filter(|&k| self.power[k] > 0.0)
+            .max_by(|&a, &b| self.power[a].total_cmp(&self.power[b]))?;
+        if k + 1 >= self.power.len() {
+            return Some(self.frequencies[k]);
+        }
+        let (a, b, c) = (
+            self.power[k - 1].ln(),
+            self.power[k].ln(),
+            self.power[k + 1].ln(),
+        );
+        let curvature = a - 2.0 * b + c;
+        let offset = if curvature < 0.0 && a.is_finite() && c.is_finite() {
+            0.5 * (a - c) / curvature
+        } else {
+            0.0
+        };
+        Some(self.frequencies[k] + offset * self.resolution())
+    }
+}
+
+/// STFT power of overlapping segments
+#[derive(Clone, Debug)]
+pub struct Spectrogram {
+    /// Center time of each segment, from the first sample
+    pub times: Vec<f64>,
+    pub frequencies: Vec<f64>,
+    /// `power[segment][k]`, one-sided densities as in `Spectrum`
+    pub power: Vec<Vec<f64>>,
+}
+
+impl Spectrogram {
+    /// Peak frequency of every segment
+    pub fn peak_frequencies(&self) -> Vec<Option<f64>> {
+        self.power
+            .iter()
+            .map(|power| {
+                Spectrum {
+                    frequencies: self.frequencies.clone(),
+                    power: power.clone(),
+                }
+                .peak_frequency()
+            })
+            .collect()
+    }
+}
+
+/// Windowed one-sided PSD of equal-length segments
+struct Estimator {
+    plan: RealFftPlan,
+    window: Vec<f64>,
+    /// $$ 1 / (f_s \sum_j w_j^2) $$
+    scale: f64,
+}
+
+impl Estimator {
+    fn new(n: usize, dt: f64, window: Window) -> Self {
+        let window = window.coefficients(n);
+        let energy: f64 = window.iter().map(|w| w * w).sum();
+        Self {
+            plan: RealFftPlan::new(n),
+            window,
+            scale: dt / energy,
+        }
+    }
+
+    fn power(&self, segment: &[f64]) -> Vec<f64> {
+        let n = segment.len();
+        let windowed: Vec<f64> = segment
+            .iter()
+            .zip(&self.window)
+            .map(|(x, w)| x * w)
+            .collect();
+        let mut spectrum = vec![Complex::zero(); self.plan.spectrum_len()];
+        self.plan.forward(&windowed, &mut spectrum);
+        spectrum
+            .iter()
+            .enumerate()
+            .map(|(k, x_k)| {
+                // Negative frequencies fold onto 0 < k < N/2
+                let fold = if k == 0 || 2 * k == n { 1.0 } else { 2.0 };
+                fold * x_k.abs_sq() * self.scale
+            })
+            .collect()
+    }
+}
+
+/// Start indices of segments of `length` samples advancing by `hop`
+fn segment_starts(n: usize, length: usize, hop: usize) -> Vec<usize> {
+    if length == 0 || hop == 0 {
+        panic!("Segment length and hop must be positive");
+    }
+    if length > n {
+        panic!("Segment of {length} samples is longer than the signal ({n})");
+    }
+    (0..=n - length).step_by(hop).collect()
+}
+
+/// Periodogram: the PSD of the whole signal sampled every `dt`
+pub fn periodogram(x: &[f64], dt: f64, window: Window) -> Spectrum {
+    let estimator = Estimator::new(x.len(), dt, window);
+    Spectrum {
+        frequencies: rfft_frequencies(x.len(), dt),
+        power: estimator.power(x),
+    }
+}
+
+/// Welch's method: the average periodogram of segments overlapping by `overlap` samples
+pub fn welch(x: &[f64], dt: f64, segment: usize, overlap: usize, window: Window) -> Spectrum {
+    if overlap >= segment {
+        panic!("Overlap {overlap} must be shorter than the segment {segment}");
+    }
+    let starts = segment_starts(x.len(), segment, segment - overlap);
+    let estimator = Estimator::new(segment, dt, window);
+    let mut power = vec![0.0; segment / 2 + 1];
+    for &start in &starts {
+        for (p, q) in power
+            .iter_mut()
+            .zip(estimator.power(&x[start..start + segment]))
+        {
+            *p += q / starts.len() as f64;
+        }
+    }
+    Spectrum {
+        frequencies: rfft_frequencies(segment, dt),
+        power,
+    }
+}
+
+/// Short-time Fourier transform power of segments advancing by `hop` samples
+pub fn spectrogram(x: &[f64], dt: f64, segment: usize, hop: usize, window: Window) -> Spectrogram {
+    let starts = segment_starts(x.len(), segment, hop);
+    let estimator = Estimator::new(segment, dt, window);
+    Spectrogram {
+        times: starts
+            .iter()
+            .map(|&start| (start as f64 + (segment - 1) as f64 / 2.0) * dt)
+            .collect(),
+        frequencies: rfft_frequencies(segment, dt),
+        power: starts
+            .iter()
+            .map(|&start| estimator.power(&x[start..start + segment]))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::integrate::{RK4Method, Solver};
+    use crate::math::random::Rng;
+    use crate::physics::harmonic_oscillator::DampedHarmonicOscillator;
+
+    use super::*;
+
+    fn tone(frequency: f64, dt: f64, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|j| (2.0 * PI * frequency * j as f64 * dt).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_windows() {
+        let hann = Window::Hann.coefficients(8);
+        assert_eq!(hann[0], 0.0);
+        assert!((hann[4] - 1.0).abs() < 1e-15);
+        assert!((hann[1] - hann[7]).abs() < 1e-15);
+        assert!((Window::Hamming.coefficients(8)[0] - 0.08).abs() < 1e-15);
+        assert!((Window::Blackman.coefficients(8)[4] - 1.0).abs() < 1e-15);
+
+        let kaiser = Window::Kaiser(0.0).coefficients(5);
+        assert!(kaiser.iter().all(|&w| (w - 1.0).abs() < 1e-15));
+        let kaiser = Window::Kaiser(8.6).coefficients(16);
+        assert!((kaiser[8] - 1.0).abs() < 1e-15);
+        assert!((kaiser[0] - 1.0 / bessel_i0(8.6)).abs() < 1e-15);
+        assert!((bessel_i0(1.0) - 1.266_065_877_752_008_4).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_parseval() {
+        let mut rng = Rng::new(15);
+        for n in [100, 101] {
+            let x: Vec<f64> = (0..n).map(|_| rng.normal()).collect();
+            let spectrum = periodogram(&x, 0.1, Window::Rectangular);
+            let total: f64 = spectrum.power.iter().sum::<f64>() * spectrum.resolution();
+            let mean_square = x.iter().map(|x| x * x).sum::<f64>() / n as f64;
+            assert!((total - mean_square).abs() < 1e-12, "N = {n}");
+        }
+    }
+
+    #[test]
+    fn test_peak_frequency() {
+        let dt = 0.01;
+        for window in [Window::Hann, Window::Blackman, Window::Kaiser(6.0)] {
+            for frequency in [3.37, 12.05, 20.0] {
+                let spectrum = periodogram(&tone(frequency, dt, 1000), dt, window);
+                let peak = spectrum.peak_frequency().unwrap();
+                assert!(
+                    (peak - frequency).abs() < 0.1 * spectrum.resolution(),
+                    "{window:?}: {peak} != {frequency}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_welch_white_noise() {
+        // One-sided density of white noise is 2 sigma^2 dt
+        let (sigma, dt) = (0.5, 0.02);
+        let mut rng = Rng::new(16);
+        let x: Vec<f64> = (0..64 * 1024).map(|_| sigma * rng.normal()).collect();
+        let spectrum = welch(&x, dt, 256, 128, Window::Hann);
+
+        assert_eq!(spectrum.power.len(), 129);
+        assert!((spectrum.frequencies[128] - 0.5 / dt).abs() < 1e-12);
+        let interior = &spectrum.power[1..128];
+        let mean = interior.iter().sum::<f64>() / interior.len() as f64;
+        let expected = 2.0 * sigma * sigma * dt;
+        assert!(
+            (mean - expected).abs() < 0.03 * expected,
+            "{mean} != {expected}"
+        );
+        assert!(
+            interior
+                .iter()
+                .all(|p| (p - expected).abs() < 0.3 * expected)
+        );
+    }
+
+    #[test]
+    fn test_spectrogram_frequency_step() {
+        let dt = 0.001;
+        let x: Vec<f64> = [tone(50.0, dt, 2000), tone(120.0, dt, 2000)].concat();
+        let stft = spectrogram(&x, dt, 250, 250, Window::Hann);
+
+        assert_eq!(stft.times.len(), 16);
+        assert!((stft.times[0] - 0.1245).abs() < 1e-12);
+        for (t, peak) in stft.times.iter().zip(stft.peak_frequencies()) {
+            let expected = if *t < 2.0 { 50.0 } else { 120.0 };
+            assert!((peak.unwrap() - expected).abs() < 0.5, "t = {t}");
+        }
+    }
+
+    #[test]
+    fn test_oscillator_resonance() {
+        // Damped frequency sqrt(k - b^2 / 4) / 2 pi
+        let oscillator = DampedHarmonicOscillator { k: 400.0, b: 0.4 };
+        let expected = (400.0 - 0.04_f64).sqrt() / (2.0 * PI);
+        let dt = 0.005;
+        let mut solver = Solver::new(RK4Method, oscillator, 1.0, 0.0);
+        solver.run(dt, 4000);
+
+        let spectrum = welch(&solver.results().get_ys_f64(), dt, 2000, 1000, Window::Hann);
+        let peak = spectrum.peak_frequency().unwrap();
+        assert!(
+            (peak - expected).abs() < 0.1 * spectrum.resolution(),
+            "{peak} != {expected}"
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use crate::math::core::{LinearSpace, Vector};
+use crate::physics::constants::G;
+use crate::physics::gravity::GravitySolver;
+use crate::physics::particle::Particle;
+
+/// Depth at which coincident particles stop being split
+const MAX_DEPTH: usize = 32;
+
+/// Cube of the octree with the monopole of everything inside
+struct Node {
+    center: Vector<f64, 3>,
+    half_width: f64,
+    mass: f64,
+    center_of_mass: Vector<f64, 3>,
+    children: Vec<usize>,
+    /// Particle indices, only on leaves
+    particles: Vec<usize>,
+}
+
+impl Node {
+    fn contains(&self, x: Vector<f64, 3>) -> bool {
+        (0..3).all(|a| (x[a] - self.center[a]).abs() <= self.half_width)
+    }
+}
+
+/// Barnes-Hut octree gravity, $$ O(N \log N) $$
+///
+/// A cell of width s at distance d from the particle acts as a point mass at its
+/// center of mass when $$ s / d < \theta $$. θ = 0 opens every cell and
+/// reproduces the direct sum.
+pub struct BarnesHut {
+    theta: f64,
+    softening: f64,
+    /// Arena of the last tree, the root first
+    nodes: Vec<Node>,
+    potentials: Vec<f64>,
+}
+
+impl BarnesHut {
+    pub fn new(theta: f64) -> Self {
+        if theta < 0.0 {
+            panic!("Opening angle must be non-negative, got {theta}");
+        }
+        Self {
+            theta,
+            softening: 0.0,
+            nodes: Vec::new(),
+            potentials: Vec::new(),
+        }
+    }
+    /// Plummer softening as in `DirectSum`
+    pub fn with_softening(mut self, softening: f64) -> Self {
+        self.softening = softening;
+        self
+    }
+    pub fn theta(&self) -> f64 {
+        self.theta
+    }
+    pub fn softening(&self) -> f64 {
+        self.softening
+    }
+    /// Number of cells in the last tree
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    fn build(&mut self, particles: &[Particle<Vector<f64, 3>>]) {
+        self.nodes.clear();
+        if particles.is_empty() {
+            return;
+        }
+        let (mut min, mut max) = ([f64::INFINITY; 3], [f64::NEG_INFINITY; 3]);
+        for p in particles {
+            for a in 0..3 {
+                min[a] = min[a].min(p.position()[a]);
+                max[a] = max[a].max(p.position()[a]);
+            }
+        }
+        let center = Vector::new(std::array::from_fn(|a| 0.5 * (min[a] + max[a])));
+        let half_width = (0..3).map(|a| 0.5 * (max[a] - min[a])).fold(0.0, f64::max);
+        self.insert(
+            particles,
+            (0..particles.len()).collect(),
+            center,
+            half_width,
+            0,
+        );
+    }
+
+    /// Adds the cell holding `indices` and its descendants, returns its index
+    fn insert(
+        &mut self,
+        particles: &[Particle<Vector<f64, 3>>],
+        indices: Vec<usize>,
+        center: Vector<f64, 3>,
+        half_width: f64,
+        depth: usize,
+    ) -> usize {
+        let mass: f64 = indices.iter().map(|&i| particles[i].mass()).sum();
+        let center_of_mass = indices.iter().fold(Vector::zero(), |sum, &i| {
+            sum + particles[i].position() * particles[i].mass()
+        }) * (1.0 / mass);
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            center,
+            half_width,
+            mass,
+            center_of_mass,
+            children: Vec::new(),
+            particles: Vec::new(),
+        });
+        if indices.len() == 1 || depth == MAX_DEPTH {
+            self.nodes[index].particles = indices;
+            return index;
+        }
+
+        let mut octants = vec![Vec::new(); 8];
+        for i in indices {
+            let x = particles[i].position();
+            let octant = (0..3)
+                .filter(|&a| x[a] >= center[a])
+                .map(|a| 1 << a)
+                .sum::<usize>();
+            octants[octant].push(i);
+        }
+        let quarter = 0.5 * half_width;
+        for (octant, indices) in octants.into_iter().enumerate() {
+            if indices.is_empty() {
+                continue;
+            }
+            let offset = Vector::new(std::array::from_fn(|a| {
+                if octant & (1 << a) == 0 {
+                    -quarter
+                } else {
+                    quarter
+                }
+            }));
+            let child = self.insert(particles, indices, center + offset, quarter, depth + 1);
+            self.nodes[index].children.push(child);
+        }
+        index
+    }
+
+    /// Acceleration and potential at particle `i` from the cell `node`
+    fn walk(
+        &self,
+        particles: &[Particle<Vector<f64, 3>>],
+        node: usize,
+        i: usize,
+    ) -> (Vector<f64, 3>, f64) {
+        let x = particles[i].position();
+        let pull = |mass: f64, source: Vector<f64, 3>| {
+            let r = source - x;
+            let r2 =
+                r.as_slice().iter().map(|r| r * r).sum::<f64>() + self.softening * self.softening;
+            (r * (G * mass / (r2 * r2.sqrt())), -G * mass / r2.sqrt())
+        };
+        let node = &self.nodes[node];
+        if node.children.is_empty() {
+            return node
+                .particles
+                .iter()
+                .filter(|&&j| j != i)
+                .map(|&j| pull(particles[j].mass(), particles[j].position()))
+                .fold((Vector::zero(), 0.0), |(a, phi), (da, dphi)| {
+                    (a + da, phi + dphi)
+                });
+        }
+        let r = node.center_of_mass - x;
+        let distance = r.as_slice().iter().map(|r| r * r).sum::<f64>().sqrt();
+        if !node.contains(x) && 2.0 * node.half_width < self.theta * distance {
+            return pull(node.mass, node.center_of_mass);
+        }
+        node.children
+            .iter()
+            .map(|&child| self.walk(particles, child, i))
+            .fold((Vector::zero(), 0.0), |(a, phi), (da, dphi)| {
+                (a + da, phi + dphi)
+            })
+    }
+}
+
+impl GravitySolver for BarnesHut {
+    fn accelerations(&mut self, particles: &[Particle<Vector<f64, 3>>]) -> Vec<Vector<f64, 3>> {
+        self.build(particles);
+        let (accelerations, potentials) = (0..particles.len())
+            .map(|i| self.walk(particles, 0, i))
+            .unzip();
+        self.potentials = potentials;
+        accelerations
+    }
+    /// $$ \frac{1}{2} \sum_i m_i \Phi(x_i) $$ from the last tree walk
+    fn potential_energy(&self, particles: &[Particle<Vector<f64, 3>>]) -> f64 {
+        0.5 * particles
+            .iter()
+            .zip(&self.potentials)
+            .map(|(p, phi)| p.mass() * phi)
+            .sum::<f64>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::random::Rng;
+    use crate::physics::gravity::{DirectSum, NBody, force_error};
+
+    use super::*;
+
+    /// Plummer sphere of scale radius 0.1, positions only
+    fn cluster(rng: &mut Rng, n: usize) -> Vec<Particle<Vector<f64, 3>>> {
+        (0..n)
+            .map(|_| {
+                let r = 0.1 / (rng.uniform().powf(-2.0 / 3.0) - 1.0).sqrt();
+                let direction: Vector<f64, 3> = Vector::new(std::array::from_fn(|_| rng.normal()));
+                let norm = direction
+                    .as_slice()
+                    .iter()
+                    .map(|x| x * x)
+                    .sum::<f64>()
+                    .sqrt();
+                Particle::new(1.0 / n as f64, direction * (r / norm), Vector::zero())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_opening_angle() {
+        let mut rng = Rng::new(25);
+        let particles = cluster(&mut rng, 400);
+        let softening = 0.01;
+        let reference = DirectSum::new(softening).accelerations(&particles);
+
+        let mut errors = Vec::new();
+        for theta in [0.0, 0.3, 0.6, 1.0] {
+            let mut tree = BarnesHut::new(theta).with_softening(softening);
+            errors.push(force_error(&tree.accelerations(&particles), &reference));
+            assert!(tree.len() >= particles.len());
+        }
+        assert!(errors[0] < 1e-12, "{errors:?}");
+        assert!(errors[1] < 5e-3 && errors[2] < 2e-2, "{errors:?}");
+        assert!(errors.windows(2).all(|e| e[0] < e[1]), "{errors:?}");
+    }
+
+    #[test]
+    fn test_potential_energy() {
+        let mut rng = Rng::new(26);
+        let particles = cluster(&mut rng, 200);
+        let exact = DirectSum::new(0.01).potential_energy(&particles);
+        let mut tree = BarnesHut::new(0.5).with_softening(0.01);
+        tree.accelerations(&particles);
+        let energy = tree.potential_energy(&particles);
+        assert!(
+            (energy - exact).abs() < 1e-2 * exact.abs(),
+            "{energy} vs {exact}"
+        );
+    }
+
+    #[test]
+    fn test_coincident_particles() {
+        // Stops splitting at the maximum depth instead of recursing forever
+        let x = Vector::new([0.1, 0.2, 0.3]);
+        let particles = vec![
+            Particle::new(1.0, x, Vector::zero()),
+            Particle::new(1.0, x, Vector::zero()),
+            Particle::new(1.0, x + Vector::new([1.0, 0.0, 0.0]), Vector::zero()),
+        ];
+        let accelerations = BarnesHut::new(0.5)
+            .with_softening(0.1)
+            .accelerations(&particles);
+        let reference = DirectSum::new(0.1).accelerations(&particles);
+        assert!(force_error(&accelerations, &reference) < 1e-12);
+    }
+
+    #[test]
+    fn test_cluster_evolution() {
+        // Tree forces are not antisymmetric, the leapfrog still keeps the energy
+        let mut rng = Rng::new(27);
+        let particles = cluster(&mut rng, 100);
+        let mut simulation = NBody::new(BarnesHut::new(0.5).with_softening(0.02), particles);
+        let energy = simulation.total_energy();
+        simulation.run(1e-3, 200);
+        let drift = (simulation.total_energy() - energy) / energy.abs();
+        assert!(drift.abs() < 1e-2, "energy drift {drift}");
+        assert!(simulation.kinetic_energy() > 0.0);
+        assert!((simulation.time() - 0.2).abs() < 1e-12);
+    }
+}
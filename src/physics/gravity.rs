@@ -1,75 +1,931 @@
-use crate::{math::{complex::Complex, core::LinearSpace, field::{Field, Field3D}}, physics::particle::Particle};
-use std::f64::consts::PI;
 use crate::physics::constants::G;
+use crate::{
+    math::{
+        assignment::Assignment,
+        complex::{Complex, ComplexSpace},
+        core::{LinearSpace, Vector},
+        fft::{FftPlan, wavenumbers},
+        field::{Field, inverse_laplacian_symbol},
+        finite_difference::{Accuracy, FiniteDifference},
+        grid::{Domain, Grid},
+        interpolation::{Interpolation, Interpolator},
+    },
+    physics::particle::Particle,
+};
+use std::f64::consts::PI;
+
+/// Boundary condition of the Poisson equation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoissonBoundary {
+    /// Periodic box, the mean density does not gravitate
+    Periodic,
+    /// Empty space around the box, by Hockney-Eastwood zero padding
+    Isolated,
+}
+
+/// Real-space Green's functions of an isolated box, as spectra on the doubled grid
+///
+/// Offsets past n wrap to negative ones, so a density in the first n samples of
+/// each axis convolves without images.
+struct IsolatedGreen {
+    plans: Vec<FftPlan>,
+    potential: Grid<Complex>,
+    /// $$ -\nabla G $$ per axis, differenced to 4th order before padding so cells
+    /// on the edge of the box need no ghost potential
+    acceleration: Vec<Grid<Complex>>,
+}
+
+impl IsolatedGreen {
+    fn new(shape: &[usize; 3], domain: &Domain) -> Self {
+        let h: Vec<f64> = domain
+            .lengths()
+            .iter()
+            .zip(shape)
+            .map(|(l, &n)| l / n as f64)
+            .collect();
+        let cell_volume = h.iter().product::<f64>();
+        // A cell on itself: potential at the center of a uniform cube of the cell's volume
+        let center =
+            -G * cell_volume * (3.0 * (2.0 + 3f64.sqrt()).ln() - PI / 2.0) / cell_volume.cbrt();
+        // Offsets -(n + 1)..=n + 1, enough for the stencil at every offset below n
+        let m = shape.map(|n| 2 * n + 3);
+        let kernel = Grid::from_fn(&m, |i| {
+            let r = (0..3)
+                .map(|a| ((i[a] as f64 - (shape[a] + 1) as f64) * h[a]).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            if r == 0.0 {
+                center
+            } else {
+                -G * cell_volume / r
+            }
+        })
+        .with_domain(Domain::new(
+            &[0.0; 3],
+            &[m[0] as f64 * h[0], m[1] as f64 * h[1], m[2] as f64 * h[2]],
+        ));
+        let doubled = shape.map(|n| 2 * n);
+        let plans = Grid::<Complex>::new(&doubled).plans();
+        let padded = |kernel: &Grid<f64>| {
+            let mut padded = Grid::from_fn(&doubled, |j| {
+                if j.iter().zip(shape).any(|(j, n)| j == n) {
+                    return Complex::zero();
+                }
+                let i: Vec<usize> = j
+                    .iter()
+                    .zip(shape)
+                    .map(|(&j, &n)| if j < n { j + n + 1 } else { j + 1 - n })
+                    .collect();
+                Complex::new(kernel.as_slice()[kernel.offset(&i)], 0.0)
+            });
+            padded.fft_with_plans(&plans);
+            padded
+        };
+        let acceleration = FiniteDifference::new(Accuracy::Fourth)
+            .gradient(&kernel)
+            .into_iter()
+            .map(|g| padded(&-g))
+            .collect();
+        Self {
+            potential: padded(&kernel),
+            acceleration,
+            plans,
+        }
+    }
+    /// Spectrum of the density zero-padded to twice its size
+    fn spectrum(&self, density: &Grid<Complex>) -> Grid<Complex> {
+        let shape: Vec<usize> = density.shape().iter().map(|n| 2 * n).collect();
+        let mut padded = Grid::from_fn(&shape, |i| {
+            if i.iter().zip(density.shape()).all(|(i, n)| i < n) {
+                density.as_slice()[density.offset(i)]
+            } else {
+                Complex::zero()
+            }
+        });
+        padded.fft_with_plans(&self.plans);
+        padded
+    }
+    /// $$ \sum_j K_{i-j} \rho_j $$ on the first half of the samples per axis
+    fn convolve(&self, spectrum: &Grid<Complex>, kernel: &Grid<Complex>) -> Grid<f64> {
+        let product: Vec<Complex> = spectrum
+            .as_slice()
+            .iter()
+            .zip(kernel.as_slice())
+            .map(|(rho, k)| *rho * *k)
+            .collect();
+        let mut product = Grid::from_vec(spectrum.shape(), product);
+        product.ifft_with_plans(&self.plans);
+        let shape: Vec<usize> = spectrum.shape().iter().map(|m| m / 2).collect();
+        Grid::from_fn(&shape, |i| product.as_slice()[product.offset(i)].re())
+    }
+}
 
-pub struct GravitationalPotential<const N: usize> {
-    x_min: f64,
-    x_max: f64,
-    y_min: f64,
-    y_max: f64,
-    z_min: f64,
-    z_max: f64,
-    field : Field3D<N>,
+/// Particle-mesh gravity on an `nx` x `ny` x `nz` grid over a `Domain`
+///
+/// The density and potential live on the heap, so large meshes fit any stack.
+pub struct GravitationalPotential {
+    domain: Domain,
+    /// Density before and potential after the Poisson solve
+    field: Grid<Complex>,
+    plans: Vec<FftPlan>,
+    assignment: Assignment,
+    /// Mesh potential from the last `step` with its interpolant
+    potential: Interpolator,
+    /// Mesh acceleration $$ -\nabla \Phi $$ per axis from the last `step`
+    acceleration: Vec<Grid<f64>>,
+    /// Potential of a unit mass on the center sample, for the mesh self-energy
+    green: Grid<f64>,
+    isolated: Option<IsolatedGreen>,
 }
 
-impl<const N: usize> GravitationalPotential<N> {
-    pub fn new(
-        x_min: f64,
-        x_max: f64,
-        y_min: f64,
-        y_max: f64,
-        z_min: f64,
-        z_max: f64,
-    ) -> Self {
-        Self { 
-            x_min,
-            x_max,
-            y_min,
-            y_max,
-            z_min,
-            z_max,
-            field: Field3D::zero(),
+impl GravitationalPotential {
+    pub fn new(shape: &[usize; 3], domain: Domain) -> Self {
+        if domain.ndim() != 3 {
+            panic!("Gravity needs a 3D domain, got {} axes", domain.ndim());
+        }
+        let field = Grid::new(shape).with_domain(domain.clone());
+        let mut potential = Self {
+            plans: field.plans(),
+            potential: Interpolator::new(
+                Grid::new(shape).with_domain(domain.clone()),
+                Interpolation::Cubic,
+            ),
+            acceleration: vec![Grid::new(shape).with_domain(domain.clone()); 3],
+            green: Grid::new(shape),
+            domain,
+            field,
+            assignment: Assignment::CIC,
+            isolated: None,
+        };
+        potential.update_green();
+        potential
+    }
+    /// Isolated boundaries need particles to stay a cloud width inside the box,
+    /// positions whose cloud would wrap around panic
+    pub fn with_boundary(mut self, boundary: PoissonBoundary) -> Self {
+        self.isolated = match boundary {
+            PoissonBoundary::Periodic => None,
+            PoissonBoundary::Isolated => {
+                let shape = [self.shape()[0], self.shape()[1], self.shape()[2]];
+                Some(IsolatedGreen::new(&shape, &self.domain))
+            }
+        };
+        self.update_green();
+        let potential = self.potential.grid().clone();
+        self.set_potential(potential);
+        self
+    }
+    pub fn boundary(&self) -> PoissonBoundary {
+        match self.isolated {
+            Some(_) => PoissonBoundary::Isolated,
+            None => PoissonBoundary::Periodic,
         }
     }
+    pub fn with_assignment(mut self, assignment: Assignment) -> Self {
+        self.assignment = assignment;
+        self
+    }
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.potential = Interpolator::new(self.potential.grid().clone(), interpolation)
+            .with_periodic(self.isolated.is_none());
+        self
+    }
+    /// Samples per axis
+    pub fn shape(&self) -> &[usize] {
+        self.field.shape()
+    }
     fn init(&mut self) {
-        for x in 0..N {
-            for y in 0..N {
-                for z in 0..N {
-                    self.field[x][y][z] = Complex::zero();
+        self.field.as_mut_slice().fill(Complex::zero());
+    }
+    fn update_green(&mut self) {
+        self.init();
+        let center: Vec<usize> = self.shape().iter().map(|n| n / 2).collect();
+        let offset = self.field.offset(&center);
+        self.field.as_mut_slice()[offset] = Complex::new(1.0 / self.cell_volume(), 0.0);
+        self.solve_poisson_eq();
+        self.green = self.field.map(|phi| phi.re());
+        self.init();
+    }
+    fn cell_volume(&self) -> f64 {
+        self.field.cell_volume()
+    }
+    /// Panics if an isolated mesh would wrap the cloud at `position` around
+    fn check_position(&self, position: Vector<f64, 3>) {
+        if self.isolated.is_some()
+            && !self
+                .assignment
+                .fits(&self.domain, self.shape(), position.as_slice())
+        {
+            panic!(
+                "Position {position} is within a cloud width of the isolated box {:?}",
+                self.domain
+            );
+        }
+    }
+    /// Adds the mass density of a particle spread by the assignment scheme
+    fn set_mass(&mut self, p: Particle<Vector<f64, 3>>) {
+        self.check_position(p.position());
+        let cell_volume = self.cell_volume();
+        for (index, weight) in
+            self.assignment
+                .cloud(&self.domain, self.field.shape(), p.position().as_slice())
+        {
+            let offset = self.field.offset(&index);
+            let rho = &mut self.field.as_mut_slice()[offset];
+            *rho = *rho + Complex::new(p.mass() * weight / cell_volume, 0.0);
+        }
+    }
+    fn solve_poisson_eq(&mut self) {
+        // Poisson equation: laplacian(phi) = 4 pi G rho
+        if self.isolated.is_some() {
+            self.solve_isolated();
+            return;
+        }
+        let wavenumbers: Vec<Vec<f64>> = self
+            .domain
+            .lengths()
+            .iter()
+            .zip(self.field.shape())
+            .map(|(&l, &n)| {
+                let h = l / n as f64;
+                wavenumbers(n, l)
+                    .iter()
+                    .map(|k| 2.0 * (k * h / 2.0).sin() / h)
+                    .collect()
+            })
+            .collect();
+        self.field.fft_with_plans(&self.plans);
+        self.field.multiply_spectrum(&wavenumbers, &|k| {
+            inverse_laplacian_symbol(k) * (4.0 * PI * G)
+        });
+        self.field.ifft_with_plans(&self.plans);
+    }
+    /// Convolves with $$ -G / r $$ and returns the padded density spectrum
+    fn solve_isolated(&mut self) -> Grid<Complex> {
+        let green = self
+            .isolated
+            .as_ref()
+            .expect("Isolated boundaries have a Green's function");
+        let spectrum = green.spectrum(&self.field);
+        self.field = green
+            .convolve(&spectrum, &green.potential)
+            .map(|phi| Complex::new(phi, 0.0))
+            .with_domain(self.domain.clone());
+        spectrum
+    }
+    /// Deposits the particles, solves for the potential and differentiates it to 4th order
+    pub fn step(&mut self, ps: &[Particle<Vector<f64, 3>>]) {
+        self.init();
+        for p in ps {
+            self.set_mass(*p);
+        }
+        if self.isolated.is_some() {
+            let spectrum = self.solve_isolated();
+            let green = self.isolated.as_ref().unwrap();
+            self.acceleration = green
+                .acceleration
+                .iter()
+                .map(|kernel| {
+                    green
+                        .convolve(&spectrum, kernel)
+                        .with_domain(self.domain.clone())
+                })
+                .collect();
+            self.set_potential(self.field.map(|phi| phi.re()));
+            return;
+        }
+        self.solve_poisson_eq();
+        self.set_potential(self.field.map(|phi| phi.re()));
+        self.acceleration = FiniteDifference::new(Accuracy::Fourth)
+            .gradient(self.potential.grid())
+            .into_iter()
+            .map(|g| -g)
+            .collect();
+    }
+    /// Stores the mesh potential, building the interpolant once per `step`
+    ///
+    /// An isolated potential is not periodic, so its interpolant must not wrap either.
+    fn set_potential(&mut self, potential: Grid<f64>) {
+        self.potential = Interpolator::new(potential, self.potential.method())
+            .with_periodic(self.isolated.is_none());
+    }
+    /// Potential gathered with the assignment weights, for energies consistent with the forces
+    pub fn potential_at(&self, position: Vector<f64, 3>) -> f64 {
+        self.check_position(position);
+        self.assignment
+            .interpolate(self.potential.grid(), position.as_slice())
+    }
+    /// Potential a unit mass at `position` exerts on itself through the mesh
+    ///
+    /// $$ \sum_{ij} W_i W_j g_{i-j} $$ varies with the position inside a cell, so energies
+    /// subtract it. On an isolated mesh the cloud fits, so the offsets never wrap.
+    pub fn self_potential(&self, position: Vector<f64, 3>) -> f64 {
+        self.check_position(position);
+        let shape = self.shape();
+        let cloud = self
+            .assignment
+            .cloud(&self.domain, shape, position.as_slice());
+        let mut phi = 0.0;
+        for (i, w_i) in &cloud {
+            for (j, w_j) in &cloud {
+                let r: Vec<usize> = (0..3)
+                    .map(|a| (shape[a] / 2 + i[a] + shape[a] - j[a]) % shape[a])
+                    .collect();
+                phi += w_i * w_j * self.green.as_slice()[self.green.offset(&r)];
+            }
+        }
+        phi
+    }
+    /// Mesh acceleration gathered with the assignment weights, free of self-forces
+    pub fn acceleration_at(&self, position: Vector<f64, 3>) -> Vector<f64, 3> {
+        self.check_position(position);
+        let a = self
+            .assignment
+            .interpolate_vector(&self.acceleration, position.as_slice());
+        Vector::new([a[0], a[1], a[2]])
+    }
+    /// Gravitational field $$ \mathbf{g} = -\nabla \Phi $$ interpolated from the potential of the last `step`
+    pub fn get_gravitational_field(&self, x: f64, y: f64, z: f64) -> Vector<f64, 3> {
+        self.check_position(Vector::new([x, y, z]));
+        let gradient = self.potential.gradient(&[x, y, z]);
+        Vector::new([-gradient[0], -gradient[1], -gradient[2]])
+    }
+}
+
+impl GravitySolver for GravitationalPotential {
+    fn accelerations(&mut self, particles: &[Particle<Vector<f64, 3>>]) -> Vec<Vector<f64, 3>> {
+        self.step(particles);
+        particles
+            .iter()
+            .map(|p| self.acceleration_at(p.position()))
+            .collect()
+    }
+    /// $$ \frac{1}{2} \sum_i m_i \Phi(x_i) $$ without the mesh self-energy
+    ///
+    /// The periodic potential has zero mean, so the zero of energy differs from
+    /// that of an isolated system.
+    fn potential_energy(&self, particles: &[Particle<Vector<f64, 3>>]) -> f64 {
+        0.5 * particles
+            .iter()
+            .map(|p| {
+                let position = p.position();
+                p.mass() * (self.potential_at(position) - p.mass() * self.self_potential(position))
+            })
+            .sum::<f64>()
+    }
+}
+
+/// Force law of an N-body simulation
+pub trait GravitySolver {
+    /// Acceleration of every particle by all the others
+    fn accelerations(&mut self, particles: &[Particle<Vector<f64, 3>>]) -> Vec<Vector<f64, 3>>;
+    /// Potential energy of the particles last passed to `accelerations`
+    fn potential_energy(&self, particles: &[Particle<Vector<f64, 3>>]) -> f64;
+}
+
+/// Pairwise $$ O(N^2) $$ summation with Plummer softening
+///
+/// $$ \mathbf{a}_i = -G \sum_{j \neq i} m_j \frac{\mathbf{x}_i - \mathbf{x}_j}{(r_{ij}^2 + \epsilon^2)^{3/2}} $$
+pub struct DirectSum {
+    softening: f64,
+}
+
+impl DirectSum {
+    pub fn new(softening: f64) -> Self {
+        Self { softening }
+    }
+    pub fn softening(&self) -> f64 {
+        self.softening
+    }
+}
+
+impl GravitySolver for DirectSum {
+    fn accelerations(&mut self, particles: &[Particle<Vector<f64, 3>>]) -> Vec<Vector<f64, 3>> {
+        let mut accelerations = vec![Vector::zero(); particles.len()];
+        for i in 0..particles.len() {
+            for j in i + 1..particles.len() {
+                let r = particles[j].position() - particles[i].position();
+                let r2 = r.as_slice().iter().map(|r| r * r).sum::<f64>()
+                    + self.softening * self.softening;
+                let pull = r * (G / (r2 * r2.sqrt()));
+                accelerations[i] = accelerations[i] + pull * particles[j].mass();
+                accelerations[j] = accelerations[j] - pull * particles[i].mass();
+            }
+        }
+        accelerations
+    }
+    /// $$ -G \sum_{i<j} \frac{m_i m_j}{\sqrt{r_{ij}^2 + \epsilon^2}} $$
+    fn potential_energy(&self, particles: &[Particle<Vector<f64, 3>>]) -> f64 {
+        let mut energy = 0.0;
+        for i in 0..particles.len() {
+            for j in i + 1..particles.len() {
+                let r = particles[j].position() - particles[i].position();
+                let r2 = r.as_slice().iter().map(|r| r * r).sum::<f64>()
+                    + self.softening * self.softening;
+                energy -= G * particles[i].mass() * particles[j].mass() / r2.sqrt();
+            }
+        }
+        energy
+    }
+}
+
+/// RMS force error relative to a reference, $$ \sqrt{\sum_i |a_i - a_i^{ref}|^2 / \sum_i |a_i^{ref}|^2} $$
+pub fn force_error(accelerations: &[Vector<f64, 3>], reference: &[Vector<f64, 3>]) -> f64 {
+    let norm2 = |a: Vector<f64, 3>| a.as_slice().iter().map(|a| a * a).sum::<f64>();
+    let error: f64 = accelerations
+        .iter()
+        .zip(reference)
+        .map(|(a, b)| norm2(*a - *b))
+        .sum();
+    (error / reference.iter().map(|b| norm2(*b)).sum::<f64>()).sqrt()
+}
+
+/// N-body simulation driven by any `GravitySolver`
+///
+/// Kick-drift-kick leapfrog: symplectic and time-reversible, 2nd-order. Positions
+/// are not wrapped, a periodic mesh sees them periodically.
+pub struct NBody<S: GravitySolver> {
+    solver: S,
+    particles: Vec<Particle<Vector<f64, 3>>>,
+    accelerations: Vec<Vector<f64, 3>>,
+    time: f64,
+}
+
+/// Particle-mesh N-body simulation
+pub type ParticleMesh = NBody<GravitationalPotential>;
+
+impl<S: GravitySolver> NBody<S> {
+    pub fn new(solver: S, particles: Vec<Particle<Vector<f64, 3>>>) -> Self {
+        let mut simulation = Self {
+            solver,
+            particles,
+            accelerations: Vec::new(),
+            time: 0.0,
+        };
+        simulation.update_forces();
+        simulation
+    }
+    pub fn particles(&self) -> &[Particle<Vector<f64, 3>>] {
+        &self.particles
+    }
+    pub fn solver(&self) -> &S {
+        &self.solver
+    }
+    pub fn accelerations(&self) -> &[Vector<f64, 3>] {
+        &self.accelerations
+    }
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+    fn update_forces(&mut self) {
+        self.accelerations = self.solver.accelerations(&self.particles);
+    }
+    fn kick(&mut self, dt: f64) {
+        for (p, a) in self.particles.iter_mut().zip(&self.accelerations) {
+            p.set_velocity(p.velocity() + *a * dt);
+        }
+    }
+    pub fn step(&mut self, dt: f64) {
+        self.kick(dt / 2.0);
+        for p in self.particles.iter_mut() {
+            p.set_position(p.position() + p.velocity() * dt);
+        }
+        self.update_forces();
+        self.kick(dt / 2.0);
+        self.time += dt;
+    }
+    pub fn run(&mut self, dt: f64, steps: usize) {
+        for _ in 0..steps {
+            self.step(dt);
+        }
+    }
+    /// $$ \sum_i \frac{1}{2} m_i v_i^2 $$
+    pub fn kinetic_energy(&self) -> f64 {
+        self.particles
+            .iter()
+            .map(|p| 0.5 * p.mass() * p.velocity().as_slice().iter().map(|v| v * v).sum::<f64>())
+            .sum()
+    }
+    pub fn potential_energy(&self) -> f64 {
+        self.solver.potential_energy(&self.particles)
+    }
+    pub fn total_energy(&self) -> f64 {
+        self.kinetic_energy() + self.potential_energy()
+    }
+    /// $$ \sum_i m_i v_i $$, conserved when the forces obey Newton's third law
+    pub fn momentum(&self) -> Vector<f64, 3> {
+        self.particles
+            .iter()
+            .fold(Vector::zero(), |sum, p| sum + p.momentum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::random::Rng;
+    use crate::physics::barnes_hut::BarnesHut;
+
+    use super::*;
+
+    #[test]
+    fn test_gravitational_field_from_potential() {
+        // Phi = sin(k x) on [0, L)^3 gives g = (-k cos(k x), 0, 0)
+        const N: usize = 16;
+        let length = 4.0;
+        let k = 2.0 * PI / length;
+        for interpolation in [Interpolation::Cubic, Interpolation::Fourier] {
+            let mut potential = GravitationalPotential::new(&[N; 3], Domain::cube(3, length))
+                .with_interpolation(interpolation);
+            potential.set_potential(
+                Grid::from_fn(&[N; 3], |i| (k * length * i[0] as f64 / N as f64).sin())
+                    .with_domain(Domain::cube(3, length)),
+            );
+            let g = potential.get_gravitational_field(1.3, 0.2, 2.9);
+            let tolerance = if interpolation == Interpolation::Fourier {
+                1e-12
+            } else {
+                1e-2
+            };
+            assert!(
+                (g[0] + k * (k * 1.3).cos()).abs() < tolerance,
+                "{interpolation:?}: {g:?}"
+            );
+            assert!(g[1].abs() < 1e-12 && g[2].abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_set_mass_conserves_mass() {
+        const N: usize = 8;
+        for assignment in [Assignment::NGP, Assignment::CIC, Assignment::TSC] {
+            let mut potential = GravitationalPotential::new(
+                &[N; 3],
+                Domain::new(&[-1.0, 0.0, 0.0], &[1.0, 4.0, 2.0]),
+            )
+            .with_assignment(assignment);
+            potential.init();
+            potential.set_mass(Particle::new(
+                2.0,
+                Vector::new([0.93, -0.2, 1.37]),
+                Vector::zero(),
+            ));
+            potential.set_mass(Particle::new(
+                0.5,
+                Vector::new([-1.1, 3.95, 5.0]),
+                Vector::zero(),
+            ));
+            let cell_volume = 16.0 / (N * N * N) as f64;
+            let mut mass = 0.0;
+            for x in 0..N {
+                for y in 0..N {
+                    for z in 0..N {
+                        mass += potential.field[[x, y, z]].re() * cell_volume;
+                    }
                 }
             }
+            assert!((mass - 2.5).abs() < 1e-12, "{assignment:?}: {mass}");
         }
     }
-    fn set_mass(&mut self, p: Particle) {
-        // Get mass density distribution
-        // Maybe object need mass density and radius (mass and volume)
-        todo!()
+
+    #[test]
+    fn test_large_mesh() {
+        // 64^3 complex samples are 4 MiB, more than a test thread's stack
+        let mut potential = GravitationalPotential::new(&[64; 3], Domain::cube(3, 1.0));
+        assert_eq!(potential.shape(), &[64; 3]);
+        let particles = [
+            Particle::new(1.0, Vector::new([0.4, 0.5, 0.5]), Vector::zero()),
+            Particle::new(1.0, Vector::new([0.6, 0.5, 0.5]), Vector::zero()),
+        ];
+        let a = potential.accelerations(&particles);
+        assert!(
+            a[0][0] > 0.0 && (a[0][0] + a[1][0]).abs() < 1e-12 * a[0][0],
+            "{a:?}"
+        );
     }
-    fn solve_poisson_eq(&mut self) {
-        self.field.fft();
+
+    #[test]
+    fn test_non_cubic_box() {
+        // A point mass on a node of an isolated slab sees exactly -G m / r at the other nodes
+        let domain = Domain::new(&[-2.0, -1.0, -0.5], &[2.0, 1.0, 0.5]);
+        let mut potential = GravitationalPotential::new(&[32, 16, 8], domain)
+            .with_assignment(Assignment::NGP)
+            .with_boundary(PoissonBoundary::Isolated);
+        assert_eq!(potential.shape(), &[32, 16, 8]);
+        potential.step(&[Particle::new(2.0, Vector::zero(), Vector::zero())]);
+        for r in [
+            Vector::new([1.875, 0.0, 0.0]),
+            Vector::new([-1.0, 0.875, 0.0]),
+            Vector::new([0.0, 0.0, -0.5]),
+            Vector::new([1.5, -1.0, 0.375]),
+        ] {
+            let exact = -G * 2.0 / r.as_slice().iter().map(|x| x * x).sum::<f64>().sqrt();
+            let phi = potential.potential_at(r);
+            assert!(
+                (phi - exact).abs() < 1e-12 * exact.abs(),
+                "{r}: {phi} vs {exact}"
+            );
+        }
+
+        // Periodic modes along axes of different lengths and spacings
+        let lengths = [4.0, 1.0, 2.0];
+        let shape = [16, 8, 4];
+        let mut potential = GravitationalPotential::new(&shape, Domain::new(&[0.0; 3], &lengths));
+        let k: Vec<f64> = lengths.iter().map(|l| 2.0 * PI / l).collect();
+        let h: Vec<f64> = lengths
+            .iter()
+            .zip(shape)
+            .map(|(l, n)| l / n as f64)
+            .collect();
+        for x in 0..shape[0] {
+            for y in 0..shape[1] {
+                for z in 0..shape[2] {
+                    let rho = (k[0] * x as f64 * h[0]).cos()
+                        + (k[1] * y as f64 * h[1]).cos()
+                        + (k[2] * z as f64 * h[2]).sin();
+                    potential.field[[x, y, z]] = Complex::new(rho, 0.0);
+                }
+            }
+        }
+        potential.solve_poisson_eq();
+        // Discrete Laplacian eigenvalues -(2 sin(k h / 2) / h)^2
+        let scale: Vec<f64> = (0..3)
+            .map(|a| -4.0 * PI * G / (2.0 * (k[a] * h[a] / 2.0).sin() / h[a]).powi(2))
+            .collect();
+        for x in 0..shape[0] {
+            for y in 0..shape[1] {
+                for z in 0..shape[2] {
+                    let phi = scale[0] * (k[0] * x as f64 * h[0]).cos()
+                        + scale[1] * (k[1] * y as f64 * h[1]).cos()
+                        + scale[2] * (k[2] * z as f64 * h[2]).sin();
+                    assert!(
+                        (potential.field[[x, y, z]].re() - phi).abs() < 1e-12,
+                        "{:?} vs {phi}",
+                        potential.field[[x, y, z]]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_isolated_field_near_face() {
+        // Off-center, a wrapping interpolant would mix in the potential at the far face
+        let domain = Domain::new(&[-2.0, -1.0, -0.5], &[2.0, 1.0, 0.5]);
+        let mut potential = GravitationalPotential::new(&[32, 16, 8], domain)
+            .with_assignment(Assignment::NGP)
+            .with_boundary(PoissonBoundary::Isolated);
+        potential.step(&[Particle::new(
+            2.0,
+            Vector::new([0.5, 0.0, 0.0]),
+            Vector::zero(),
+        )]);
+        // Wrapping gives -1.78 here
+        let field = potential.get_gravitational_field(1.85, 0.0, 0.0);
+        let newton = -G * 2.0 / (1.35 * 1.35);
+        assert!(
+            (field[0] - newton).abs() < 0.05 * newton.abs(),
+            "{field} vs {newton}"
+        );
+        assert!(field[1].abs() < 1e-12 && field[2].abs() < 1e-12, "{field}");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_isolated_field_outside() {
+        // Inside the samples, but the TSC cloud would wrap
+        let mut potential =
+            GravitationalPotential::new(&[8; 3], Domain::new(&[-1.0; 3], &[1.0; 3]))
+                .with_assignment(Assignment::TSC)
+                .with_boundary(PoissonBoundary::Isolated);
+        potential.step(&[Particle::new(1.0, Vector::zero(), Vector::zero())]);
+        potential.get_gravitational_field(0.7, 0.0, 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_isolated_edge() {
+        // TSC needs a sample on either side, the periodic mesh wraps instead
+        let particles = [Particle::new(
+            1.0,
+            Vector::new([0.0, 0.0, -0.95]),
+            Vector::zero(),
+        )];
+        let domain = Domain::new(&[-1.0; 3], &[1.0; 3]);
+        let mut potential =
+            GravitationalPotential::new(&[8; 3], domain).with_assignment(Assignment::TSC);
+        potential.step(&particles);
+        potential
+            .with_boundary(PoissonBoundary::Isolated)
+            .step(&particles);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_isolated_position_outside() {
+        let potential = GravitationalPotential::new(&[8; 3], Domain::new(&[-1.0; 3], &[1.0; 3]))
+            .with_boundary(PoissonBoundary::Isolated);
+        potential.acceleration_at(Vector::new([1.5, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn test_two_body_orbit() {
+        // Equal masses 4 cells apart, circular in the mesh force a: v^2 = a d / 2
+        const N: usize = 16;
+        let (m, d, center) = (1.0, 0.25, 0.5);
+        let body = |side: f64, v: f64| {
+            Particle::new(
+                m,
+                Vector::new([center + side * d / 2.0, center, center]),
+                Vector::new([0.0, side * v, 0.0]),
+            )
+        };
+        let potential = GravitationalPotential::new(&[N; 3], Domain::cube(3, 1.0))
+            .with_assignment(Assignment::TSC);
+        let simulation = ParticleMesh::new(potential, vec![body(-1.0, 0.0), body(1.0, 0.0)]);
+
+        // Softened by the mesh and pulled apart by the periodic images
+        let a = simulation.accelerations[0][0];
+        let newton = G * m / (d * d);
+        assert!(a > 0.5 * newton && a < newton, "{a} vs {newton}");
+        let v = (a * d / 2.0).sqrt();
+        let mut simulation =
+            ParticleMesh::new(simulation.solver, vec![body(-1.0, v), body(1.0, v)]);
+        // The periodic zero of potential energy is arbitrary, so compare with the kinetic energy
+        let (energy, kinetic) = (simulation.total_energy(), simulation.kinetic_energy());
+
+        let period = PI * d / v;
+        let steps = 100;
+        let separation = |s: &ParticleMesh| {
+            let r = s.particles()[1].position() - s.particles()[0].position();
+            r.as_slice().iter().map(|r| r * r).sum::<f64>().sqrt()
+        };
+        let (mut closest, mut farthest) = (d, d);
+        for _ in 0..2 * steps {
+            simulation.step(period / steps as f64);
+            closest = closest.min(separation(&simulation));
+            farthest = farthest.max(separation(&simulation));
+            for p in simulation.momentum().as_slice() {
+                assert!(p.abs() < 1e-12, "momentum {p}");
+            }
+        }
+        assert!(
+            closest > 0.9 * d && farthest < 1.1 * d,
+            "separation in [{closest}, {farthest}]"
+        );
+        let drift = (simulation.total_energy() - energy) / kinetic;
+        assert!(drift.abs() < 1e-2, "energy drift {drift}");
+    }
+
+    #[test]
+    fn test_isolated_uniform_sphere() {
+        // Phi = -G M (3 R^2 - r^2) / (2 R^3) inside and -G M / r outside, no images
+        const N: usize = 16;
+        let h = 2.0 / N as f64;
+        let node = |i: usize| -1.0 + i as f64 * h;
+        // Samples within 4 cells of the center, as a sphere of the same volume
+        let mut particles = Vec::new();
         for x in 0..N {
-            let kx = if x <= N/2 { x as f64 } else { (x as f64) - (N as f64)/2.0 };
             for y in 0..N {
-                let ky = if y <= N/2 { y as f64 } else { (y as f64) - (N as f64)/2.0 };
                 for z in 0..N {
-                    let kz = if z <= N/2 { z as f64 } else { (z as f64) - (N as f64)/2.0 };
-                    let k_sq = 1.0/((kx * kx + ky * ky + kz * kz) * PI);
-                    self.field[x][y][z] = self.field[x][y][z] * G * -k_sq;
+                    if [x, y, z]
+                        .iter()
+                        .map(|&i| (i as isize - 8).pow(2))
+                        .sum::<isize>()
+                        <= 16
+                    {
+                        particles.push(Particle::new(
+                            h * h * h,
+                            Vector::new([node(x), node(y), node(z)]),
+                            Vector::zero(),
+                        ));
+                    }
                 }
             }
         }
-        self.field.ifft();
+        let mass = particles.len() as f64 * h * h * h;
+        let radius = (3.0 * mass / (4.0 * PI)).cbrt();
+        let analytic = |r: f64| {
+            if r < radius {
+                -G * mass * (3.0 * radius * radius - r * r) / (2.0 * radius.powi(3))
+            } else {
+                -G * mass / r
+            }
+        };
+
+        let mut errors = Vec::new();
+        for boundary in [PoissonBoundary::Isolated, PoissonBoundary::Periodic] {
+            let mut potential =
+                GravitationalPotential::new(&[N; 3], Domain::new(&[-1.0; 3], &[1.0; 3]))
+                    .with_assignment(Assignment::NGP)
+                    .with_boundary(boundary);
+            assert_eq!(potential.boundary(), boundary);
+            potential.step(&particles);
+            let mut error: f64 = 0.0;
+            for i in [8, 10, 13, 15] {
+                let phi = potential.potential_at(Vector::new([node(i), 0.0, 0.0]));
+                error = error.max((phi - analytic(node(i))).abs() / analytic(0.0).abs());
+            }
+            errors.push(error);
+        }
+        assert!(errors[0] < 1e-2, "isolated error {}", errors[0]);
+        // The images and the neutralizing background of a periodic box
+        assert!(errors[1] > 0.3, "periodic error {}", errors[1]);
+
+        // Away from the staircase surface the field is radial and Newtonian
+        let mut potential =
+            GravitationalPotential::new(&[N; 3], Domain::new(&[-1.0; 3], &[1.0; 3]))
+                .with_assignment(Assignment::NGP)
+                .with_boundary(PoissonBoundary::Isolated);
+        potential.step(&particles);
+        for i in [6, 15] {
+            let r = node(i);
+            let g = potential.acceleration_at(Vector::new([0.0, r, 0.0]));
+            let newton = if r.abs() < radius {
+                -G * mass * r / radius.powi(3)
+            } else {
+                -G * mass / (r * r)
+            };
+            assert!(
+                (g[1] - newton).abs() < 3e-2 * newton.abs(),
+                "{r}: {g:?} vs {newton}"
+            );
+            assert!(g[0].abs() < 1e-12 && g[2].abs() < 1e-12, "{g:?}");
+        }
+        // The interpolant stored by `step` differentiates the same potential
+        let r = node(6);
+        let g = potential.get_gravitational_field(0.0, r, 0.0);
+        let newton = -G * mass * r / radius.powi(3);
+        assert!(
+            (g[1] - newton).abs() < 3e-2 * newton.abs(),
+            "{g:?} vs {newton}"
+        );
     }
-    pub fn step(&mut self, ps: Vec<Particle>) {
-        self.init();
-        for p in ps {
-            self.set_mass(p);
+
+    #[test]
+    fn test_direct_sum_kepler_orbit() {
+        // m1 = 3, m2 = 1 on a circle of separation d: omega^2 = G M / d^3
+        let (d, total) = (1.0, 4.0);
+        let v = (G * total / d).sqrt();
+        let particles = vec![
+            Particle::new(
+                3.0,
+                Vector::new([-0.25 * d, 0.0, 0.0]),
+                Vector::new([0.0, -0.25 * v, 0.0]),
+            ),
+            Particle::new(
+                1.0,
+                Vector::new([0.75 * d, 0.0, 0.0]),
+                Vector::new([0.0, 0.75 * v, 0.0]),
+            ),
+        ];
+        let mut simulation = NBody::new(DirectSum::new(0.0), particles);
+        let energy = simulation.total_energy();
+        assert!((energy - (0.5 * 3.0 / 4.0 * v * v - G * 3.0 / d)).abs() < 1e-12);
+
+        let period = 2.0 * PI * (d.powi(3) / (G * total)).sqrt();
+        simulation.run(period / 1000.0, 1000);
+        let p = simulation.particles()[1].position();
+        assert!(
+            (p - Vector::new([0.75 * d, 0.0, 0.0]))
+                .as_slice()
+                .iter()
+                .all(|x| x.abs() < 1e-3),
+            "{p}"
+        );
+        assert!(((simulation.total_energy() - energy) / energy).abs() < 1e-5);
+        for p in simulation.momentum().as_slice() {
+            assert!(p.abs() < 1e-12, "momentum {p}");
         }
-        // ps.iter().map(|p| self.set_mass(*p));
-        self.solve_poisson_eq();
     }
-    pub fn get_gravitational_field(&self, x: f64, y: f64, z: f64) {
-        // 3D Interpolation and calculate gradient
-        todo!()
+
+    #[test]
+    fn test_force_errors_between_solvers() {
+        // A clump inside an isolated mesh, against a direct sum softened like a TSC mesh
+        const N: usize = 16;
+        let mut rng = Rng::new(28);
+        let particles: Vec<Particle<Vector<f64, 3>>> = (0..100)
+            .map(|_| {
+                let x: Vector<f64, 3> = Vector::new(std::array::from_fn(|_| {
+                    (0.2 * rng.normal()).clamp(-0.6, 0.6)
+                }));
+                Particle::new(0.01, x, Vector::zero())
+            })
+            .collect();
+        let softening = 0.1;
+        let reference = DirectSum::new(softening).accelerations(&particles);
+        let tree = BarnesHut::new(0.5)
+            .with_softening(softening)
+            .accelerations(&particles);
+        let mesh = GravitationalPotential::new(&[N; 3], Domain::new(&[-1.0; 3], &[1.0; 3]))
+            .with_assignment(Assignment::TSC)
+            .with_boundary(PoissonBoundary::Isolated)
+            .accelerations(&particles);
+        let (tree, mesh) = (
+            force_error(&tree, &reference),
+            force_error(&mesh, &reference),
+        );
+        assert!(tree < 1e-2, "tree error {tree}");
+        // Close pairs within a cell or two are where the mesh departs from Plummer
+        assert!(mesh < 0.15 && mesh > tree, "mesh error {mesh}");
     }
 }
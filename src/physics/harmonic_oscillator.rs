@@ -1,5 +1,5 @@
 use crate::math::core::LinearSpace;
-use crate::math::integrate::{EulerMethod, RK4Method, System};
+use crate::math::integrate::System;
 
 pub struct SimpleHarmonicOscillator {
     pub omega_square: f64,
@@ -43,7 +43,7 @@ impl System for DrivenHarmonicOscillator {
 
 #[cfg(test)]
 mod tests {
-    use crate::math::integrate::Solver;
+    use crate::math::integrate::{RK4Method, Solver};
 
     use super::*;
 
@@ -65,8 +65,6 @@ mod tests {
         for ((t, y), y_prime) in ts.iter().zip(ys).zip(ys_prime) {
             println!("({:.2}, {:.6}, {:.6})", t, y, y_prime);
         }
-
-        assert!(true);
     }
 
     #[test]
@@ -87,7 +85,5 @@ mod tests {
         for ((t, y), y_prime) in ts.iter().zip(ys).zip(ys_prime) {
             println!("({:.2}, {:.6}, {:.6})", t, y, y_prime);
         }
-
-        assert!(true);
     }
 }
@@ -1,14 +1,17 @@
 use std::fmt;
 
+use crate::math::core::LinearSpace;
+
+/// Point mass, 1D by default or e.g. `Particle<Vector<f64, 3>>` in space
 #[derive(Clone, Copy)]
-pub struct Particle {
+pub struct Particle<V: LinearSpace = f64> {
     mass: f64,
-    position: f64,
-    velocity: f64,
+    position: V,
+    velocity: V,
 }
 
-impl Particle {
-    pub fn new(m: f64, x: f64, v: f64) -> Self {
+impl<V: LinearSpace> Particle<V> {
+    pub fn new(m: f64, x: V, v: V) -> Self {
         Self {
             mass: m,
             position: x,
@@ -18,24 +21,24 @@ impl Particle {
     pub fn mass(&self) -> f64 {
         self.mass
     }
-    pub fn position(&self) -> f64 {
+    pub fn position(&self) -> V {
         self.position
     }
-    pub fn velocity(&self) -> f64 {
+    pub fn velocity(&self) -> V {
         self.velocity
     }
-    pub fn momentum(&self) -> f64 {
-        self.mass * self.velocity
+    pub fn momentum(&self) -> V {
+        self.velocity * self.mass
     }
-    pub fn set_position(&mut self, x: f64) {
+    pub fn set_position(&mut self, x: V) {
         self.position = x;
     }
-    pub fn set_velocity(&mut self, x: f64) {
+    pub fn set_velocity(&mut self, x: V) {
         self.velocity = x;
     }
 }
 
-impl fmt::Display for Particle {
+impl<V: LinearSpace> fmt::Display for Particle<V> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
@@ -31,14 +31,14 @@ pub fn plot_one(
             ))?
             .label(&labels[idx])
             .legend(move |(x, y)| {
-                PathElement::new(vec![(x, y), (x + 20, y)], &Palette99::pick(idx))
+                PathElement::new(vec![(x, y), (x + 20, y)], Palette99::pick(idx))
             });
     }
 
     chart
         .configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .draw()?;
 
     root.present()?;